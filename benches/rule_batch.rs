@@ -0,0 +1,118 @@
+//! Adds 1000 rules one request at a time and as a single `Batch`, to show
+//! what saving the round trip per rule is worth. Needs root, since the rules
+//! are added in a network namespace of their own.
+
+// Netlink only exists on Linux, like the handles benchmarked here.
+#[cfg(target_os = "linux")]
+mod rule_add {
+    use std::time::{Duration, Instant};
+
+    use criterion::{criterion_group, Criterion};
+    use nix::sched::{unshare, CloneFlags};
+    use rsln::{
+        handle::{batch::Batch, sock_handle::SocketHandle},
+        types::rule::Rule,
+    };
+
+    const RULES: u32 = 1000;
+    const TABLE: u32 = 100;
+
+    fn rules() -> Vec<Rule> {
+        (0..RULES)
+            .map(|i| Rule {
+                priority: Some(1000 + i),
+                table: TABLE,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn add_one_by_one(handle: &mut SocketHandle, rules: &[Rule]) {
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        for rule in rules {
+            handle
+                .handle_rule()
+                .handle(rule, libc::RTM_NEWRULE, flags)
+                .unwrap();
+        }
+    }
+
+    fn add_batched(handle: &mut SocketHandle, rules: &[Rule]) {
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL;
+        let mut batch = Batch::new();
+
+        for rule in rules {
+            handle
+                .handle_rule()
+                .handle_batched(&mut batch, rule, libc::RTM_NEWRULE, flags)
+                .unwrap();
+        }
+
+        assert!(handle.send_batch(batch).unwrap().iter().all(Result::is_ok));
+    }
+
+    fn delete_all(handle: &mut SocketHandle, rules: &[Rule]) {
+        let mut batch = Batch::new();
+
+        for rule in rules {
+            handle
+                .handle_rule()
+                .handle_batched(&mut batch, rule, libc::RTM_DELRULE, 0)
+                .unwrap();
+        }
+
+        assert!(handle.send_batch(batch).unwrap().iter().all(Result::is_ok));
+    }
+
+    fn bench_rule_add(c: &mut Criterion) {
+        if !nix::unistd::getuid().is_root() {
+            eprintln!("benchmark skipped, requires root");
+            return;
+        }
+        unshare(CloneFlags::CLONE_NEWNET).expect("unshare(CLONE_NEWNET)");
+
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let rules = rules();
+
+        let mut group = c.benchmark_group("rule_add_1000");
+
+        for (name, add) in [
+            (
+                "one_by_one",
+                add_one_by_one as fn(&mut SocketHandle, &[Rule]),
+            ),
+            ("batched", add_batched),
+        ] {
+            group.bench_function(name, |b| {
+                b.iter_custom(|iters| {
+                    let mut elapsed = Duration::ZERO;
+
+                    for _ in 0..iters {
+                        let start = Instant::now();
+                        add(&mut handle, &rules);
+                        elapsed += start.elapsed();
+
+                        delete_all(&mut handle, &rules);
+                    }
+
+                    elapsed
+                })
+            });
+        }
+
+        group.finish();
+    }
+
+    criterion_group! {
+        name = benches;
+        config = Criterion::default().sample_size(10);
+        targets = bench_rule_add
+    }
+}
+
+#[cfg(target_os = "linux")]
+criterion::criterion_main!(rule_add::benches);
+
+#[cfg(not(target_os = "linux"))]
+fn main() {}
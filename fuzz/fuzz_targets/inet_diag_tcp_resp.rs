@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rsln::types::sock_diag::InetDiagTcpResp;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = InetDiagTcpResp::try_from(data);
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rsln::types::message::RouteAttrs;
+
+fuzz_target!(|data: &[u8]| {
+    for attr in RouteAttrs::from(data) {
+        let _ = attr.payload.to_string();
+        let _ = attr.payload.to_u32();
+        let _ = attr.payload.as_u64();
+        let _ = RouteAttrs::from(attr.payload.as_slice());
+    }
+});
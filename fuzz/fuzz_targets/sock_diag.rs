@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rsln::types::sock_diag::SockDiag;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SockDiag::deserialize(data);
+});
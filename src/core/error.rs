@@ -0,0 +1,216 @@
+use std::{borrow::Cow, fmt, io};
+
+use anyhow::Result;
+use nix::errno::Errno;
+
+use crate::types::nexthop::{RTM_DELNEXTHOP, RTM_GETNEXTHOP, RTM_NEWNEXTHOP};
+
+use super::message::NlFlags;
+
+/// A request the kernel answered with an error. It names the request, so
+/// one failed message among many, e.g. of a batch, can be told apart:
+///
+/// `RTM_NEWROUTE (dst 10.0.0.0/24) failed: EEXIST (File exists)`
+///
+/// The `std::io::Error` of the errno is its `source`.
+#[derive(Debug)]
+pub struct RequestError {
+    /// The `nlmsg_type` of the failed request.
+    pub nlmsg_type: u16,
+    pub flags: NlFlags,
+    /// The netlink protocol of the socket, which decides how `nlmsg_type`
+    /// is named. `None` when the transport does not know it.
+    pub protocol: Option<i32>,
+    /// A FNV-1a hash of the payload of the failed request, to correlate it
+    /// with a logged request. `None` when the kernel did not echo the
+    /// payload, e.g. with `NETLINK_CAP_ACK`.
+    pub digest: Option<u64>,
+    /// What the request was about, e.g. the destination of a route, added
+    /// by the handle that sent it with `RequestContext::request_context`.
+    pub context: Option<String>,
+    /// The message of an extended ack.
+    pub ext_msg: Option<String>,
+    source: io::Error,
+}
+
+impl RequestError {
+    pub fn new(nlmsg_type: u16, flags: NlFlags, errno: i32) -> Self {
+        Self {
+            nlmsg_type,
+            flags,
+            protocol: None,
+            digest: None,
+            context: None,
+            ext_msg: None,
+            source: io::Error::from_raw_os_error(errno),
+        }
+    }
+
+    /// The positive errno the kernel failed the request with.
+    pub fn errno(&self) -> i32 {
+        self.source.raw_os_error().unwrap_or_default()
+    }
+
+    /// The name of the request type, e.g. `RTM_NEWRULE`.
+    pub fn type_name(&self) -> Cow<'static, str> {
+        match self.protocol {
+            Some(libc::NETLINK_ROUTE) => rtm_name(self.nlmsg_type),
+            _ => None,
+        }
+        .map_or_else(
+            || Cow::Owned(format!("nlmsg_type {}", self.nlmsg_type)),
+            Cow::Borrowed,
+        )
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.type_name())?;
+
+        if let Some(context) = &self.context {
+            write!(f, " ({})", context)?;
+        }
+
+        let errno = Errno::from_raw(self.errno());
+        write!(f, " failed: {:?} ({})", errno, errno.desc())?;
+
+        if let Some(ext_msg) = &self.ext_msg {
+            write!(f, ": {}", ext_msg)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RequestError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Lets handle methods describe the request they sent, e.g. with the
+/// priority of a rule, when the kernel rejects it.
+pub trait RequestContext<T> {
+    /// Adds `f()` to a `RequestError`, or any other error, as context.
+    fn request_context<C, F>(self, f: F) -> Result<T>
+    where
+        C: fmt::Display + Send + Sync + 'static,
+        F: FnOnce() -> C;
+}
+
+impl<T> RequestContext<T> for Result<T> {
+    fn request_context<C, F>(self, f: F) -> Result<T>
+    where
+        C: fmt::Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.map_err(|mut e| match e.downcast_mut::<RequestError>() {
+            Some(req) => {
+                req.context = Some(f().to_string());
+                e
+            }
+            None => e.context(f()),
+        })
+    }
+}
+
+/// The errno of `e` if the kernel rejected a request with it, e.g. to treat
+/// an `EEXIST` as success.
+pub fn errno(e: &anyhow::Error) -> Option<i32> {
+    e.downcast_ref::<RequestError>().map(RequestError::errno)
+}
+
+/// FNV-1a, which is good enough to recognize a payload in a log.
+pub(crate) fn digest(buf: &[u8]) -> u64 {
+    buf.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn rtm_name(nlmsg_type: u16) -> Option<&'static str> {
+    Some(match nlmsg_type {
+        libc::RTM_NEWLINK => "RTM_NEWLINK",
+        libc::RTM_DELLINK => "RTM_DELLINK",
+        libc::RTM_GETLINK => "RTM_GETLINK",
+        libc::RTM_SETLINK => "RTM_SETLINK",
+        libc::RTM_NEWADDR => "RTM_NEWADDR",
+        libc::RTM_DELADDR => "RTM_DELADDR",
+        libc::RTM_GETADDR => "RTM_GETADDR",
+        libc::RTM_NEWROUTE => "RTM_NEWROUTE",
+        libc::RTM_DELROUTE => "RTM_DELROUTE",
+        libc::RTM_GETROUTE => "RTM_GETROUTE",
+        libc::RTM_NEWNEIGH => "RTM_NEWNEIGH",
+        libc::RTM_DELNEIGH => "RTM_DELNEIGH",
+        libc::RTM_GETNEIGH => "RTM_GETNEIGH",
+        libc::RTM_NEWRULE => "RTM_NEWRULE",
+        libc::RTM_DELRULE => "RTM_DELRULE",
+        libc::RTM_GETRULE => "RTM_GETRULE",
+        libc::RTM_NEWQDISC => "RTM_NEWQDISC",
+        libc::RTM_DELQDISC => "RTM_DELQDISC",
+        libc::RTM_GETQDISC => "RTM_GETQDISC",
+        libc::RTM_NEWTCLASS => "RTM_NEWTCLASS",
+        libc::RTM_DELTCLASS => "RTM_DELTCLASS",
+        libc::RTM_GETTCLASS => "RTM_GETTCLASS",
+        libc::RTM_NEWTFILTER => "RTM_NEWTFILTER",
+        libc::RTM_DELTFILTER => "RTM_DELTFILTER",
+        libc::RTM_GETTFILTER => "RTM_GETTFILTER",
+        libc::RTM_NEWACTION => "RTM_NEWACTION",
+        libc::RTM_DELACTION => "RTM_DELACTION",
+        libc::RTM_GETACTION => "RTM_GETACTION",
+        libc::RTM_NEWNEIGHTBL => "RTM_NEWNEIGHTBL",
+        libc::RTM_GETNEIGHTBL => "RTM_GETNEIGHTBL",
+        libc::RTM_SETNEIGHTBL => "RTM_SETNEIGHTBL",
+        libc::RTM_NEWNSID => "RTM_NEWNSID",
+        libc::RTM_DELNSID => "RTM_DELNSID",
+        libc::RTM_GETNSID => "RTM_GETNSID",
+        RTM_NEWNEXTHOP => "RTM_NEWNEXTHOP",
+        RTM_DELNEXTHOP => "RTM_DELNEXTHOP",
+        RTM_GETNEXTHOP => "RTM_GETNEXTHOP",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_error_display() {
+        let mut err = RequestError::new(libc::RTM_NEWRULE, NlFlags::CREATE, libc::EEXIST);
+        assert_eq!(
+            err.to_string(),
+            "nlmsg_type 32 failed: EEXIST (File exists)"
+        );
+
+        err.protocol = Some(libc::NETLINK_ROUTE);
+        let err: Result<()> = Err(err.into());
+        let err = err
+            .request_context(|| "priority 100, table 250")
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "RTM_NEWRULE (priority 100, table 250) failed: EEXIST (File exists)"
+        );
+
+        let err = err.downcast::<RequestError>().unwrap();
+        assert_eq!(err.errno(), libc::EEXIST);
+        assert_eq!(
+            std::error::Error::source(&err)
+                .unwrap()
+                .downcast_ref::<io::Error>()
+                .unwrap()
+                .raw_os_error(),
+            Some(libc::EEXIST)
+        );
+    }
+
+    #[test]
+    fn test_request_context_other_errors() {
+        let err: Result<()> = Err(anyhow::anyhow!("truncated"));
+        let err = err.request_context(|| "dst 10.0.0.0/24").unwrap_err();
+
+        assert_eq!(format!("{:#}", err), "dst 10.0.0.0/24: truncated");
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::VecDeque, time::Duration};
+
+use anyhow::{anyhow, Result};
+
+use super::{
+    message::{Message, Messages},
+    socket::{recv_error, recv_retry, NetlinkTransport, Socket, MAX_RECV_BUF_SIZE, RECV_BUF_SIZE},
+};
+
+const PID_KERNEL: u32 = 0;
+
+/// An item yielded by `EventStream`.
+pub enum Event {
+    Message(Message),
+    /// The socket overran its receive buffer (`ENOBUFS`) and the kernel
+    /// dropped notifications, so any state built from earlier events may be
+    /// stale and has to be rebuilt from a fresh dump of the subsystem.
+    Resync,
+}
+
+type ResyncHook = Box<dyn FnMut() -> Result<()> + Send>;
+
+/// A netlink socket joined to one or more multicast groups that yields the
+/// notifications the kernel broadcasts to them, one message at a time.
+pub struct EventStream<S: NetlinkTransport = Socket> {
+    socket: S,
+    buf: Vec<u8>,
+    pending: VecDeque<Message>,
+    /// Yields an error instead of growing `buf` beyond this size.
+    max_recv_buf_size: usize,
+    max_sock_buf_size: Option<usize>,
+    on_resync: Option<ResyncHook>,
+}
+
+impl EventStream {
+    pub fn new(proto: i32, groups: &[u32]) -> Result<Self> {
+        let socket = Socket::new(proto, 0, 0)?;
+
+        for group in groups {
+            socket.add_membership(*group)?;
+        }
+
+        Ok(Self::with_transport(socket))
+    }
+
+    pub fn set_recv_timeout(&self, timeout: Duration) -> Result<()> {
+        Ok(self.socket.set_recv_timeout(timeout)?)
+    }
+}
+
+impl<S: NetlinkTransport> EventStream<S> {
+    /// Wraps a transport that already joined its groups, e.g. a
+    /// `MockTransport` in tests.
+    pub fn with_transport(socket: S) -> Self {
+        Self {
+            socket,
+            buf: vec![0; RECV_BUF_SIZE],
+            pending: VecDeque::new(),
+            max_recv_buf_size: MAX_RECV_BUF_SIZE,
+            max_sock_buf_size: None,
+            on_resync: None,
+        }
+    }
+
+    /// Doubles the socket receive buffer with `SO_RCVBUFFORCE` every time an
+    /// overrun is detected, up to `max` bytes. Forcing the size past
+    /// `net.core.rmem_max` requires `CAP_NET_ADMIN`.
+    pub fn grow_recv_buf_on_overrun(mut self, max: usize) -> Self {
+        self.max_sock_buf_size = Some(max);
+        self
+    }
+
+    /// Sets the size the userspace buffer may grow to for a notification
+    /// that does not fit, `MAX_RECV_BUF_SIZE` by default. A larger one is
+    /// yielded as an error.
+    pub fn max_recv_buf_size(mut self, max: usize) -> Self {
+        self.max_recv_buf_size = max;
+        self
+    }
+
+    /// Registers `hook` to be called on every overrun before `Event::Resync`
+    /// is yielded, e.g. to trigger a full re-dump of the subsystem. An error
+    /// returned by the hook is yielded in place of `Event::Resync`.
+    pub fn on_resync<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut() -> Result<()> + Send + 'static,
+    {
+        self.on_resync = Some(Box::new(hook));
+        self
+    }
+
+    fn resync(&mut self) -> Result<Event> {
+        self.pending.clear();
+
+        if let Some(max) = self.max_sock_buf_size {
+            let size = self.socket.recv_buf_size()?;
+            if size < max {
+                self.socket.set_recv_buf_size_force((size * 2).min(max))?;
+            }
+        }
+
+        if let Some(hook) = self.on_resync.as_mut() {
+            hook()?;
+        }
+
+        Ok(Event::Resync)
+    }
+}
+
+impl<S: NetlinkTransport> Iterator for EventStream<S> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(msg) = self.pending.pop_front() {
+                return Some(Ok(Event::Message(msg)));
+            }
+
+            match recv_retry(&self.socket, &mut self.buf, None) {
+                Ok((len, _)) if len > self.buf.len() => {
+                    if len > self.max_recv_buf_size {
+                        return Some(Err(anyhow!(
+                            "netlink message of {} bytes exceeds the receive buffer limit of {} bytes",
+                            len,
+                            self.max_recv_buf_size
+                        )));
+                    }
+                    self.buf.resize(len, 0);
+                }
+                Ok((len, PID_KERNEL)) => self.pending.extend(Messages::from(&self.buf[..len])),
+                Ok(_) => continue,
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => return Some(self.resync()),
+                Err(e) => return Some(Err(recv_error(e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::core::mock::MockTransport;
+
+    use super::*;
+
+    #[test]
+    fn test_event_stream_timeout() {
+        let mut events = EventStream::new(libc::NETLINK_ROUTE, &[libc::RTNLGRP_LINK]).unwrap();
+        events.set_recv_timeout(Duration::from_millis(10)).unwrap();
+
+        let err = events.next().unwrap().err().unwrap();
+        let err = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn test_event_stream_resync() {
+        let mock = MockTransport::default();
+        let link = Message::new(libc::RTM_NEWLINK, 0).serialize().unwrap();
+
+        mock.push_response_verbatim(&link);
+        mock.push_error(libc::ENOBUFS);
+        mock.push_response_verbatim(&link);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let mut events = EventStream::with_transport(mock)
+            .grow_recv_buf_on_overrun(1 << 20)
+            .on_resync(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        let size = events.socket.recv_buf_size().unwrap();
+
+        assert!(matches!(events.next(), Some(Ok(Event::Message(_)))));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        assert!(matches!(events.next(), Some(Ok(Event::Resync))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(events.socket.recv_buf_size().unwrap(), size * 2);
+
+        // The stream keeps yielding the notifications after the overrun.
+        assert!(matches!(events.next(), Some(Ok(Event::Message(_)))));
+    }
+
+    #[test]
+    fn test_event_stream_grows_recv_buf() {
+        let mock = MockTransport::default();
+        let mut link = Message::new(libc::RTM_NEWLINK, 0);
+        link.add(&[0; RECV_BUF_SIZE]);
+        let link = link.serialize().unwrap();
+
+        mock.push_response_verbatim(&link);
+        let mut events = EventStream::with_transport(mock);
+        assert!(matches!(events.next(), Some(Ok(Event::Message(_)))));
+
+        // The buffer only grows up to the cap.
+        let mock = MockTransport::default();
+        mock.push_response_verbatim(&link);
+        let mut events = EventStream::with_transport(mock).max_recv_buf_size(RECV_BUF_SIZE);
+        assert!(events.next().unwrap().is_err());
+    }
+}
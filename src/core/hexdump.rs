@@ -0,0 +1,42 @@
+use std::fmt;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Formats a buffer as offset-prefixed lines of 16 hex bytes, the way
+/// netlink messages are usually compared against `nlmon` captures. The
+/// formatting is lazy, so wrapping a buffer costs nothing unless it is
+/// actually displayed.
+pub struct HexDump<'a>(pub &'a [u8]);
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.0.chunks(BYTES_PER_LINE).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{:04x}:", i * BYTES_PER_LINE)?;
+            for b in line {
+                write!(f, " {:02x}", b)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hexdump() {
+        let buf: Vec<u8> = (0..20).collect();
+
+        assert_eq!(
+            HexDump(&buf).to_string(),
+            "0000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n0010: 10 11 12 13"
+        );
+        assert_eq!(HexDump(&[]).to_string(), "");
+    }
+}
@@ -4,7 +4,8 @@ use std::{
     vec,
 };
 
-use anyhow::{bail, Ok, Result};
+use anyhow::{anyhow, bail, Ok, Result};
+use bitflags::bitflags;
 use libc::{NLM_F_MULTI, NLM_F_REQUEST};
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +13,38 @@ use crate::align_of;
 
 const NLMSG_ALIGNTO: usize = 0x4;
 const NLMSG_HDRLEN: usize = 0x10;
+const RTA_ALIGNTO: usize = 0x4;
+const RTA_HDRLEN: usize = 0x4;
+
+bitflags! {
+    /// The `nlmsg_flags` of a request. Some values are shared between `GET`
+    /// and `NEW` requests, e.g. `ROOT` and `REPLACE`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct NlFlags: u16 {
+        const REQUEST = libc::NLM_F_REQUEST as u16;
+        const MULTI = libc::NLM_F_MULTI as u16;
+        const ACK = libc::NLM_F_ACK as u16;
+        const ECHO = libc::NLM_F_ECHO as u16;
+        const DUMP_INTR = 0x10;
+        const DUMP_FILTERED = 0x20;
+
+        const ROOT = libc::NLM_F_ROOT as u16;
+        const MATCH = libc::NLM_F_MATCH as u16;
+        const ATOMIC = libc::NLM_F_ATOMIC as u16;
+        const DUMP = libc::NLM_F_DUMP as u16;
+
+        const REPLACE = libc::NLM_F_REPLACE as u16;
+        const EXCL = libc::NLM_F_EXCL as u16;
+        const CREATE = libc::NLM_F_CREATE as u16;
+        const APPEND = libc::NLM_F_APPEND as u16;
+    }
+}
+
+impl From<i32> for NlFlags {
+    fn from(flags: i32) -> Self {
+        Self::from_bits_retain(flags as u16)
+    }
+}
 
 pub struct Messages(Vec<Message>);
 
@@ -20,9 +53,19 @@ impl From<&[u8]> for Messages {
         let mut messages = Vec::new();
 
         while buf.len() >= NLMSG_HDRLEN {
-            let message = Message::from(buf);
-            let len = align_of(message.header.nlmsg_len as usize, NLMSG_ALIGNTO);
-            messages.push(message);
+            // Like the kernel's `nlmsg_ok`, stop at the first message whose
+            // length does not fit, instead of reading past it.
+            let nlmsg_len = u32::from_ne_bytes(buf[..4].try_into().unwrap()) as usize;
+            if nlmsg_len < NLMSG_HDRLEN || nlmsg_len > buf.len() {
+                break;
+            }
+
+            let Some(msg) = Message::try_from(buf).ok() else {
+                break;
+            };
+            messages.push(msg);
+
+            let len = align_of(nlmsg_len, NLMSG_ALIGNTO).min(buf.len());
             buf = &buf[len..];
         }
 
@@ -60,14 +103,25 @@ pub struct Message {
     pub payload: Option<Vec<u8>>,
 }
 
-impl From<&[u8]> for Message {
-    fn from(buf: &[u8]) -> Self {
-        let header: Header = bincode::deserialize(buf).expect("Failed to deserialize header");
-        let data = buf[NLMSG_HDRLEN..header.nlmsg_len as usize].to_vec();
-        Self {
+impl TryFrom<&[u8]> for Message {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let header: Header = bincode::deserialize(buf)?;
+        let data = buf
+            .get(NLMSG_HDRLEN..header.nlmsg_len as usize)
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid message length {} for a {} byte buffer",
+                    header.nlmsg_len,
+                    buf.len()
+                )
+            })?;
+
+        Ok(Self {
             header,
-            payload: Some(data),
-        }
+            payload: Some(data.to_vec()),
+        })
     }
 }
 
@@ -80,22 +134,34 @@ impl Message {
     }
 
     pub fn serialize(&self) -> Result<Vec<u8>> {
-        let estimated_size = match &self.payload {
-            Some(payload) => NLMSG_HDRLEN + payload.len(),
-            None => NLMSG_HDRLEN,
-        };
+        let mut buf = Vec::with_capacity(align_of(self.len(), NLMSG_ALIGNTO));
+        self.serialize_into(&mut buf);
 
-        let mut buf = Vec::with_capacity(estimated_size);
-        buf.extend(bincode::serialize(&self.header)?);
+        Ok(buf)
+    }
+
+    /// Appends the message to `buf`, so a single buffer can be reused or
+    /// filled with several messages without allocating for each of them.
+    /// The message is padded to `NLMSG_ALIGNTO`, so that the next one starts
+    /// where the kernel looks for it.
+    pub fn serialize_into(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.reserve(align_of(self.len(), NLMSG_ALIGNTO));
+        buf.extend_from_slice(&(self.len() as u32).to_ne_bytes());
+        buf.extend_from_slice(&self.header.nlmsg_type.to_ne_bytes());
+        buf.extend_from_slice(&self.header.nlmsg_flags.to_ne_bytes());
+        buf.extend_from_slice(&self.header.nlmsg_seq.to_ne_bytes());
+        buf.extend_from_slice(&self.header.nlmsg_pid.to_ne_bytes());
 
         if let Some(payload) = &self.payload {
-            buf.extend(payload);
+            buf.extend_from_slice(payload);
         }
 
-        let len = buf.len() as u16;
-        buf[..2].copy_from_slice(&len.to_ne_bytes());
+        buf.resize(start + align_of(self.len(), NLMSG_ALIGNTO), 0);
+    }
 
-        Ok(buf)
+    fn len(&self) -> usize {
+        NLMSG_HDRLEN + self.payload.as_ref().map_or(0, Vec::len)
     }
 
     pub fn add(&mut self, data: &[u8]) {
@@ -104,17 +170,75 @@ impl Message {
         payload.extend(data);
     }
 
+    /// Sets the `nlmsg_pid` of the header to the port id of the sender.
+    pub fn set_pid(&mut self, pid: u32) {
+        self.header.nlmsg_pid = pid;
+    }
+
     pub fn verify_header(&self, seq: u32, pid: u32) -> Result<()> {
         self.header.verify(seq, pid)
     }
 
+    /// Appends the attribute `rta_type` with the attributes `f` adds as its
+    /// children, e.g. `IFLA_INFO_DATA` inside `IFLA_LINKINFO`. The header is
+    /// reserved before the children are written into the message and its
+    /// length patched afterwards, so nested attributes need neither a
+    /// buffer of their own nor a length computed up front. If `f` fails,
+    /// the message is left half written and should be dropped.
+    pub fn nest<F>(&mut self, rta_type: u16, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let start = self.payload.as_ref().map_or(0, Vec::len);
+        self.add(&[0; RTA_HDRLEN]);
+
+        f(self)?;
+
+        let payload = self.payload.get_or_insert_with(Vec::new);
+        let len = payload.len() - start;
+        if len > u16::MAX as usize {
+            bail!("nested attribute {} too long: {} bytes", rta_type, len);
+        }
+
+        payload[start..start + 2].copy_from_slice(&(len as u16).to_ne_bytes());
+        payload[start + 2..start + 4].copy_from_slice(&rta_type.to_ne_bytes());
+
+        self.add(&vec![0; align_of(len, RTA_ALIGNTO) - len]);
+
+        Ok(())
+    }
+
     pub fn check_last_message(&self) -> bool {
         self.header.nlmsg_flags & NLM_F_MULTI as u16 == 0
     }
 }
 
+/// A response message with its header, for callers that need the type, the
+/// flags or the sequence number of a response and not only its payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NlMsg {
+    pub header: Header,
+    pub payload: Vec<u8>,
+}
+
+impl NlMsg {
+    /// Returns the payload if the message has the type `nlmsg_type`, so a
+    /// message of another type fails instead of being parsed as garbage.
+    pub fn expect_type(&self, nlmsg_type: u16) -> Result<&[u8]> {
+        if self.header.nlmsg_type != nlmsg_type {
+            bail!(
+                "unexpected netlink message type: {}, expected: {}",
+                self.header.nlmsg_type,
+                nlmsg_type
+            );
+        }
+
+        Ok(&self.payload)
+    }
+}
+
 #[repr(C)]
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Header {
     pub nlmsg_len: u32,
     pub nlmsg_type: u16,
@@ -153,6 +277,57 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_nest_matches_nested_route_attr() {
+        let mut data = RouteAttr::new(libc::IFLA_INFO_DATA, &[]);
+        data.add(1, &10u16.to_ne_bytes());
+        data.add(5, &[0x81, 0x00]);
+
+        let mut link_info = RouteAttr::new(libc::IFLA_LINKINFO, &[]);
+        link_info.add(libc::IFLA_INFO_KIND, b"vlan");
+        link_info.add_attribute(Box::new(data));
+
+        let mut expected = Message::new(libc::RTM_NEWLINK, 0);
+        expected.add(&link_info.serialize().unwrap());
+        expected.add(
+            &RouteAttr::new(libc::IFLA_MTU, &1500u32.to_ne_bytes())
+                .serialize()
+                .unwrap(),
+        );
+
+        let mut req = Message::new(libc::RTM_NEWLINK, 0);
+        req.nest(libc::IFLA_LINKINFO, |m| {
+            m.add(&RouteAttr::new(libc::IFLA_INFO_KIND, b"vlan").serialize()?);
+            m.nest(libc::IFLA_INFO_DATA, |m| {
+                m.add(&RouteAttr::new(1, &10u16.to_ne_bytes()).serialize()?);
+                m.add(&RouteAttr::new(5, &[0x81, 0x00]).serialize()?);
+                Ok(())
+            })
+        })
+        .unwrap();
+        req.add(
+            &RouteAttr::new(libc::IFLA_MTU, &1500u32.to_ne_bytes())
+                .serialize()
+                .unwrap(),
+        );
+
+        assert_eq!(req.serialize().unwrap(), expected.serialize().unwrap());
+        assert_eq!(req.header.nlmsg_len, expected.header.nlmsg_len);
+    }
+
+    #[test]
+    fn test_nest_pads_unaligned_children() {
+        let mut req = Message::new(0, 0);
+        req.nest(libc::IFLA_LINKINFO, |m| {
+            m.add(&[5, 0, 1, 0, 1]);
+            Ok(())
+        })
+        .unwrap();
+
+        // rta_len covers the header and the 5 byte child, not the padding.
+        assert_eq!(req.payload.unwrap(), [9, 0, 18, 0, 5, 0, 1, 0, 1, 0, 0, 0]);
+    }
+
     #[test]
     fn test_messages_from_bytes() {
         let buf: [u8; 32] = [
@@ -176,6 +351,20 @@ mod tests {
         assert_eq!(messages.0[1].header.nlmsg_seq, 2);
     }
 
+    #[test]
+    fn test_message_try_from_truncated() {
+        let buf: [u8; 16] = [
+            0x14, 0x00, 0x00, 0x00, // nlmsg_len = 20
+            0x00, 0x10, // nlmsg_type = 16
+            0x01, 0x00, // nlmsg_flags = 1
+            0x01, 0x00, 0x00, 0x00, // nlmsg_seq = 1
+            0x01, 0x00, 0x00, 0x00, // nlmsg_pid = 1
+        ];
+
+        assert!(Message::try_from(&buf[..]).is_err());
+        assert!(Message::try_from(&buf[..4]).is_err());
+    }
+
     #[test]
     fn test_netlink_request() {
         let mut req = Message::new(0, 0);
@@ -188,4 +377,47 @@ mod tests {
         assert_eq!(buf.len(), 24);
         assert_eq!(req.header.nlmsg_len, 24);
     }
+
+    #[test]
+    fn test_serialize_into_reuses_buf() {
+        let mut req = Message::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+        req.header.nlmsg_seq = 7;
+
+        let name = RouteAttr::new(libc::IFLA_IFNAME, "lo".as_bytes());
+        req.add(&name.serialize().unwrap());
+
+        let mut buf = Vec::with_capacity(64);
+        let ptr = buf.as_ptr();
+
+        req.serialize_into(&mut buf);
+        req.serialize_into(&mut buf);
+
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(buf[..24], req.serialize().unwrap()[..]);
+        assert_eq!(buf[..24], buf[24..]);
+        assert_eq!(buf[..4], 24u32.to_ne_bytes());
+    }
+
+    #[test]
+    fn test_serialize_into_pads_odd_attribute() {
+        let mut req = Message::new(libc::RTM_NEWLINK, 0);
+        // An IFLA_MTU without the padding after its 1 byte payload.
+        req.add(&[0x05, 0x00, 0x04, 0x00, 0xff]);
+
+        let mut buf = vec![];
+        req.serialize_into(&mut buf);
+        req.serialize_into(&mut buf);
+
+        // nlmsg_len leaves out the padding, the next message starts after it.
+        assert_eq!(buf.len(), 2 * 24);
+        assert_eq!(buf[..4], 21u32.to_ne_bytes());
+        assert_eq!(buf[21..24], [0, 0, 0]);
+
+        let msgs = Messages::from(&buf[..]);
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(
+            msgs[1].payload.as_deref(),
+            Some(&[0x05, 0x00, 0x04, 0x00, 0xff][..])
+        );
+    }
 }
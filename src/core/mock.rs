@@ -0,0 +1,197 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    io::{Error, ErrorKind, Result},
+};
+
+use super::socket::{NetlinkTransport, RECV_BUF_SIZE};
+
+const NLMSG_HDRLEN: usize = 0x10;
+const NLMSG_ERROR: u16 = 2;
+
+/// An in-memory `NetlinkTransport` that records every request and replays
+/// canned response datagrams, so that handles can be exercised without root
+/// or a kernel.
+///
+/// Responses are usually captured from a real socket, so the sequence number
+/// and port id of every message in a replayed datagram are rewritten to match
+/// the last request before it is handed out, unless it was queued with
+/// `push_response_verbatim`.
+pub struct MockTransport {
+    pid: u32,
+    sent: RefCell<Vec<Vec<u8>>>,
+    responses: RefCell<VecDeque<Response>>,
+    recv_buf_size: Cell<usize>,
+}
+
+enum Response {
+    Datagram(Vec<u8>, bool),
+    Error(i32),
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl MockTransport {
+    pub fn new(pid: u32) -> Self {
+        Self {
+            pid,
+            sent: RefCell::new(Vec::new()),
+            responses: RefCell::new(VecDeque::new()),
+            recv_buf_size: Cell::new(RECV_BUF_SIZE),
+        }
+    }
+
+    /// Queues a datagram to be returned by a later `recv`.
+    pub fn push_response(&self, buf: &[u8]) {
+        self.responses
+            .borrow_mut()
+            .push_back(Response::Datagram(buf.to_vec(), true));
+    }
+
+    /// Queues a datagram that is returned as is, e.g. the acks of a batch
+    /// whose sequence numbers differ from the one of the last request.
+    pub fn push_response_verbatim(&self, buf: &[u8]) {
+        self.responses
+            .borrow_mut()
+            .push_back(Response::Datagram(buf.to_vec(), false));
+    }
+
+    /// Queues an error to be returned by a later `recv` instead of a
+    /// datagram, e.g. `ENOBUFS` after the receive buffer overran.
+    pub fn push_error(&self, errno: i32) {
+        self.responses
+            .borrow_mut()
+            .push_back(Response::Error(errno));
+    }
+
+    /// Queues a successful `NLMSG_ERROR` acknowledgement.
+    pub fn push_ack(&self) {
+        let len = NLMSG_HDRLEN + 4 + NLMSG_HDRLEN;
+        let mut buf = vec![0; len];
+        buf[..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        buf[4..6].copy_from_slice(&NLMSG_ERROR.to_ne_bytes());
+        self.push_response(&buf);
+    }
+
+    /// Returns the requests sent so far, oldest first.
+    pub fn sent(&self) -> Vec<Vec<u8>> {
+        self.sent.borrow().clone()
+    }
+
+    fn rewrite(&self, response: &mut [u8]) {
+        let seq = match self.sent.borrow().last() {
+            Some(req) if req.len() >= NLMSG_HDRLEN => req[8..12].to_vec(),
+            _ => vec![0; 4],
+        };
+
+        let mut offset = 0;
+        while offset + NLMSG_HDRLEN <= response.len() {
+            let len = u32::from_ne_bytes(response[offset..offset + 4].try_into().unwrap()) as usize;
+            // A zeroed or truncated canned response is replayed as it is.
+            if len < NLMSG_HDRLEN || offset + len > response.len() {
+                break;
+            }
+
+            response[offset + 8..offset + 12].copy_from_slice(&seq);
+            response[offset + 12..offset + 16].copy_from_slice(&self.pid.to_ne_bytes());
+            offset += (len + 3) & !3;
+        }
+    }
+}
+
+impl NetlinkTransport for MockTransport {
+    fn send(&self, buf: &[u8]) -> Result<()> {
+        self.sent.borrow_mut().push(buf.to_vec());
+        Ok(())
+    }
+
+    fn recv(&self, buf: &mut [u8]) -> Result<(usize, u32)> {
+        let (mut response, rewrite) = match self.responses.borrow_mut().pop_front() {
+            Some(Response::Datagram(response, rewrite)) => (response, rewrite),
+            Some(Response::Error(errno)) => return Err(Error::from_raw_os_error(errno)),
+            None => return Err(Error::from(ErrorKind::WouldBlock)),
+        };
+
+        if rewrite {
+            self.rewrite(&mut response);
+        }
+
+        if response.len() > buf.len() {
+            let len = response.len();
+            self.responses
+                .borrow_mut()
+                .push_front(Response::Datagram(response, false));
+            return Ok((len, 0));
+        }
+
+        buf[..response.len()].copy_from_slice(&response);
+
+        Ok((response.len(), 0))
+    }
+
+    fn pid(&self) -> Result<u32> {
+        Ok(self.pid)
+    }
+
+    fn recv_buf_size(&self) -> Result<usize> {
+        Ok(self.recv_buf_size.get())
+    }
+
+    fn set_recv_buf_size_force(&self, size: usize) -> Result<()> {
+        self.recv_buf_size.set(size);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::message::Messages;
+
+    use super::*;
+
+    #[test]
+    fn test_mock_transport_replay() {
+        let mock = MockTransport::new(42);
+        mock.push_ack();
+
+        let mut req = vec![0; NLMSG_HDRLEN];
+        req[8..12].copy_from_slice(&7u32.to_ne_bytes());
+        mock.send(&req).unwrap();
+
+        let mut buf = [0; 64];
+        let (len, from) = mock.recv(&mut buf).unwrap();
+        assert_eq!(from, 0);
+
+        let msgs = Messages::from(&buf[..len]);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].header.nlmsg_type, NLMSG_ERROR);
+        assert!(msgs[0].verify_header(7, 42).is_ok());
+
+        assert_eq!(mock.sent(), vec![req]);
+        assert_eq!(
+            mock.recv(&mut buf).err().unwrap().kind(),
+            ErrorKind::WouldBlock
+        );
+    }
+
+    #[test]
+    fn test_mock_transport_replays_malformed_response() {
+        let mock = MockTransport::new(42);
+        mock.send(&[0; NLMSG_HDRLEN]).unwrap();
+
+        // nlmsg_len = 0, and a header claiming more than is there.
+        let mut truncated = vec![0; NLMSG_HDRLEN];
+        truncated[..4].copy_from_slice(&64u32.to_ne_bytes());
+        for response in [vec![0; NLMSG_HDRLEN], truncated] {
+            mock.push_response(&response);
+
+            let mut buf = [0; 64];
+            let (len, _) = mock.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], response.as_slice());
+        }
+    }
+}
@@ -1,2 +1,6 @@
+pub mod error;
+pub mod event;
+pub mod hexdump;
 pub mod message;
+pub mod mock;
 pub mod socket;
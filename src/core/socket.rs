@@ -1,16 +1,135 @@
 use std::{
-    io::{Error, Result},
-    mem::{size_of, zeroed},
-    os::fd::RawFd,
+    io::{Error, ErrorKind, Result},
+    mem::{self, size_of, zeroed},
+    os::fd::{AsRawFd, IntoRawFd, RawFd},
+    thread,
+    time::Duration,
 };
 
-use libc::{c_void, size_t, sockaddr, sockaddr_nl, socklen_t, AF_NETLINK, SOCK_CLOEXEC, SOCK_RAW};
+use libc::{
+    c_void, size_t, sockaddr, sockaddr_nl, socklen_t, timeval, AF_NETLINK, SOCK_CLOEXEC, SOCK_RAW,
+};
+
+use thiserror::Error;
 
 use super::message::Messages;
 
-const RECV_BUF_SIZE: usize = 65536;
+pub const RECV_BUF_SIZE: usize = 65536;
+
+/// The default upper bound for the receive buffer, which grows whenever a
+/// datagram does not fit.
+pub const MAX_RECV_BUF_SIZE: usize = 16 << 20;
+
+const NETLINK_EXT_ACK: i32 = 11;
+const NETLINK_GET_STRICT_CHK: i32 = 12;
+
+pub const RTMGRP_LINK: u32 = 0x1;
+pub const RTMGRP_NOTIFY: u32 = 0x2;
+pub const RTMGRP_NEIGH: u32 = 0x4;
+pub const RTMGRP_TC: u32 = 0x8;
+pub const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+pub const RTMGRP_IPV4_ROUTE: u32 = 0x40;
+pub const RTMGRP_IPV4_RULE: u32 = 0x80;
+pub const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+pub const RTMGRP_IPV6_ROUTE: u32 = 0x400;
+/// Only IPv4 rules have a legacy group, IPv6 rules are announced to the
+/// `RTNLGRP_IPV6_RULE` group, which is joined with `add_membership`.
+pub const RTMGRP_RULE: u32 = RTMGRP_IPV4_RULE;
+
+const RETRY_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_RETRIES: u32 = 3;
+
+#[derive(Error, Debug)]
+pub enum SocketError {
+    #[error("netlink receive buffer overrun (ENOBUFS), messages were dropped and the dump must be restarted")]
+    Overrun,
+}
 
-#[derive(Clone)]
+/// The operations `SocketHandle` needs from a netlink socket, implemented by
+/// `Socket` and by `MockTransport` so that handles can be tested without a
+/// kernel.
+pub trait NetlinkTransport {
+    fn send(&self, buf: &[u8]) -> Result<()>;
+
+    /// Receives one datagram into `buf` and returns its length together with
+    /// the port id of the sender. A datagram larger than `buf` is left queued
+    /// and only its length is returned, so the caller can retry with a buffer
+    /// that is large enough instead of reading a truncated message.
+    fn recv(&self, buf: &mut [u8]) -> Result<(usize, u32)>;
+
+    fn pid(&self) -> Result<u32>;
+
+    /// Asks the kernel to append extended ack TLVs, e.g. a human readable
+    /// error message, to its acknowledgements. Transports without a kernel
+    /// behind them can ignore this.
+    fn set_ext_ack(&self, _enable: bool) -> Result<()> {
+        Ok(())
+    }
+
+    /// The netlink protocol, e.g. `NETLINK_ROUTE`, by which the message types
+    /// in errors are named. Transports that do not know it return `None`.
+    fn protocol(&self) -> Option<i32> {
+        None
+    }
+
+    /// Returns the size of the receive buffer, as `SO_RCVBUF` reports it.
+    fn recv_buf_size(&self) -> Result<usize> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+
+    /// Sets the size of the receive buffer, ignoring `net.core.rmem_max`.
+    fn set_recv_buf_size_force(&self, _size: usize) -> Result<()> {
+        Err(Error::from(ErrorKind::Unsupported))
+    }
+}
+
+/// Sends `buf`, retrying when the call is interrupted by a signal.
+pub fn send_retry<T: NetlinkTransport + ?Sized>(transport: &T, buf: &[u8]) -> Result<()> {
+    loop {
+        match transport.send(buf) {
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            res => return res,
+        }
+    }
+}
+
+/// Receives the next batch of messages, retrying when the call is interrupted
+/// by a signal. When a receive timeout is configured, `EAGAIN` is retried up
+/// to `MAX_RETRIES` times with exponential backoff before it is returned.
+/// `ENOBUFS` is never retried since the dropped messages cannot be recovered.
+pub fn recv_retry<T: NetlinkTransport + ?Sized>(
+    transport: &T,
+    buf: &mut [u8],
+    timeout: Option<Duration>,
+) -> Result<(usize, u32)> {
+    let mut retries = 0;
+
+    loop {
+        match transport.recv(buf) {
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e)
+                if e.kind() == ErrorKind::WouldBlock
+                    && timeout.is_some()
+                    && retries < MAX_RETRIES =>
+            {
+                thread::sleep(RETRY_BACKOFF * 2u32.pow(retries));
+                retries += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Maps receive errors that callers need to tell apart to `SocketError`.
+pub fn recv_error(e: Error) -> anyhow::Error {
+    match e.raw_os_error() {
+        Some(libc::ENOBUFS) => SocketError::Overrun.into(),
+        _ => e.into(),
+    }
+}
+
+/// A bound netlink socket. The fd is owned and closed exactly once, when the
+/// socket is dropped or closed, unless it is released with `into_raw_fd`.
 pub struct Socket {
     fd: RawFd,
     sa: SocketAddr,
@@ -29,6 +148,43 @@ impl Socket {
         }
     }
 
+    /// Duplicates the fd with `F_DUPFD_CLOEXEC`. Both sockets refer to the
+    /// same kernel socket, so they share the port id and the receive queue,
+    /// but each fd is closed on its own.
+    pub fn try_clone(&self) -> Result<Self> {
+        match unsafe { libc::fcntl(self.fd, libc::F_DUPFD_CLOEXEC, 0) } {
+            -1 => Err(Error::last_os_error()),
+            fd => Ok(Self {
+                fd,
+                sa: self.sa.clone(),
+            }),
+        }
+    }
+
+    /// Clears or sets `FD_CLOEXEC`, which `new` and `try_clone` set, e.g. to
+    /// hand the socket to a program started with `exec`.
+    pub fn set_inheritable(&self, inheritable: bool) -> Result<()> {
+        let flags = match unsafe { libc::fcntl(self.fd, libc::F_GETFD) } {
+            -1 => return Err(Error::last_os_error()),
+            flags if inheritable => flags & !libc::FD_CLOEXEC,
+            flags => flags | libc::FD_CLOEXEC,
+        };
+
+        match unsafe { libc::fcntl(self.fd, libc::F_SETFD, flags) } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Closes the socket like dropping it does, but reports the error of
+    /// `close(2)`.
+    pub fn close(self) -> Result<()> {
+        match unsafe { libc::close(self.into_raw_fd()) } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
     fn bind(&self) -> Result<()> {
         let (addr, addr_len) = self.sa.as_raw();
 
@@ -64,6 +220,182 @@ impl Socket {
         }
     }
 
+    pub fn set_recv_timeout(&self, timeout: Duration) -> Result<()> {
+        let tv = timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &tv as *const _ as *const c_void,
+                size_of::<timeval>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `SO_RCVBUF`, which the kernel reports as twice the size that
+    /// was requested to account for its bookkeeping overhead.
+    pub fn recv_buf_size(&self) -> Result<usize> {
+        let mut size: i32 = 0;
+        let mut len = size_of::<i32>() as socklen_t;
+
+        match unsafe {
+            libc::getsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                &mut size as *mut _ as *mut c_void,
+                &mut len,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(size as usize),
+        }
+    }
+
+    /// Returns `SO_PROTOCOL`, the netlink protocol the socket was opened with.
+    pub fn protocol(&self) -> Result<i32> {
+        let mut proto: i32 = 0;
+        let mut len = size_of::<i32>() as socklen_t;
+
+        match unsafe {
+            libc::getsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_PROTOCOL,
+                &mut proto as *mut _ as *mut c_void,
+                &mut len,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(proto),
+        }
+    }
+
+    /// Sets `SO_RCVBUF`, capped by `net.core.rmem_max`.
+    pub fn set_recv_buf_size(&self, size: usize) -> Result<()> {
+        let size = size as i32;
+
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUF,
+                &size as *const _ as *const c_void,
+                size_of::<i32>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets `SO_SNDBUF`, capped by `net.core.wmem_max`.
+    pub fn set_send_buf_size(&self, size: usize) -> Result<()> {
+        let size = size as i32;
+
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_SNDBUF,
+                &size as *const _ as *const c_void,
+                size_of::<i32>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the receive buffer with `SO_RCVBUFFORCE`, which ignores
+    /// `net.core.rmem_max` but requires `CAP_NET_ADMIN`.
+    pub fn set_recv_buf_size_force(&self, size: usize) -> Result<()> {
+        let size = size as i32;
+
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUFFORCE,
+                &size as *const _ as *const c_void,
+                size_of::<i32>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_ext_ack(&self, enable: bool) -> Result<()> {
+        let enable = enable as i32;
+
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_NETLINK,
+                NETLINK_EXT_ACK,
+                &enable as *const _ as *const c_void,
+                size_of::<i32>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn set_strict_checking(&self, enable: bool) -> Result<()> {
+        let enable = enable as i32;
+
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_NETLINK,
+                NETLINK_GET_STRICT_CHK,
+                &enable as *const _ as *const c_void,
+                size_of::<i32>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Binds the socket again with `nl_groups` set to the `RTMGRP_*` bitmask
+    /// `groups`, keeping its port id. Groups past the first 32 can only be
+    /// joined with `add_membership`.
+    pub fn subscribe(&self, groups: u32) -> Result<()> {
+        let sa = SocketAddr::new(self.pid()?, groups);
+        let (addr, addr_len) = sa.as_raw();
+
+        match unsafe { libc::bind(self.fd, addr, addr_len) } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn add_membership(&self, group: u32) -> Result<()> {
+        match unsafe {
+            libc::setsockopt(
+                self.fd,
+                libc::SOL_NETLINK,
+                libc::NETLINK_ADD_MEMBERSHIP,
+                &group as *const _ as *const c_void,
+                size_of::<u32>() as socklen_t,
+            )
+        } {
+            -1 => Err(Error::last_os_error()),
+            _ => Ok(()),
+        }
+    }
+
     pub fn send(&self, buf: &[u8]) -> Result<()> {
         let (addr, addr_len) = self.sa.as_raw();
 
@@ -83,21 +415,37 @@ impl Socket {
     }
 
     pub fn recv(&self) -> Result<(Messages, sockaddr_nl)> {
+        let mut buf = vec![0; RECV_BUF_SIZE];
+        let (len, from) = self.recv_from(&mut buf)?;
+
+        Ok((Messages::from(&buf[..len]), from))
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, sockaddr_nl)> {
+        self.recv_from_flags(buf, 0)
+    }
+
+    /// Returns the length of the next queued datagram without consuming it.
+    fn peek_len(&self) -> Result<usize> {
+        let (len, _) = self.recv_from_flags(&mut [], libc::MSG_PEEK | libc::MSG_TRUNC)?;
+        Ok(len)
+    }
+
+    fn recv_from_flags(&self, buf: &mut [u8], flags: i32) -> Result<(usize, sockaddr_nl)> {
         let mut from: sockaddr_nl = unsafe { zeroed() };
-        let mut buf: [u8; RECV_BUF_SIZE] = [0; RECV_BUF_SIZE];
 
         match unsafe {
             libc::recvfrom(
                 self.fd,
                 buf.as_mut_ptr() as *mut c_void,
                 buf.len() as size_t,
-                0,
+                flags,
                 &mut from as *mut _ as *mut sockaddr,
                 &mut size_of::<sockaddr_nl>() as *mut _ as *mut socklen_t,
             )
         } {
             -1 => Err(Error::last_os_error()),
-            ret => Ok((Messages::from(&buf[..ret as usize]), from)),
+            ret => Ok((ret as usize, from)),
         }
     }
 
@@ -117,6 +465,56 @@ impl Socket {
     }
 }
 
+impl NetlinkTransport for Socket {
+    fn send(&self, buf: &[u8]) -> Result<()> {
+        Socket::send(self, buf)
+    }
+
+    fn recv(&self, buf: &mut [u8]) -> Result<(usize, u32)> {
+        let len = self.peek_len()?;
+        if len > buf.len() {
+            return Ok((len, 0));
+        }
+
+        let (len, from) = self.recv_from(buf)?;
+        Ok((len, from.nl_pid))
+    }
+
+    fn pid(&self) -> Result<u32> {
+        Socket::pid(self)
+    }
+
+    fn set_ext_ack(&self, enable: bool) -> Result<()> {
+        Socket::set_ext_ack(self, enable)
+    }
+
+    fn protocol(&self) -> Option<i32> {
+        Socket::protocol(self).ok()
+    }
+
+    fn recv_buf_size(&self) -> Result<usize> {
+        Socket::recv_buf_size(self)
+    }
+
+    fn set_recv_buf_size_force(&self, size: usize) -> Result<()> {
+        Socket::set_recv_buf_size_force(self, size)
+    }
+}
+
+impl AsRawFd for Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl IntoRawFd for Socket {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.fd;
+        mem::forget(self);
+        fd
+    }
+}
+
 impl Drop for Socket {
     fn drop(&mut self) {
         unsafe {
@@ -147,10 +545,113 @@ impl SocketAddr {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::RefCell;
+
     use libc::NETLINK_ROUTE;
 
     use super::*;
 
+    /// Fails with the queued errnos first, then succeeds.
+    struct FlakyTransport {
+        errors: RefCell<Vec<i32>>,
+        calls: RefCell<u32>,
+    }
+
+    impl FlakyTransport {
+        fn new(errors: &[i32]) -> Self {
+            Self {
+                errors: RefCell::new(errors.iter().rev().copied().collect()),
+                calls: RefCell::new(0),
+            }
+        }
+
+        fn next(&self) -> Result<()> {
+            *self.calls.borrow_mut() += 1;
+            match self.errors.borrow_mut().pop() {
+                Some(errno) => Err(Error::from_raw_os_error(errno)),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl NetlinkTransport for FlakyTransport {
+        fn send(&self, _buf: &[u8]) -> Result<()> {
+            self.next()
+        }
+
+        fn recv(&self, _buf: &mut [u8]) -> Result<(usize, u32)> {
+            self.next()?;
+            Ok((0, 0))
+        }
+
+        fn pid(&self) -> Result<u32> {
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_send_retry_eintr() {
+        let t = FlakyTransport::new(&[libc::EINTR, libc::EINTR]);
+        assert!(send_retry(&t, &[]).is_ok());
+        assert_eq!(*t.calls.borrow(), 3);
+
+        let t = FlakyTransport::new(&[libc::EPERM]);
+        assert!(send_retry(&t, &[]).is_err());
+        assert_eq!(*t.calls.borrow(), 1);
+    }
+
+    #[test]
+    fn test_recv_retry_eintr() {
+        let t = FlakyTransport::new(&[libc::EINTR, libc::EINTR, libc::EINTR]);
+        assert!(recv_retry(&t, &mut [], None).is_ok());
+        assert_eq!(*t.calls.borrow(), 4);
+    }
+
+    #[test]
+    fn test_recv_retry_eagain() {
+        let t = FlakyTransport::new(&[libc::EAGAIN]);
+        let err = recv_retry(&t, &mut [], None).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(*t.calls.borrow(), 1);
+
+        let timeout = Some(Duration::from_millis(1));
+
+        let t = FlakyTransport::new(&[libc::EAGAIN, libc::EAGAIN]);
+        assert!(recv_retry(&t, &mut [], timeout).is_ok());
+        assert_eq!(*t.calls.borrow(), 3);
+
+        let t = FlakyTransport::new(&[libc::EAGAIN; 5]);
+        let err = recv_retry(&t, &mut [], timeout).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        assert_eq!(*t.calls.borrow(), MAX_RETRIES + 1);
+    }
+
+    #[test]
+    fn test_recv_retry_enobufs() {
+        let t = FlakyTransport::new(&[libc::ENOBUFS]);
+        let err = recv_retry(&t, &mut [], Some(Duration::from_millis(1)))
+            .err()
+            .unwrap();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOBUFS));
+        assert_eq!(*t.calls.borrow(), 1);
+    }
+
+    #[test]
+    fn test_recv_error() {
+        let err = recv_error(Error::from_raw_os_error(libc::ENOBUFS));
+        assert!(matches!(
+            err.downcast_ref::<SocketError>(),
+            Some(SocketError::Overrun)
+        ));
+
+        let err = recv_error(Error::from_raw_os_error(libc::EINVAL));
+        assert!(err.downcast_ref::<SocketError>().is_none());
+        assert_eq!(
+            err.downcast_ref::<Error>().unwrap().raw_os_error(),
+            Some(libc::EINVAL)
+        );
+    }
+
     #[test]
     fn test_netlink_socket() {
         let s = Socket::new(NETLINK_ROUTE, 0, 0).unwrap();
@@ -177,7 +678,30 @@ mod tests {
         assert_eq!(from.nl_pid, 0);
         assert_eq!(from.nl_groups, 0);
 
-        assert!(netlink_msgs.len() > 0);
+        assert!(!netlink_msgs.is_empty());
+    }
+
+    #[test]
+    fn test_socket_cloexec() {
+        let s = Socket::new(NETLINK_ROUTE, 0, 0).unwrap();
+        let cloexec = || unsafe { libc::fcntl(s.fd, libc::F_GETFD) } & libc::FD_CLOEXEC != 0;
+
+        assert!(cloexec());
+
+        s.set_inheritable(true).unwrap();
+        assert!(!cloexec());
+
+        s.set_inheritable(false).unwrap();
+        assert!(cloexec());
+    }
+
+    #[test]
+    fn test_socket_recv_timeout() {
+        let s = Socket::new(NETLINK_ROUTE, 0, 0).unwrap();
+        s.set_recv_timeout(Duration::from_millis(10)).unwrap();
+
+        let err = s.recv().err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
     }
 
     #[test]
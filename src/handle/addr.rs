@@ -7,46 +7,51 @@ use anyhow::Result;
 use ipnet::IpNet;
 
 use crate::{
-    core::message::Message,
+    core::{
+        error::RequestContext,
+        event::{Event, EventStream},
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
     types::{
-        addr::Address,
+        addr::{Address, PrefixEvent, IFA_FLAGS, IFA_F_NOPREFIXROUTE},
         link::Link,
-        message::{AddressMessage, Attribute, RouteAttr},
+        message::{Attribute, IfAddrMsg, RouteAttr},
+        routing::RouteScope,
     },
 };
 
 use super::{sock_handle::SocketHandle, zero_terminated};
 
-pub struct AddrHandle<'a> {
-    pub socket: &'a mut SocketHandle,
+pub struct AddrHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
 }
 
-impl<'a> Deref for AddrHandle<'a> {
-    type Target = SocketHandle;
+impl<S: NetlinkTransport> Deref for AddrHandle<'_, S> {
+    type Target = SocketHandle<S>;
 
     fn deref(&self) -> &Self::Target {
         self.socket
     }
 }
 
-impl DerefMut for AddrHandle<'_> {
+impl<S: NetlinkTransport> DerefMut for AddrHandle<'_, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.socket
     }
 }
 
-impl<'a> From<&'a mut SocketHandle> for AddrHandle<'a> {
-    fn from(socket: &'a mut SocketHandle) -> Self {
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for AddrHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
         Self { socket }
     }
 }
 
-impl AddrHandle<'_> {
+impl<S: NetlinkTransport> AddrHandle<'_, S> {
     pub fn handle<T>(&mut self, link: &T, addr: &Address, proto: u16, flags: i32) -> Result<()>
     where
         T: Link + ?Sized,
     {
-        let mut req = Message::new(proto, flags);
         let base = link.attrs();
         let mut index: i32 = base.index;
 
@@ -58,6 +63,30 @@ impl AddrHandle<'_> {
             }
         }
 
+        self.send(index, addr, proto, flags)
+    }
+
+    /// Adds `addr` to the link `ifindex` with `IFA_F_NOPREFIXROUTE`, so the
+    /// kernel does not install the route to its subnet, e.g. because a
+    /// routing daemon manages it.
+    pub fn add_no_prefix_route(&mut self, ifindex: u32, addr: IpNet) -> Result<()> {
+        let addr = Address {
+            ip: addr,
+            flags: IFA_F_NOPREFIXROUTE,
+            ..Default::default()
+        };
+
+        self.send(
+            ifindex as i32,
+            &addr,
+            libc::RTM_NEWADDR,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        )
+    }
+
+    fn send(&mut self, index: i32, addr: &Address, proto: u16, flags: i32) -> Result<()> {
+        let mut req = Message::new(proto, flags);
+
         let (family, local_addr_data) = match addr.ip {
             IpNet::V4(ip) => (libc::AF_INET, ip.addr().octets().to_vec()),
             IpNet::V6(ip) => (libc::AF_INET6, ip.addr().octets().to_vec()),
@@ -75,10 +104,10 @@ impl AddrHandle<'_> {
             None => local_addr_data.clone(),
         };
 
-        let msg = AddressMessage {
+        let msg = IfAddrMsg {
             family: family as u8,
             prefix_len: addr.ip.prefix_len(),
-            flags: addr.flags,
+            flags: addr.flags as u8,
             scope: addr.scope,
             index,
         };
@@ -90,6 +119,10 @@ impl AddrHandle<'_> {
         req.add(&local_data.serialize()?);
         req.add(&address_data.serialize()?);
 
+        if addr.flags > 0xff {
+            req.add(&RouteAttr::u32(IFA_FLAGS, addr.flags).serialize()?);
+        }
+
         if family == libc::AF_INET {
             let broadcast = match addr.broadcast {
                 Some(IpAddr::V4(br)) => Some(br.octets().to_vec()),
@@ -112,7 +145,8 @@ impl AddrHandle<'_> {
             }
         }
 
-        self.request(&mut req, 0)?;
+        self.request(&mut req, 0)
+            .request_context(|| format!("{} on ifindex {}", addr.ip, index))?;
 
         Ok(())
     }
@@ -122,55 +156,154 @@ impl AddrHandle<'_> {
         T: Link + ?Sized,
     {
         let link_index = link.attrs().index;
-        let mut req = Message::new(libc::RTM_GETADDR, libc::NLM_F_DUMP);
-        let msg = AddressMessage::new(family);
-        req.add(&msg.serialize()?);
+        let msg = IfAddrMsg::new(family);
 
         Ok(self
-            .request(&mut req, libc::RTM_NEWADDR)?
-            .iter()
-            .filter_map(|m| {
-                let addr = Address::from(m.as_slice());
-                if addr.index == link_index {
-                    Some(addr)
-                } else {
-                    None
-                }
-            })
+            .dump::<_, Address>(libc::RTM_GETADDR, libc::RTM_NEWADDR, &msg)?
+            .into_iter()
+            .filter(|addr| addr.index == link_index)
             .collect())
     }
+
+    /// Lists the addresses of all links with the given `scope`, e.g.
+    /// `RouteScope::Universe` for global addresses only. The kernel rejects
+    /// a scope in the header of a strictly checked `RTM_GETADDR` dump, so
+    /// every address is dumped and filtered here.
+    pub fn list_by_scope(&mut self, scope: RouteScope) -> Result<Vec<Address>> {
+        let scope = u8::from(scope);
+        let msg = IfAddrMsg::new(libc::AF_UNSPEC);
+
+        Ok(self
+            .dump::<_, Address>(libc::RTM_GETADDR, libc::RTM_NEWADDR, &msg)?
+            .into_iter()
+            .filter(|addr| addr.scope == scope)
+            .collect())
+    }
+
+    /// Subscribes to `RTNLGRP_IPV6_PREFIX` and yields the prefixes the kernel
+    /// learns from Router Advertisements, e.g. for SLAAC or DHCPv6-PD tracking.
+    pub fn subscribe_prefix_delegation(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<PrefixEvent>>> {
+        let events = EventStream::new(libc::NETLINK_ROUTE, &[libc::RTNLGRP_IPV6_PREFIX])?;
+
+        // Prefixes cannot be dumped, but routers keep advertising them, so a
+        // dropped notification is recovered by the next advertisement and
+        // `Event::Resync` needs no handling here.
+        Ok(events.filter_map(|event| match event {
+            Ok(Event::Message(msg)) if msg.header.nlmsg_type == libc::RTM_NEWPREFIX => {
+                Some(PrefixEvent::try_from(msg.payload.as_deref()?))
+            }
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         test_setup,
-        types::{addr::AddressBuilder, link::LinkAttrs},
+        types::{
+            addr::{AddressBuilder, IFA_F_NOPREFIXROUTE},
+            link::LinkAttrs,
+        },
     };
 
+    use super::RouteScope;
+
     #[test]
-    fn test_addr_handle() {
+    fn test_addr_list_by_scope() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
-        let mut link_handle = handle.handle_link();
+        let link = handle.handle_link().get(&LinkAttrs::new("lo")).unwrap();
 
-        let attr = LinkAttrs::new("lo");
+        let host = "127.0.0.2/8".parse().unwrap();
+        let global = "10.1.2.3/24".parse().unwrap();
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
 
-        let link = link_handle.get(&attr).unwrap();
+        let mut addr_handle = handle.handle_addr();
+        for (ip, scope) in [(host, RouteScope::Host), (global, RouteScope::Universe)] {
+            let addr = AddressBuilder::default()
+                .ip(ip)
+                .scope(scope.into())
+                .build()
+                .unwrap();
+            addr_handle
+                .handle(&link, &addr, libc::RTM_NEWADDR, flags)
+                .unwrap();
+        }
 
-        let address = "127.0.0.2/24".parse().unwrap();
-        let addr = AddressBuilder::default().ip(address).build().unwrap();
+        let addrs = addr_handle.list_by_scope(RouteScope::Universe).unwrap();
+        assert!(addrs.iter().any(|addr| addr.ip == global));
+        assert!(addrs.iter().all(|addr| addr.scope == 0));
 
-        let proto = libc::RTM_NEWADDR;
-        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+        let addrs = addr_handle.list_by_scope(RouteScope::Host).unwrap();
+        assert!(addrs.iter().any(|addr| addr.ip == host));
+        assert!(addrs.iter().all(|addr| addr.ip != global));
+    }
 
-        let mut addr_handle = handle.handle_addr();
+    #[test]
+    fn test_addr_list_by_scope_host_lo() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
 
-        addr_handle.handle(&link, &addr, proto, flags).unwrap();
+        let addrs = handle
+            .handle_addr()
+            .list_by_scope(RouteScope::Host)
+            .unwrap();
 
-        let addrs = addr_handle.list(&link, libc::AF_UNSPEC).unwrap();
+        assert!(addrs
+            .iter()
+            .any(|addr| addr.ip == "127.0.0.1/8".parse().unwrap()));
+        assert!(addrs.iter().all(|addr| addr.scope == libc::RT_SCOPE_HOST));
+    }
 
-        assert_eq!(addrs.len(), 1);
-        assert_eq!(addrs[0].ip, address);
+    #[test]
+    fn test_addr_add_no_prefix_route() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+        let index = link.attrs().index as u32;
+
+        let mut addr_handle = handle.handle_addr();
+        addr_handle
+            .add_no_prefix_route(index, "10.1.0.1/24".parse().unwrap())
+            .unwrap();
+        addr_handle
+            .handle(
+                &link,
+                &AddressBuilder::default()
+                    .ip("10.2.0.1/24".parse().unwrap())
+                    .build()
+                    .unwrap(),
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let addrs = addr_handle.list(&link, libc::AF_INET).unwrap();
+        let flags_of = |ip: &str| {
+            let ip = ip.parse().unwrap();
+            addrs.iter().find(|addr| addr.ip == ip).unwrap().flags
+        };
+        assert_ne!(flags_of("10.1.0.1/24") & IFA_F_NOPREFIXROUTE, 0);
+        assert_eq!(flags_of("10.2.0.1/24") & IFA_F_NOPREFIXROUTE, 0);
+
+        let dsts: Vec<_> = handle
+            .handle_route()
+            .list(libc::AF_INET)
+            .unwrap()
+            .into_iter()
+            .filter_map(|route| route.dst)
+            .collect();
+        assert!(!dsts.contains(&"10.1.0.0/24".parse().unwrap()));
+        assert!(dsts.contains(&"10.2.0.0/24".parse().unwrap()));
     }
 }
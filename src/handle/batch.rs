@@ -0,0 +1,40 @@
+use std::vec;
+
+use crate::core::message::Message;
+
+/// Fully built messages that `SocketHandle::send_batch` sends together, e.g.
+/// to install thousands of routes without a round trip per route. Every
+/// message is acknowledged on its own, so the batch reports a result per
+/// message instead of failing as a whole.
+#[derive(Default)]
+pub struct Batch {
+    messages: Vec<Message>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `msg`. Its sequence number is assigned when the batch is sent.
+    pub fn add(&mut self, msg: Message) {
+        self.messages.push(msg);
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+impl IntoIterator for Batch {
+    type Item = Message;
+    type IntoIter = vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.messages.into_iter()
+    }
+}
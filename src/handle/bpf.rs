@@ -0,0 +1,98 @@
+use std::{
+    ops::{Deref, DerefMut},
+    os::fd::RawFd,
+};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        bpf::{xdp_attr, XdpFlags},
+        message::{Attribute, IfInfoMsg},
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+pub struct BpfHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for BpfHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for BpfHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for BpfHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> BpfHandle<'_, S> {
+    /// Attaches the loaded XDP program `prog_fd` to the link. The kernel
+    /// takes its own reference, so the fd may be closed afterwards.
+    pub fn attach_xdp(&mut self, ifindex: u32, prog_fd: RawFd, flags: XdpFlags) -> Result<()> {
+        self.set_xdp(ifindex, prog_fd, flags)
+    }
+
+    /// Detaches the XDP program attached in the mode selected by `flags`.
+    pub fn detach_xdp(&mut self, ifindex: u32, flags: XdpFlags) -> Result<()> {
+        self.set_xdp(ifindex, -1, flags)
+    }
+
+    fn set_xdp(&mut self, ifindex: u32, prog_fd: RawFd, flags: XdpFlags) -> Result<()> {
+        let mut req = Message::new(libc::RTM_SETLINK, libc::NLM_F_ACK);
+
+        let mut msg = IfInfoMsg::new(libc::AF_UNSPEC);
+        msg.index = ifindex as i32;
+
+        req.add(&msg.serialize()?);
+        req.add(&xdp_attr(prog_fd, flags).serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::AsRawFd;
+
+    use crate::{test_setup, types::link::LinkAttrs};
+
+    use super::*;
+
+    #[test]
+    fn test_xdp_attach_detach() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let lo = handle.handle_link().get(&LinkAttrs::new("lo")).unwrap();
+        let ifindex = lo.attrs().index as u32;
+
+        let mut bpf_handle = handle.handle_bpf();
+
+        // Detaching is a no-op when no program is attached.
+        bpf_handle.detach_xdp(ifindex, XdpFlags::SKB_MODE).unwrap();
+
+        let not_a_prog = std::fs::File::open("/dev/null").unwrap();
+        assert!(bpf_handle
+            .attach_xdp(ifindex, not_a_prog.as_raw_fd(), XdpFlags::SKB_MODE)
+            .is_err());
+    }
+}
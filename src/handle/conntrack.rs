@@ -0,0 +1,142 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::socket::{NetlinkTransport, Socket},
+    types::{
+        conntrack::{ConntrackEntry, IPCTNL_MSG_CT_GET, IPCTNL_MSG_CT_NEW},
+        message::NfGenMessage,
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+pub struct ConntrackHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for ConntrackHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for ConntrackHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for ConntrackHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> ConntrackHandle<'_, S> {
+    /// Dumps the connection tracking table. `family` is `AF_INET`,
+    /// `AF_INET6`, or `AF_UNSPEC` for both.
+    pub fn list(&mut self, family: i32) -> Result<Vec<ConntrackEntry>> {
+        let subsys = (libc::NFNL_SUBSYS_CTNETLINK as u16) << 8;
+
+        self.dump(
+            subsys | IPCTNL_MSG_CT_GET,
+            subsys | IPCTNL_MSG_CT_NEW,
+            &NfGenMessage::new(family),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::{
+        core::message::NlFlags,
+        test_setup,
+        types::{
+            conntrack::{
+                ConntrackTuple, CTA_IP_V4_DST, CTA_IP_V4_SRC, CTA_PROTO_DST_PORT, CTA_PROTO_NUM,
+                CTA_PROTO_SRC_PORT, CTA_TIMEOUT, CTA_TUPLE_IP, CTA_TUPLE_ORIG, CTA_TUPLE_PROTO,
+                CTA_TUPLE_REPLY,
+            },
+            message::{Attribute, RouteAttr},
+            NLA_F_NESTED,
+        },
+    };
+
+    use super::*;
+
+    fn tuple_attr(rta_type: u16, tuple: &ConntrackTuple) -> RouteAttr {
+        let mut ip = RouteAttr::new(CTA_TUPLE_IP | NLA_F_NESTED, &[]);
+        ip.add_attribute(Box::new(RouteAttr::ip(CTA_IP_V4_SRC, tuple.src)));
+        ip.add_attribute(Box::new(RouteAttr::ip(CTA_IP_V4_DST, tuple.dst)));
+
+        let mut proto = RouteAttr::new(CTA_TUPLE_PROTO | NLA_F_NESTED, &[]);
+        proto.add_attribute(Box::new(RouteAttr::u8(CTA_PROTO_NUM, tuple.protocol)));
+        proto.add_attribute(Box::new(RouteAttr::u16_be(
+            CTA_PROTO_SRC_PORT,
+            tuple.src_port,
+        )));
+        proto.add_attribute(Box::new(RouteAttr::u16_be(
+            CTA_PROTO_DST_PORT,
+            tuple.dst_port,
+        )));
+
+        let mut attr = RouteAttr::new(rta_type | NLA_F_NESTED, &[]);
+        attr.add_attribute(Box::new(ip));
+        attr.add_attribute(Box::new(proto));
+        attr
+    }
+
+    #[test]
+    fn test_conntrack_list() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_NETFILTER);
+
+        // Without a ruleset conntrack does not hook into a fresh namespace,
+        // so a real flow over lo would not be tracked. Insert one instead.
+        let lo: IpAddr = "127.0.0.1".parse().unwrap();
+        let original = ConntrackTuple {
+            src: lo,
+            dst: lo,
+            protocol: libc::IPPROTO_UDP as u8,
+            src_port: 40000,
+            dst_port: 53,
+        };
+        let reply = ConntrackTuple {
+            src_port: original.dst_port,
+            dst_port: original.src_port,
+            ..original.clone()
+        };
+
+        let subsys = (libc::NFNL_SUBSYS_CTNETLINK as u16) << 8;
+        let mut payload = NfGenMessage::new(libc::AF_INET).serialize().unwrap();
+        payload.extend(tuple_attr(CTA_TUPLE_ORIG, &original).serialize().unwrap());
+        payload.extend(tuple_attr(CTA_TUPLE_REPLY, &reply).serialize().unwrap());
+        payload.extend(RouteAttr::u32_be(CTA_TIMEOUT, 60).serialize().unwrap());
+        handle
+            .raw_request(
+                subsys | IPCTNL_MSG_CT_NEW,
+                NlFlags::CREATE | NlFlags::EXCL | NlFlags::ACK,
+                &payload,
+            )
+            .unwrap();
+
+        let entries = handle.handle_conntrack().list(libc::AF_UNSPEC).unwrap();
+
+        assert!(entries
+            .iter()
+            .all(|e| e.family == libc::AF_INET as u8 || e.family == libc::AF_INET6 as u8));
+        let entry = entries
+            .iter()
+            .find(|e| e.original == original)
+            .expect("inserted entry is listed");
+        assert_eq!(entry.family, libc::AF_INET as u8);
+        assert_eq!(entry.reply, reply);
+        assert!(entry.timeout > 0 && entry.timeout <= 60);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        ethtool::{
+            EthtoolStats, ETHTOOL_A_BITSET_NOMASK, ETHTOOL_A_BITSET_SIZE, ETHTOOL_A_BITSET_VALUE,
+            ETHTOOL_A_HEADER_DEV_INDEX, ETHTOOL_A_LINKSTATE_HEADER, ETHTOOL_A_LINKSTATE_LINK,
+            ETHTOOL_A_STATS_GROUPS, ETHTOOL_A_STATS_HEADER, ETHTOOL_GENL_NAME,
+            ETHTOOL_GENL_VERSION, ETHTOOL_MSG_LINKSTATE_GET, ETHTOOL_MSG_STATS_GET,
+            ETHTOOL_STATS_CNT,
+        },
+        message::{Attribute, GenlMessage, RouteAttr, RouteAttrMap, RouteAttrs},
+        NLA_F_NESTED,
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+pub struct EthtoolHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for EthtoolHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for EthtoolHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for EthtoolHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> EthtoolHandle<'_, S> {
+    pub fn get_stats(&mut self, ifindex: u32) -> Result<HashMap<String, u64>> {
+        let mut req = self.new_request(ETHTOOL_MSG_STATS_GET, ETHTOOL_A_STATS_HEADER, ifindex)?;
+
+        let mut groups = RouteAttr::new(ETHTOOL_A_STATS_GROUPS | NLA_F_NESTED, &[]);
+        groups.add(ETHTOOL_A_BITSET_NOMASK, &[]);
+        groups.add(ETHTOOL_A_BITSET_SIZE, &ETHTOOL_STATS_CNT.to_ne_bytes());
+        groups.add(
+            ETHTOOL_A_BITSET_VALUE,
+            &((1u32 << ETHTOOL_STATS_CNT) - 1).to_ne_bytes(),
+        );
+        req.add(&groups.serialize()?);
+
+        let msgs = self.request(&mut req, 0)?;
+        let msg = msgs
+            .first()
+            .ok_or_else(|| anyhow!("invalid response for ETHTOOL_MSG_STATS_GET"))?;
+
+        Ok(EthtoolStats::try_from(msg.as_slice())?.into())
+    }
+
+    pub fn get_link_state(&mut self, ifindex: u32) -> Result<bool> {
+        let mut req = self.new_request(
+            ETHTOOL_MSG_LINKSTATE_GET,
+            ETHTOOL_A_LINKSTATE_HEADER,
+            ifindex,
+        )?;
+
+        let msgs = self.request(&mut req, 0)?;
+        let msg = msgs
+            .first()
+            .ok_or_else(|| anyhow!("invalid response for ETHTOOL_MSG_LINKSTATE_GET"))?;
+
+        let attrs = RouteAttrs::from(&msg[4..]);
+
+        RouteAttrMap::from(&attrs)
+            .get_bool(&ETHTOOL_A_LINKSTATE_LINK)
+            .ok_or_else(|| anyhow!("link state not reported for device {}", ifindex))
+    }
+
+    fn new_request(&mut self, command: u8, header_type: u16, ifindex: u32) -> Result<Message> {
+        let family = self.handle_generic().get_family(ETHTOOL_GENL_NAME)?;
+
+        let mut req = Message::new(family.id, 0);
+        req.add(&GenlMessage::new(command, ETHTOOL_GENL_VERSION).serialize()?);
+
+        let mut header = RouteAttr::new(header_type | NLA_F_NESTED, &[]);
+        header.add(ETHTOOL_A_HEADER_DEV_INDEX, &ifindex.to_ne_bytes());
+        req.add(&header.serialize()?);
+
+        Ok(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_setup, types::link::LinkAttrs};
+
+    use super::*;
+
+    #[test]
+    fn test_ethtool_link_state() {
+        test_setup!();
+        let mut route_handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = route_handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let mut handle = SocketHandle::new(libc::NETLINK_GENERIC);
+        let mut ethtool_handle = handle.handle_ethtool();
+
+        assert!(ethtool_handle
+            .get_link_state(link.attrs().index as u32)
+            .unwrap());
+    }
+}
@@ -0,0 +1,133 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        fou::{
+            FouEntry, FOU_ATTR_AF, FOU_ATTR_IPPROTO, FOU_ATTR_PORT, FOU_ATTR_TYPE, FOU_CMD_ADD,
+            FOU_CMD_DEL, FOU_CMD_GET, FOU_ENCAP_DIRECT, FOU_ENCAP_GUE, FOU_GENL_NAME,
+            FOU_GENL_VERSION,
+        },
+        message::{Attribute, GenlMessage, RouteAttr},
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+pub struct FouHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for FouHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for FouHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for FouHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> FouHandle<'_, S> {
+    pub fn add(&mut self, port: u16, proto: u8, gue: bool) -> Result<()> {
+        let mut req = self.new_request(FOU_CMD_ADD, libc::NLM_F_ACK)?;
+
+        let encap = match gue {
+            true => FOU_ENCAP_GUE,
+            false => FOU_ENCAP_DIRECT,
+        };
+
+        req.add(&RouteAttr::u16_be(FOU_ATTR_PORT, port).serialize()?);
+        req.add(&RouteAttr::u8(FOU_ATTR_AF, libc::AF_INET as u8).serialize()?);
+        req.add(&RouteAttr::u8(FOU_ATTR_TYPE, encap).serialize()?);
+
+        if !gue {
+            req.add(&RouteAttr::u8(FOU_ATTR_IPPROTO, proto).serialize()?);
+        }
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn del(&mut self, port: u16) -> Result<()> {
+        let mut req = self.new_request(FOU_CMD_DEL, libc::NLM_F_ACK)?;
+
+        req.add(&RouteAttr::u16_be(FOU_ATTR_PORT, port).serialize()?);
+        req.add(&RouteAttr::u8(FOU_ATTR_AF, libc::AF_INET as u8).serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn list(&mut self) -> Result<Vec<FouEntry>> {
+        let mut req = self.new_request(FOU_CMD_GET, libc::NLM_F_DUMP)?;
+
+        self.request(&mut req, 0)?
+            .iter()
+            .map(|m| FouEntry::try_from(m.as_slice()))
+            .collect()
+    }
+
+    fn new_request(&mut self, command: u8, flags: i32) -> Result<Message> {
+        let family = self.handle_generic().get_family(FOU_GENL_NAME)?;
+
+        let mut req = Message::new(family.id, flags);
+        req.add(&GenlMessage::new(command, FOU_GENL_VERSION).serialize()?);
+
+        Ok(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{skip_unless_supported, test_setup};
+
+    use super::*;
+
+    #[test]
+    fn test_fou_add_list_del() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_GENERIC);
+
+        skip_unless_supported!(
+            handle.handle_generic().get_family(FOU_GENL_NAME),
+            "the fou module"
+        );
+
+        let mut fou_handle = handle.handle_fou();
+
+        fou_handle
+            .add(5555, libc::IPPROTO_IPIP as u8, false)
+            .unwrap();
+        fou_handle.add(5556, 0, true).unwrap();
+
+        let entries = fou_handle.list().unwrap();
+
+        assert!(entries
+            .iter()
+            .any(|e| e.port == 5555 && e.protocol == libc::IPPROTO_IPIP as u8 && !e.gue));
+        assert!(entries.iter().any(|e| e.port == 5556 && e.gue));
+
+        fou_handle.del(5555).unwrap();
+        fou_handle.del(5556).unwrap();
+
+        assert!(fou_handle.list().unwrap().is_empty());
+    }
+}
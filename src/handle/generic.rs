@@ -3,7 +3,10 @@ use std::ops::{Deref, DerefMut};
 use anyhow::{anyhow, Result};
 
 use crate::{
-    core::message::Message,
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
     handle::zero_terminated,
     types::{
         generic::{GenlFamilies, GenlFamily},
@@ -13,31 +16,31 @@ use crate::{
 
 use super::sock_handle::SocketHandle;
 
-pub struct GenericHandle<'a> {
-    pub socket: &'a mut SocketHandle,
+pub struct GenericHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
 }
 
-impl<'a> Deref for GenericHandle<'a> {
-    type Target = SocketHandle;
+impl<S: NetlinkTransport> Deref for GenericHandle<'_, S> {
+    type Target = SocketHandle<S>;
 
     fn deref(&self) -> &Self::Target {
         self.socket
     }
 }
 
-impl DerefMut for GenericHandle<'_> {
+impl<S: NetlinkTransport> DerefMut for GenericHandle<'_, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.socket
     }
 }
 
-impl<'a> From<&'a mut SocketHandle> for GenericHandle<'a> {
-    fn from(socket: &'a mut SocketHandle) -> Self {
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for GenericHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
         Self { socket }
     }
 }
 
-impl GenericHandle<'_> {
+impl<S: NetlinkTransport> GenericHandle<'_, S> {
     pub fn list_family(&mut self) -> Result<GenlFamilies> {
         let mut req = Message::new(libc::GENL_ID_CTRL as u16, libc::NLM_F_DUMP);
         let msg = GenlMessage::get_family_message();
@@ -1,9 +1,25 @@
 pub mod addr;
+pub mod batch;
+pub mod bpf;
+pub mod conntrack;
+pub mod ethtool;
+pub mod fou;
 pub mod generic;
 pub mod link;
+pub mod mptcp;
+pub mod ndt;
 pub mod neigh;
+pub mod nft;
+pub mod nl80211;
+pub mod qdisc;
 pub mod routing;
+pub mod rule;
+pub mod sock_diag;
 pub mod sock_handle;
+pub mod tc;
+pub mod xfrm;
+
+pub use crate::zero_terminated;
 
 #[macro_export]
 macro_rules! test_setup {
@@ -16,9 +32,22 @@ macro_rules! test_setup {
     };
 }
 
-pub fn zero_terminated(s: &str) -> Vec<u8> {
-    let mut v = Vec::with_capacity(s.len() + 1);
-    v.extend_from_slice(s.as_bytes());
-    v.push(0);
-    v
+/// Skips a root test when `$probe` fails because the kernel lacks `$what`,
+/// i.e. with `EOPNOTSUPP`, `ENOENT` or `EPROTONOSUPPORT`. Any other result
+/// of the probe lets the test go on, so the call under test is unwrapped and
+/// a request the kernel rejects as malformed fails the test.
+#[cfg(test)]
+#[macro_export]
+macro_rules! skip_unless_supported {
+    ($probe:expr, $what:expr) => {
+        if let Err(e) = $probe {
+            if matches!(
+                $crate::core::error::errno(&e),
+                Some(libc::EOPNOTSUPP | libc::ENOENT | libc::EPROTONOSUPPORT)
+            ) {
+                eprintln!("test skipped, requires {}: {e}", $what);
+                return;
+            }
+        }
+    };
 }
@@ -0,0 +1,166 @@
+use std::{
+    net::IpAddr,
+    ops::{Deref, DerefMut},
+};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::{Attribute, GenlMessage, RouteAttr},
+        mptcp::{
+            MptcpAddr, MPTCP_PM_ADDR_ATTR_ADDR4, MPTCP_PM_ADDR_ATTR_ADDR6,
+            MPTCP_PM_ADDR_ATTR_FAMILY, MPTCP_PM_ADDR_ATTR_FLAGS, MPTCP_PM_ADDR_ATTR_ID,
+            MPTCP_PM_ADDR_ATTR_PORT, MPTCP_PM_ATTR_ADDR, MPTCP_PM_CMD_ADD_ADDR,
+            MPTCP_PM_CMD_DEL_ADDR, MPTCP_PM_CMD_GET_ADDR, MPTCP_PM_NAME, MPTCP_PM_VER,
+        },
+        NLA_F_NESTED,
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+pub struct MptcpHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for MptcpHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for MptcpHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for MptcpHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> MptcpHandle<'_, S> {
+    /// Adds a path manager endpoint. `flags` is a combination of the
+    /// `MPTCP_PM_ADDR_FLAG_*` values, e.g. `MPTCP_PM_ADDR_FLAG_SUBFLOW`.
+    pub fn add_addr(&mut self, addr: IpAddr, port: u16, flags: u32) -> Result<()> {
+        let mut req = self.new_request(MPTCP_PM_CMD_ADD_ADDR, libc::NLM_F_ACK)?;
+
+        let mut endpoint = addr_attr(&addr);
+        if port != 0 {
+            endpoint.add(MPTCP_PM_ADDR_ATTR_PORT, &port.to_ne_bytes());
+        }
+        endpoint.add(MPTCP_PM_ADDR_ATTR_FLAGS, &flags.to_ne_bytes());
+        req.add(&endpoint.serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    /// Deletes the endpoint with address `addr`. The kernel identifies
+    /// endpoints by id, so it is looked up first.
+    pub fn del_addr(&mut self, addr: IpAddr) -> Result<()> {
+        let id = self
+            .list_addrs()?
+            .into_iter()
+            .find(|a| a.addr == addr)
+            .ok_or_else(|| anyhow!("no mptcp endpoint for {}", addr))?
+            .id;
+
+        let mut req = self.new_request(MPTCP_PM_CMD_DEL_ADDR, libc::NLM_F_ACK)?;
+
+        let mut endpoint = addr_attr(&addr);
+        endpoint.add(MPTCP_PM_ADDR_ATTR_ID, &[id]);
+        req.add(&endpoint.serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    pub fn list_addrs(&mut self) -> Result<Vec<MptcpAddr>> {
+        let mut req = self.new_request(MPTCP_PM_CMD_GET_ADDR, libc::NLM_F_DUMP)?;
+
+        self.request(&mut req, 0)?
+            .iter()
+            .map(|m| MptcpAddr::try_from(m.as_slice()))
+            .collect()
+    }
+
+    fn new_request(&mut self, command: u8, flags: i32) -> Result<Message> {
+        let family = self.handle_generic().get_family(MPTCP_PM_NAME)?;
+
+        let mut req = Message::new(family.id, flags);
+        req.add(&GenlMessage::new(command, MPTCP_PM_VER).serialize()?);
+
+        Ok(req)
+    }
+}
+
+fn addr_attr(addr: &IpAddr) -> RouteAttr {
+    let mut attr = RouteAttr::new(MPTCP_PM_ATTR_ADDR | NLA_F_NESTED, &[]);
+
+    match addr {
+        IpAddr::V4(ip) => {
+            attr.add(
+                MPTCP_PM_ADDR_ATTR_FAMILY,
+                &(libc::AF_INET as u16).to_ne_bytes(),
+            );
+            attr.add(MPTCP_PM_ADDR_ATTR_ADDR4, &ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            attr.add(
+                MPTCP_PM_ADDR_ATTR_FAMILY,
+                &(libc::AF_INET6 as u16).to_ne_bytes(),
+            );
+            attr.add(MPTCP_PM_ADDR_ATTR_ADDR6, &ip.octets());
+        }
+    }
+
+    attr
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{skip_unless_supported, test_setup, types::mptcp::MPTCP_PM_ADDR_FLAG_SIGNAL};
+
+    use super::*;
+
+    #[test]
+    fn test_mptcp_add_list_del() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_GENERIC);
+
+        skip_unless_supported!(
+            handle.handle_generic().get_family(MPTCP_PM_NAME),
+            "CONFIG_MPTCP"
+        );
+
+        let mut mptcp_handle = handle.handle_mptcp();
+        let addr: IpAddr = "10.0.0.1".parse().unwrap();
+
+        mptcp_handle
+            .add_addr(addr, 8080, MPTCP_PM_ADDR_FLAG_SIGNAL)
+            .unwrap();
+
+        let addrs = mptcp_handle.list_addrs().unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].addr, addr);
+        assert_eq!(addrs[0].port, 8080);
+        assert_eq!(addrs[0].flags, MPTCP_PM_ADDR_FLAG_SIGNAL);
+
+        mptcp_handle.del_addr(addr).unwrap();
+
+        assert!(mptcp_handle.list_addrs().unwrap().is_empty());
+        assert!(mptcp_handle.del_addr(addr).is_err());
+    }
+}
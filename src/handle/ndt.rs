@@ -0,0 +1,198 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        error::RequestContext,
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::{Attribute, NdtMsg, RouteAttr},
+        ndt::{
+            NeighTable, NeighTableParams, NDTA_GC_INTERVAL, NDTA_NAME, NDTA_PARMS, NDTA_THRESH1,
+            NDTA_THRESH2, NDTA_THRESH3, NDTPA_BASE_REACHABLE_TIME, NDTPA_GC_STALETIME,
+            NDTPA_IFINDEX,
+        },
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+/// Reads and tunes the neighbor tables, i.e. the ARP and NDP caches.
+pub struct NdtHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for NdtHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for NdtHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for NdtHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> NdtHandle<'_, S> {
+    /// Dumps the neighbor tables of every family.
+    pub fn list(&mut self) -> Result<Vec<NeighTable>> {
+        let tables: Vec<NeighTable> = self.dump(
+            libc::RTM_GETNEIGHTBL,
+            libc::RTM_NEWNEIGHTBL,
+            &NdtMsg::default(),
+        )?;
+
+        // The dump also has a message with the parameters of every link,
+        // which lacks the NDTA_CONFIG of a table.
+        Ok(tables.into_iter().filter(|t| t.key_len > 0).collect())
+    }
+
+    /// Changes the parameters of the table `name`, e.g. `arp_cache`, with
+    /// `RTM_SETNEIGHTBL`.
+    pub fn set(&mut self, name: &str, params: &NeighTableParams) -> Result<()> {
+        let mut req = Message::new(libc::RTM_SETNEIGHTBL, libc::NLM_F_ACK);
+        req.add(&NdtMsg::default().serialize()?);
+        req.add(&RouteAttr::string(NDTA_NAME, name).serialize()?);
+
+        for (rta_type, thresh) in [
+            (NDTA_THRESH1, params.gc_thresh1),
+            (NDTA_THRESH2, params.gc_thresh2),
+            (NDTA_THRESH3, params.gc_thresh3),
+        ] {
+            if let Some(thresh) = thresh {
+                req.add(&RouteAttr::u32(rta_type, thresh).serialize()?);
+            }
+        }
+
+        if let Some(interval) = params.gc_interval {
+            let ms = interval.as_millis() as u64;
+            req.add(&RouteAttr::u64(NDTA_GC_INTERVAL, ms).serialize()?);
+        }
+
+        let mut parms = RouteAttr::new(NDTA_PARMS, &[]);
+
+        if let Some(ifindex) = params.ifindex {
+            parms.add_attribute(Box::new(RouteAttr::u32(NDTPA_IFINDEX, ifindex)));
+        }
+
+        for (rta_type, time) in [
+            (NDTPA_BASE_REACHABLE_TIME, params.base_reachable_time),
+            (NDTPA_GC_STALETIME, params.gc_stale_time),
+        ] {
+            if let Some(time) = time {
+                let ms = time.as_millis() as u64;
+                parms.add_attribute(Box::new(RouteAttr::u64(rta_type, ms)));
+            }
+        }
+
+        if parms.attributes.is_some() {
+            req.add(&parms.serialize()?);
+        }
+
+        self.request(&mut req, 0)
+            .request_context(|| format!("name {}", name))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Duration};
+
+    use crate::{
+        core::{error::errno, hexdump::HexDump, mock::MockTransport},
+        test_setup,
+        types::AF_INET,
+    };
+
+    use super::*;
+
+    #[rustfmt::skip]
+    static SETNEIGHTBL_REQ: [u8; 68] = [
+        0x44, 0x00, 0x00, 0x00, // nlmsg_len = 68
+        0x43, 0x00, // nlmsg_type = RTM_SETNEIGHTBL
+        0x05, 0x00, // nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK
+        0x01, 0x00, 0x00, 0x00, // nlmsg_seq = 1
+        0x01, 0x00, 0x00, 0x00, // nlmsg_pid = 1
+        0x00, 0x00, 0x00, 0x00, // family = AF_UNSPEC
+        0x0e, 0x00, 0x01, 0x00, // NDTA_NAME
+        b'a', b'r', b'p', b'_', b'c', b'a', b'c', b'h', b'e', 0x00, 0x00, 0x00, // "arp_cache"
+        0x08, 0x00, 0x04, 0x00, 0x00, 0x04, 0x00, 0x00, // NDTA_THRESH3 = 1024
+        0x18, 0x00, 0x06, 0x00, // NDTA_PARMS, 24 bytes
+        0x08, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, // NDTPA_IFINDEX = 1
+        0x0c, 0x00, 0x04, 0x00, // NDTPA_BASE_REACHABLE_TIME
+        0x10, 0x27, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 10000 ms
+    ];
+
+    #[test]
+    fn test_ndt_set_mock_serialize() {
+        let mock = MockTransport::default();
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let params = NeighTableParams {
+            ifindex: Some(1),
+            gc_thresh3: Some(1024),
+            base_reachable_time: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+
+        handle.handle_ndt().set("arp_cache", &params).unwrap();
+
+        let sent = handle.socket.sent();
+        assert_eq!(
+            sent,
+            vec![SETNEIGHTBL_REQ.to_vec()],
+            "sent:\n{}",
+            HexDump(&sent[0])
+        );
+    }
+
+    #[test]
+    fn test_ndt_handle() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut ndt_handle = handle.handle_ndt();
+
+        let tables = ndt_handle.list().unwrap();
+        let arp = tables.iter().find(|t| t.name == "arp_cache").unwrap();
+
+        assert_eq!(arp.family, AF_INET);
+        assert_eq!(arp.key_len, 4);
+        assert!(arp.gc_thresh1 <= arp.gc_thresh2 && arp.gc_thresh2 <= arp.gc_thresh3);
+        assert!(arp.gc_thresh3 > 0);
+        assert!(arp.base_reachable_time > Duration::ZERO);
+
+        // The parameters of a link may be changed in any namespace.
+        let params = NeighTableParams {
+            ifindex: Some(1),
+            base_reachable_time: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        ndt_handle.set("arp_cache", &params).unwrap();
+
+        let ms = fs::read_to_string("/proc/sys/net/ipv4/neigh/lo/base_reachable_time_ms").unwrap();
+        assert_eq!(ms.trim(), "10000");
+
+        // The thresholds only in the initial one.
+        let params = NeighTableParams {
+            gc_thresh3: Some(arp.gc_thresh3),
+            ..Default::default()
+        };
+        let err = ndt_handle.set("arp_cache", &params).unwrap_err();
+        assert_eq!(errno(&err), Some(libc::ENOENT));
+    }
+}
@@ -1,45 +1,54 @@
 use std::{
+    collections::HashMap,
     net::IpAddr,
     ops::{Deref, DerefMut},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
 
 use crate::{
-    core::message::Message,
+    core::{
+        event::{Event, EventStream},
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
     types::{
-        message::{Attribute, NeighborMessage, RouteAttr},
-        neigh::Neighbor,
+        message::{Attribute, NdMsg, NudState, RouteAttr},
+        neigh::{MacMoveEvent, Neighbor},
     },
 };
 
 use super::sock_handle::SocketHandle;
 
-pub struct NeighHandle<'a> {
-    pub socket: &'a mut SocketHandle,
+/// The link, MAC address and time an IP address was last seen with.
+type MacTable = HashMap<IpAddr, (u32, Vec<u8>, Instant)>;
+
+pub struct NeighHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
 }
 
-impl<'a> Deref for NeighHandle<'a> {
-    type Target = SocketHandle;
+impl<S: NetlinkTransport> Deref for NeighHandle<'_, S> {
+    type Target = SocketHandle<S>;
 
     fn deref(&self) -> &Self::Target {
         self.socket
     }
 }
 
-impl<'a> DerefMut for NeighHandle<'a> {
+impl<S: NetlinkTransport> DerefMut for NeighHandle<'_, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.socket
     }
 }
 
-impl<'a> From<&'a mut SocketHandle> for NeighHandle<'a> {
-    fn from(socket: &'a mut SocketHandle) -> Self {
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for NeighHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
         Self { socket }
     }
 }
 
-impl NeighHandle<'_> {
+impl<S: NetlinkTransport> NeighHandle<'_, S> {
     pub fn handle(&mut self, neigh: &Neighbor, proto: u16, flags: i32) -> Result<()> {
         let mut req = Message::new(proto, flags);
 
@@ -51,7 +60,7 @@ impl NeighHandle<'_> {
 
         let family = neigh.family.map_or(family, |f| f);
 
-        let neigh_msg = NeighborMessage::new(
+        let neigh_msg = NdMsg::new(
             family,
             neigh.link_index,
             neigh.state,
@@ -73,6 +82,153 @@ impl NeighHandle<'_> {
 
         Ok(())
     }
+
+    pub fn list(&mut self, ifindex: u32, family: u8) -> Result<Vec<Neighbor>> {
+        let msg = NdMsg::new(family, ifindex, NudState::empty(), 0, 0);
+
+        Ok(self
+            .dump::<_, Neighbor>(libc::RTM_GETNEIGH, libc::RTM_NEWNEIGH, &msg)?
+            .into_iter()
+            .filter(|n| ifindex == 0 || n.link_index == ifindex)
+            .collect())
+    }
+
+    /// Replaces the link-layer address of an existing entry in place. The
+    /// entry keeps its current state and flags, so unlike a delete followed
+    /// by an add it never goes through `NUD_INCOMPLETE`.
+    pub fn update_mac(&mut self, ifindex: u32, ip: IpAddr, mac: [u8; 6]) -> Result<()> {
+        let family = match ip {
+            IpAddr::V4(_) => libc::AF_INET as u8,
+            IpAddr::V6(_) => libc::AF_INET6 as u8,
+        };
+
+        let mut neigh = self
+            .list(ifindex, family)?
+            .into_iter()
+            .find(|n| n.ip_addr == Some(ip))
+            .ok_or_else(|| anyhow!("no neighbor entry for {} on link {}", ip, ifindex))?;
+
+        neigh.mac_addr = Some(mac.to_vec());
+
+        self.handle(
+            &neigh,
+            libc::RTM_NEWNEIGH,
+            libc::NLM_F_REPLACE | libc::NLM_F_ACK,
+        )
+    }
+
+    /// Yields a `MacMoveEvent` whenever the neighbor entry of an IP address
+    /// shows up with another MAC address or on another link less than
+    /// `window` after the address was last seen, e.g. a host that moved
+    /// behind another port, as needed to detect EVPN MAC mobility. Deleted
+    /// entries are remembered, so a move by delete and re-add is reported.
+    pub fn subscribe_mac_move(
+        &mut self,
+        window: Duration,
+    ) -> Result<impl Iterator<Item = Result<MacMoveEvent>>> {
+        let events = EventStream::new(libc::NETLINK_ROUTE, &[libc::RTNLGRP_NEIGH])?;
+        let mut handle = SocketHandle::with_transport(Socket::new(libc::NETLINK_ROUTE, 0, 0)?)?;
+
+        let mut seen = MacTable::new();
+        let now = Instant::now();
+        for neigh in self.list(0, libc::AF_UNSPEC as u8)? {
+            mac_move(&mut seen, window, &neigh, now);
+        }
+
+        Ok(events.flat_map(move |event| match event {
+            Ok(Event::Message(msg)) => match (msg.header.nlmsg_type, msg.payload.as_deref()) {
+                (libc::RTM_NEWNEIGH, Some(payload)) => {
+                    mac_move(&mut seen, window, &Neighbor::from(payload), Instant::now())
+                        .map(Ok)
+                        .into_iter()
+                        .collect()
+                }
+                _ => vec![],
+            },
+            Ok(Event::Resync) => resync_macs(&mut handle, &mut seen, window),
+            Err(e) => vec![Err(e)],
+        }))
+    }
+
+    /// Adds `remote_vtep` to the FDB of a VXLAN device, so frames to `mac`
+    /// are sent to it. Without `mac`, the all-zeros address is used, i.e. a
+    /// head-end replication entry that floods BUM traffic to the remote. The
+    /// entry is appended, so several remotes can share the same `mac`.
+    pub fn add_vtep(
+        &mut self,
+        vxlan_ifindex: u32,
+        remote_vtep: IpAddr,
+        mac: Option<[u8; 6]>,
+    ) -> Result<()> {
+        let neigh = Neighbor {
+            link_index: vxlan_ifindex,
+            family: Some(libc::AF_BRIDGE as u8),
+            state: NudState::PERMANENT,
+            ip_addr: Some(remote_vtep),
+            mac_addr: Some(mac.unwrap_or_default().to_vec()),
+            flags: libc::NTF_SELF,
+            ..Default::default()
+        };
+
+        self.handle(
+            &neigh,
+            libc::RTM_NEWNEIGH,
+            libc::NLM_F_CREATE | libc::NLM_F_APPEND | libc::NLM_F_ACK,
+        )
+    }
+}
+
+/// Replaces `seen` with a fresh dump over `handle` and returns the moves it
+/// reveals.
+fn resync_macs(
+    handle: &mut SocketHandle,
+    seen: &mut MacTable,
+    window: Duration,
+) -> Vec<Result<MacMoveEvent>> {
+    match handle.handle_neigh().list(0, libc::AF_UNSPEC as u8) {
+        Ok(dump) => {
+            let now = Instant::now();
+            dump.iter()
+                .filter_map(|neigh| mac_move(seen, window, neigh, now))
+                .map(Ok)
+                .collect()
+        }
+        Err(e) => vec![Err(e)],
+    }
+}
+
+/// Records the IP and MAC address of `neigh` in `seen` and returns a move if
+/// the IP address was seen with another MAC address or on another link less
+/// than `window` before `now`. Bridge FDB entries are ignored, their
+/// address is that of a remote VTEP shared by many MAC addresses.
+fn mac_move(
+    seen: &mut MacTable,
+    window: Duration,
+    neigh: &Neighbor,
+    now: Instant,
+) -> Option<MacMoveEvent> {
+    if !matches!(
+        neigh.family.map(i32::from),
+        Some(libc::AF_INET | libc::AF_INET6)
+    ) {
+        return None;
+    }
+
+    let ip = neigh.ip_addr?;
+    let mac = neigh.mac_addr.clone()?;
+
+    let (old_ifindex, old_mac, last_seen) =
+        seen.insert(ip, (neigh.link_index, mac.clone(), now))?;
+
+    let moved = old_ifindex != neigh.link_index || old_mac != mac;
+
+    (moved && now.duration_since(last_seen) < window).then_some(MacMoveEvent {
+        ip,
+        old_mac,
+        new_mac: mac,
+        old_ifindex,
+        new_ifindex: neigh.link_index,
+    })
 }
 
 #[cfg(test)]
@@ -80,7 +236,7 @@ mod tests {
     use crate::{
         parse_mac, test_setup,
         types::{
-            link::{Kind, LinkAttrs},
+            link::{Kind, LinkAttrs, VxlanAttrs},
             neigh::NeighborBuilder,
         },
     };
@@ -112,7 +268,7 @@ mod tests {
 
         let neigh = NeighborBuilder::default()
             .link_index(link.attrs().index as u32)
-            .state(libc::NUD_PERMANENT)
+            .state(NudState::PERMANENT)
             .neigh_type(libc::RTN_UNICAST)
             .ip_addr(Some(IpAddr::V4("10.244.0.0".parse().unwrap())))
             .mac_addr(Some(mac_bytes))
@@ -127,4 +283,157 @@ mod tests {
             )
             .unwrap();
     }
+
+    #[test]
+    fn test_neigh_update_mac() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let attr = LinkAttrs::new("foo");
+
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        link_handle
+            .add(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = link_handle.get(&attr).unwrap();
+        let index = link.attrs().index as u32;
+        let ip = IpAddr::V4("10.244.0.1".parse().unwrap());
+
+        let mut neigh_handle = handle.handle_neigh();
+
+        let neigh = NeighborBuilder::default()
+            .link_index(index)
+            .state(NudState::PERMANENT)
+            .neigh_type(libc::RTN_UNICAST)
+            .ip_addr(Some(ip))
+            .mac_addr(Some(parse_mac("aa:bb:cc:dd:00:01").unwrap()))
+            .build()
+            .unwrap();
+
+        neigh_handle
+            .handle(
+                &neigh,
+                libc::RTM_NEWNEIGH,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        neigh_handle
+            .update_mac(index, ip, [0xaa, 0xbb, 0xcc, 0xdd, 0x00, 0x02])
+            .unwrap();
+
+        let neighs = neigh_handle.list(index, libc::AF_INET as u8).unwrap();
+        let updated = neighs.iter().find(|n| n.ip_addr == Some(ip)).unwrap();
+
+        assert_eq!(updated.state, NudState::PERMANENT);
+        assert_eq!(
+            updated.mac_addr,
+            Some(parse_mac("aa:bb:cc:dd:00:02").unwrap())
+        );
+
+        let missing = IpAddr::V4("10.244.0.2".parse().unwrap());
+        assert!(neigh_handle.update_mac(index, missing, [0; 6]).is_err());
+    }
+
+    #[test]
+    fn test_neigh_add_vtep() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let attr = LinkAttrs::new("vxlan0");
+
+        let link = Kind::Vxlan {
+            attrs: attr.clone(),
+            vxlan_attrs: VxlanAttrs {
+                id: 42,
+                port: Some(4789),
+                ..Default::default()
+            },
+        };
+
+        link_handle
+            .add(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let index = link_handle.get(&attr).unwrap().attrs().index as u32;
+        let remotes: [IpAddr; 2] = ["192.0.2.1".parse().unwrap(), "192.0.2.2".parse().unwrap()];
+        let mac = [0xaa, 0xbb, 0xcc, 0xdd, 0x00, 0x01];
+
+        let mut neigh_handle = handle.handle_neigh();
+
+        for remote in remotes {
+            neigh_handle.add_vtep(index, remote, None).unwrap();
+        }
+        neigh_handle.add_vtep(index, remotes[0], Some(mac)).unwrap();
+
+        let fdb = neigh_handle.list(index, libc::AF_BRIDGE as u8).unwrap();
+
+        for remote in remotes {
+            assert!(fdb
+                .iter()
+                .any(|n| n.ip_addr == Some(remote) && n.mac_addr == Some(vec![0; 6])));
+        }
+        assert!(fdb
+            .iter()
+            .any(|n| n.ip_addr == Some(remotes[0]) && n.mac_addr == Some(mac.to_vec())));
+    }
+
+    #[test]
+    fn test_mac_move() {
+        let mut seen = MacTable::new();
+        let window = Duration::from_secs(10);
+        let now = Instant::now();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let neigh = |link_index, mac: &str| Neighbor {
+            link_index,
+            family: Some(libc::AF_INET as u8),
+            ip_addr: Some(ip),
+            mac_addr: Some(parse_mac(mac).unwrap()),
+            ..Default::default()
+        };
+
+        let first = neigh(2, "aa:bb:cc:dd:ee:01");
+        assert_eq!(mac_move(&mut seen, window, &first, now), None);
+        assert_eq!(mac_move(&mut seen, window, &first, now), None);
+
+        let moved = neigh(3, "aa:bb:cc:dd:ee:02");
+        assert_eq!(
+            mac_move(&mut seen, window, &moved, now + Duration::from_secs(1)),
+            Some(MacMoveEvent {
+                ip,
+                old_mac: first.mac_addr.clone().unwrap(),
+                new_mac: moved.mac_addr.clone().unwrap(),
+                old_ifindex: 2,
+                new_ifindex: 3,
+            })
+        );
+
+        // Seen again only after the window, which is not a move.
+        assert_eq!(
+            mac_move(&mut seen, window, &first, now + Duration::from_secs(20)),
+            None
+        );
+
+        let fdb = Neighbor {
+            family: Some(libc::AF_BRIDGE as u8),
+            ..neigh(4, "aa:bb:cc:dd:ee:03")
+        };
+        assert_eq!(mac_move(&mut seen, window, &fdb, now), None);
+    }
 }
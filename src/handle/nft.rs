@@ -0,0 +1,95 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::{Attribute, NfGenMessage, RouteAttr},
+        nft::{
+            nft_msg_type, NftChain, NftTable, NFTA_CHAIN_TABLE, NFT_MSG_GETCHAIN, NFT_MSG_GETTABLE,
+            NFT_MSG_NEWTABLE,
+        },
+    },
+};
+
+use super::{sock_handle::SocketHandle, zero_terminated};
+
+pub struct NftHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for NftHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for NftHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for NftHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> NftHandle<'_, S> {
+    /// Lists the tables of every family.
+    pub fn list_tables(&mut self) -> Result<Vec<NftTable>> {
+        self.dump(
+            nft_msg_type(NFT_MSG_GETTABLE),
+            nft_msg_type(NFT_MSG_NEWTABLE),
+            &NfGenMessage::new(libc::NFPROTO_UNSPEC),
+        )
+    }
+
+    /// Lists the chains of every table named `table`, whatever its family.
+    pub fn list_chains(&mut self, table: &str) -> Result<Vec<NftChain>> {
+        let mut req = Message::new(nft_msg_type(NFT_MSG_GETCHAIN), libc::NLM_F_DUMP);
+        req.add(&NfGenMessage::new(libc::NFPROTO_UNSPEC).serialize()?);
+        req.add(&RouteAttr::new(NFTA_CHAIN_TABLE, &zero_terminated(table)).serialize()?);
+
+        Ok(self
+            .request(&mut req, 0)?
+            .iter()
+            .map(|m| NftChain::try_from(m.as_slice()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|c| c.table == table)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{skip_unless_supported, test_setup};
+
+    use super::*;
+
+    #[test]
+    fn test_nft_list() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_NETFILTER);
+        let mut nft_handle = handle.handle_nft();
+
+        skip_unless_supported!(nft_handle.list_tables(), "nf_tables");
+
+        let tables = nft_handle.list_tables().unwrap();
+
+        for table in tables {
+            let chains = nft_handle.list_chains(&table.name).unwrap();
+            assert!(chains.iter().all(|c| c.table == table.name));
+        }
+
+        assert!(nft_handle.list_chains("rsln-missing").unwrap().is_empty());
+    }
+}
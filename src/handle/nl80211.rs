@@ -0,0 +1,217 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    core::{
+        error::errno,
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::{Attribute, GenlMessage, RouteAttr},
+        nl80211::{
+            associated_bssid, Nl80211Interface, NL80211_ATTR_IFINDEX, NL80211_CMD_GET_INTERFACE,
+            NL80211_CMD_GET_SCAN, NL80211_GENL_NAME, NL80211_GENL_VERSION,
+        },
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+pub struct Nl80211Handle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for Nl80211Handle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for Nl80211Handle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for Nl80211Handle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> Nl80211Handle<'_, S> {
+    pub fn get_interface(&mut self, ifindex: u32) -> Result<Nl80211Interface> {
+        let family = self.family_id()?;
+
+        let mut req = new_request(family, NL80211_CMD_GET_INTERFACE, 0)?;
+        req.add(&RouteAttr::new(NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes()).serialize()?);
+
+        let msgs = self.request(&mut req, 0)?;
+        let msg = msgs
+            .first()
+            .ok_or_else(|| anyhow!("invalid response for NL80211_CMD_GET_INTERFACE"))?;
+
+        let mut iface = Nl80211Interface::try_from(msg.as_slice())?;
+        iface.bssid = self.get_bssid(family, ifindex)?;
+
+        Ok(iface)
+    }
+
+    /// Lists every wireless interface. The family is resolved once, so this
+    /// takes a dump plus one scan result lookup per interface.
+    pub fn list_interfaces(&mut self) -> Result<Vec<Nl80211Interface>> {
+        let family = self.family_id()?;
+
+        let mut req = new_request(family, NL80211_CMD_GET_INTERFACE, libc::NLM_F_DUMP)?;
+
+        let mut ifaces = self
+            .request(&mut req, 0)?
+            .iter()
+            .map(|m| Nl80211Interface::try_from(m.as_slice()))
+            .collect::<Result<Vec<_>>>()?;
+
+        for iface in ifaces.iter_mut() {
+            iface.bssid = self.get_bssid(family, iface.index)?;
+        }
+
+        Ok(ifaces)
+    }
+
+    /// Looks up the BSS the interface is associated with in its scan results.
+    fn get_bssid(&mut self, family: u16, ifindex: u32) -> Result<Option<Vec<u8>>> {
+        let mut req = new_request(family, NL80211_CMD_GET_SCAN, libc::NLM_F_DUMP)?;
+        req.add(&RouteAttr::new(NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes()).serialize()?);
+
+        // Interfaces that cannot scan, e.g. monitors, reject the dump with
+        // EOPNOTSUPP, and ones removed since they were listed with ENODEV.
+        let msgs = match self.request(&mut req, 0) {
+            Ok(msgs) => msgs,
+            Err(e) if matches!(errno(&e), Some(libc::EOPNOTSUPP | libc::ENODEV)) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        };
+
+        for msg in msgs {
+            if let Some(bssid) = associated_bssid(&msg)? {
+                return Ok(Some(bssid));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn family_id(&mut self) -> Result<u16> {
+        Ok(self.handle_generic().get_family(NL80211_GENL_NAME)?.id)
+    }
+}
+
+fn new_request(family: u16, command: u8, flags: i32) -> Result<Message> {
+    let mut req = Message::new(family, flags);
+    req.add(&GenlMessage::new(command, NL80211_GENL_VERSION).serialize()?);
+
+    Ok(req)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{core::mock::MockTransport, skip_unless_supported, test_setup};
+
+    use super::*;
+
+    const NL80211_ID: u16 = 0x20;
+
+    fn family_reply() -> Vec<u8> {
+        let mut msg = Message::new(libc::GENL_ID_CTRL as u16, 0);
+        msg.add(
+            &GenlMessage::new(libc::CTRL_CMD_NEWFAMILY as u8, 2)
+                .serialize()
+                .unwrap(),
+        );
+        msg.add(
+            &RouteAttr::new(libc::CTRL_ATTR_FAMILY_ID as u16, &NL80211_ID.to_ne_bytes())
+                .serialize()
+                .unwrap(),
+        );
+        msg.serialize().unwrap()
+    }
+
+    fn dump_reply(ifindexes: &[u32]) -> Vec<u8> {
+        let mut dump = vec![];
+        for ifindex in ifindexes {
+            let mut msg = Message::new(NL80211_ID, libc::NLM_F_MULTI);
+            msg.add(
+                &GenlMessage::new(7, NL80211_GENL_VERSION)
+                    .serialize()
+                    .unwrap(),
+            );
+            msg.add(
+                &RouteAttr::new(NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes())
+                    .serialize()
+                    .unwrap(),
+            );
+            dump.extend(msg.serialize().unwrap());
+        }
+        let mut done = Message::new(libc::NLMSG_DONE as u16, libc::NLM_F_MULTI);
+        done.add(&0i32.to_ne_bytes());
+        dump.extend(done.serialize().unwrap());
+        dump
+    }
+
+    fn error_reply(errno: i32) -> Vec<u8> {
+        let mut err = vec![];
+        err.extend_from_slice(&36u32.to_ne_bytes());
+        err.extend_from_slice(&(libc::NLMSG_ERROR as u16).to_ne_bytes());
+        err.extend_from_slice(&[0; 10]);
+        err.extend_from_slice(&(-errno).to_ne_bytes());
+        err.extend_from_slice(&[0; 16]);
+        err
+    }
+
+    #[test]
+    fn test_nl80211_list_interfaces_mock() {
+        let mock = MockTransport::default();
+        mock.push_response(&family_reply());
+        mock.push_response(&dump_reply(&[4, 5]));
+        mock.push_response(&error_reply(libc::EOPNOTSUPP));
+        mock.push_response(&dump_reply(&[]));
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let ifaces = handle.handle_nl80211().list_interfaces().unwrap();
+
+        assert_eq!(ifaces.len(), 2);
+        assert!(ifaces.iter().all(|iface| iface.bssid.is_none()));
+        // One family lookup, the interface dump and a scan dump per interface.
+        assert_eq!(handle.socket.sent().len(), 4);
+
+        let mock = MockTransport::default();
+        mock.push_response(&family_reply());
+        mock.push_response(&dump_reply(&[4]));
+        mock.push_response(&error_reply(libc::EPERM));
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        assert!(handle.handle_nl80211().list_interfaces().is_err());
+    }
+
+    #[test]
+    fn test_nl80211_list_interfaces() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_GENERIC);
+
+        skip_unless_supported!(
+            handle.handle_generic().get_family(NL80211_GENL_NAME),
+            "the cfg80211 module"
+        );
+
+        let mut nl80211_handle = handle.handle_nl80211();
+
+        for iface in nl80211_handle.list_interfaces().unwrap() {
+            let got = nl80211_handle.get_interface(iface.index).unwrap();
+            assert_eq!(got.name, iface.name);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::{Attribute, RouteAttr, TcMessage},
+        tc::{FqCodelParams, Tbf, TCA_KIND},
+    },
+};
+
+use super::{sock_handle::SocketHandle, zero_terminated};
+
+pub struct QdiscHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for QdiscHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for QdiscHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for QdiscHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> QdiscHandle<'_, S> {
+    /// Adds a `tbf` qdisc with the given handle below `parent`, which is
+    /// `TC_H_ROOT` to replace the root qdisc of the link.
+    pub fn add_tbf(&mut self, ifindex: u32, handle: u32, parent: u32, tbf: &Tbf) -> Result<()> {
+        self.add(ifindex, handle, parent, "tbf", Some(tbf.options()?))
+    }
+
+    /// Adds an `fq_codel` qdisc with the given handle below `parent`.
+    pub fn add_fq_codel(
+        &mut self,
+        ifindex: u32,
+        handle: u32,
+        parent: u32,
+        params: FqCodelParams,
+    ) -> Result<()> {
+        self.add(ifindex, handle, parent, "fq_codel", Some(params.options()))
+    }
+
+    /// Adds a `pfifo_fast` qdisc, which takes no options.
+    pub fn add_pfifo_fast(&mut self, ifindex: u32, handle: u32, parent: u32) -> Result<()> {
+        self.add(ifindex, handle, parent, "pfifo_fast", None)
+    }
+
+    /// Adds a qdisc of `kind` with the given handle below `parent`, failing
+    /// if the link already has one there.
+    pub(crate) fn add(
+        &mut self,
+        ifindex: u32,
+        handle: u32,
+        parent: u32,
+        kind: &str,
+        options: Option<RouteAttr>,
+    ) -> Result<()> {
+        let mut req = Message::new(
+            libc::RTM_NEWQDISC,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        );
+
+        let msg = TcMessage {
+            family: libc::AF_UNSPEC as u8,
+            index: ifindex as i32,
+            handle,
+            parent,
+            ..Default::default()
+        };
+
+        req.add(&msg.serialize()?);
+        req.add(&RouteAttr::new(TCA_KIND, &zero_terminated(kind)).serialize()?);
+
+        if let Some(options) = options {
+            req.add(&options.serialize()?);
+        }
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    /// Probes for the `kind` qdisc by attaching it without options as the
+    /// root qdisc of the link and removing it again. A missing module fails
+    /// with `ENOENT`, a qdisc that needs options with `EINVAL`.
+    #[cfg(test)]
+    pub(crate) fn probe(&mut self, ifindex: u32, kind: &str) -> Result<()> {
+        use crate::types::tc::TC_H_ROOT;
+
+        self.add(ifindex, 0, TC_H_ROOT, kind, None)?;
+
+        let mut req = Message::new(libc::RTM_DELQDISC, libc::NLM_F_ACK);
+        let msg = TcMessage {
+            family: libc::AF_UNSPEC as u8,
+            index: ifindex as i32,
+            parent: TC_H_ROOT,
+            ..Default::default()
+        };
+        req.add(&msg.serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{skip_unless_supported, test_setup, types::link::LinkAttrs, types::tc::TC_H_ROOT};
+
+    use super::*;
+
+    #[test]
+    fn test_qdisc_add_tbf_and_pfifo_fast() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+        let ifindex = lo.attrs().index as u32;
+
+        let tbf = Tbf {
+            rate: 125_000,
+            burst: 32 * 1024,
+            latency: 400_000,
+            mtu: 1514,
+        };
+
+        skip_unless_supported!(handle.handle_qdisc().probe(ifindex, "tbf"), "sch_tbf");
+
+        handle
+            .handle_qdisc()
+            .add_tbf(ifindex, 0x10000, TC_H_ROOT, &tbf)
+            .unwrap();
+
+        // tbf is classful, its single class 1:1 holds the child qdisc.
+        handle
+            .handle_qdisc()
+            .add_pfifo_fast(ifindex, 0x20000, 0x10001)
+            .unwrap();
+
+        let tbf = handle
+            .handle_tc()
+            .get_qdisc(ifindex, 0x10000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(tbf.kind, "tbf");
+        assert_eq!(tbf.ifindex, ifindex);
+        assert_eq!(tbf.parent, 0);
+
+        let child = handle
+            .handle_tc()
+            .get_qdisc(ifindex, 0x20000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(child.kind, "pfifo_fast");
+        assert_eq!(child.handle, 0x20000);
+
+        assert_eq!(
+            handle.handle_tc().get_qdisc(ifindex, 0x30000).unwrap(),
+            None
+        );
+    }
+}
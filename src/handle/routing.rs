@@ -1,182 +1,785 @@
 use std::{
-    net::IpAddr,
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     ops::{Deref, DerefMut},
 };
 
 use anyhow::{bail, Result};
-use ipnet::IpNet;
+use ipnet::{IpNet, Ipv6Net};
 
 use crate::{
-    core::message::Message,
+    core::{
+        error::{errno, RequestContext},
+        event::{Event, EventStream},
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
     types::{
-        message::{Attribute, RouteAttr, RouteMessage},
+        addr::AddrFamily,
+        message::{Attribute, NexthopMessage, RouteAttr, RouteMessage},
+        nexthop::{
+            NexthopGroup, NexthopObject, NHA_GROUPS, NHA_ID, RTM_DELNEXTHOP, RTM_GETNEXTHOP,
+            RTM_NEWNEXTHOP,
+        },
         routing::Routing,
+        seg6::{end_dx4_encap, LWTUNNEL_ENCAP_SEG6_LOCAL, RTA_ENCAP_TYPE},
     },
-    RTA_MTU, RTA_VIA,
+    RTA_MTU, RTA_UID, RTA_VIA,
 };
 
-use super::sock_handle::SocketHandle;
+use super::{
+    batch::Batch,
+    sock_handle::{RequestOptions, SocketHandle},
+};
 
 const RTM_F_LOOKUP_TABLE: u32 = 0x1000;
+const LOOPBACK_IFINDEX: u32 = 1;
 
-pub struct RouteHandle<'a> {
-    pub socket: &'a mut SocketHandle,
+pub struct RouteHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
 }
 
-impl<'a> Deref for RouteHandle<'a> {
-    type Target = SocketHandle;
+impl<S: NetlinkTransport> Deref for RouteHandle<'_, S> {
+    type Target = SocketHandle<S>;
 
     fn deref(&self) -> &Self::Target {
         self.socket
     }
 }
 
-impl DerefMut for RouteHandle<'_> {
+impl<S: NetlinkTransport> DerefMut for RouteHandle<'_, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.socket
     }
 }
 
-impl<'a> From<&'a mut SocketHandle> for RouteHandle<'a> {
-    fn from(socket: &'a mut SocketHandle) -> Self {
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for RouteHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
         Self { socket }
     }
 }
 
-impl RouteHandle<'_> {
+impl<S: NetlinkTransport> RouteHandle<'_, S> {
     pub fn handle(&mut self, route: &Routing, proto: u16, flags: i32) -> Result<()> {
-        let mut req = Message::new(proto, flags);
+        let mut req = route_request(route, proto, flags)?;
+        self.request(&mut req, 0)
+            .request_context(|| route_context(route))?;
+
+        Ok(())
+    }
+
+    /// Replaces the route matching `route`, or adds it if there is none,
+    /// so that unlike a delete and an add, the destination never goes
+    /// without a route.
+    pub fn replace(&mut self, route: &Routing) -> Result<()> {
+        self.handle(
+            route,
+            libc::RTM_NEWROUTE,
+            Routing::FLAG_REPLACE | libc::NLM_F_ACK,
+        )
+    }
 
-        let mut msg = match proto {
-            libc::RTM_DELROUTE => RouteMessage::new_delete_msg(),
-            _ => RouteMessage::new(),
+    /// Adds `route` next to the routes to the same destination. IPv6 merges
+    /// it into a multipath route with their nexthops, while IPv4 keeps it
+    /// as a separate route that is only used once they are gone, so an
+    /// IPv4 ECMP route needs all of its `nexthops` in a single route.
+    pub fn append(&mut self, route: &Routing) -> Result<()> {
+        self.handle(
+            route,
+            libc::RTM_NEWROUTE,
+            Routing::FLAG_APPEND | libc::NLM_F_ACK,
+        )
+    }
+
+    /// Adds `route` with `NLM_F_ECHO` and returns the route the kernel
+    /// installed, including the fields it filled in, e.g. the table, the
+    /// protocol or the metric, without a `get` afterwards.
+    pub fn add_and_return(&mut self, route: &Routing) -> Result<Routing> {
+        let mut req = route_request(
+            route,
+            libc::RTM_NEWROUTE,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL,
+        )?;
+
+        let opts = RequestOptions {
+            ack: true,
+            require_echo: true,
+            ..Default::default()
         };
+        let res = self
+            .request_with_options(&mut req, libc::RTM_NEWROUTE, opts)
+            .request_context(|| route_context(route))?;
+
+        Routing::try_from(res[0].as_slice())
+    }
 
-        let mut attrs = vec![];
+    /// Like `handle`, but adds the request to `batch` instead of sending it,
+    /// e.g. to install many routes with `SocketHandle::send_batch`.
+    pub fn handle_batched(
+        &self,
+        batch: &mut Batch,
+        route: &Routing,
+        proto: u16,
+        flags: i32,
+    ) -> Result<()> {
+        batch.add(route_request(route, proto, flags)?);
 
-        if proto != libc::RTM_GETROUTE || route.oif_index > 0 {
-            let mut b = [0; 4];
-            b.copy_from_slice(&route.oif_index.to_ne_bytes());
-            attrs.push(RouteAttr::new(libc::RTA_OIF, &b));
+        Ok(())
+    }
+
+    pub fn get(&mut self, dst: &IpAddr) -> Result<Vec<Routing>> {
+        let mut req = lookup_request(dst)?;
+
+        self.request(&mut req, libc::RTM_NEWROUTE)?
+            .iter()
+            .map(|m| Routing::try_from(m.as_slice()))
+            .collect()
+    }
+
+    /// Looks up the route to `dst` for the traffic of the user `uid`, which
+    /// the rules with a `uidrange` match on, or returns `None` if `dst` is
+    /// unreachable for it.
+    pub fn get_by_uid(&mut self, dst: IpAddr, uid: u32) -> Result<Option<Routing>> {
+        let mut req = lookup_request(&dst)?;
+        req.add(&RouteAttr::u32(RTA_UID, uid).serialize()?);
+
+        match self.request(&mut req, libc::RTM_NEWROUTE) {
+            Ok(res) => res
+                .first()
+                .map(|m| Routing::try_from(m.as_slice()))
+                .transpose(),
+            Err(e) if errno(&e) == Some(libc::ENETUNREACH) => Ok(None),
+            Err(e) => Err(e),
         }
+    }
 
-        if let Some(dst) = route.dst {
-            let (family, dst_data) = match dst {
-                IpNet::V4(ip) => (libc::AF_INET, ip.addr().octets().to_vec()),
-                IpNet::V6(ip) => (libc::AF_INET6, ip.addr().octets().to_vec()),
-            };
-            msg.family = family as u8;
-            msg.dst_len = dst.prefix_len();
+    /// Dumps the routes of every table for `family`, or of all families
+    /// with `AF_UNSPEC`.
+    pub fn list(&mut self, family: i32) -> Result<Vec<Routing>> {
+        let msg = RouteMessage {
+            family: family as u8,
+            ..Default::default()
+        };
+
+        self.dump(libc::RTM_GETROUTE, libc::RTM_NEWROUTE, &msg)
+    }
+
+    /// Returns the routes of every table of `family` that go through
+    /// `gateway`, as `RTA_GATEWAY`, as `RTA_VIA` or as one of their nexthops,
+    /// e.g. to find the routes to withdraw when the gateway becomes
+    /// unreachable. The kernel cannot
+    /// filter a dump by gateway, so every route of `family` is dumped and
+    /// filtered here, which is slow with full routing tables.
+    pub fn list_by_gateway(&mut self, gateway: IpAddr, family: AddrFamily) -> Result<Vec<Routing>> {
+        Ok(self
+            .list(family.into())?
+            .into_iter()
+            .filter(|r| {
+                r.gw == Some(gateway)
+                    || r.via.as_ref().is_some_and(|via| via.addr == gateway)
+                    || r.nexthops.iter().any(|nh| nh.gateway == gateway)
+            })
+            .collect())
+    }
+
+    /// Returns the routes to exactly `dst` from every table, most preferred
+    /// first, i.e. sorted by ascending metric. A route without a metric
+    /// counts as metric 0, and the default route matches `0.0.0.0/0` and
+    /// `::/0`.
+    pub fn list_sorted_by_metric(&mut self, dst: IpNet) -> Result<Vec<Routing>> {
+        let family = match dst {
+            IpNet::V4(_) => libc::AF_INET,
+            IpNet::V6(_) => libc::AF_INET6,
+        };
+        let dst = dst.trunc();
+
+        let mut routes: Vec<Routing> = self
+            .list(family)?
+            .into_iter()
+            .filter(|r| match r.dst {
+                Some(d) => d.trunc() == dst,
+                None => dst.prefix_len() == 0,
+            })
+            .collect();
+
+        routes.sort_by_key(|r| r.priority.unwrap_or_default());
+
+        Ok(routes)
+    }
+
+    /// Subscribes to `RTNLGRP_IPV4_ROUTE` and `RTNLGRP_IPV6_ROUTE`, or to the
+    /// one of `family`, and yields the gateway of the preferred default route
+    /// of the main table each time it changes, or `None` once the last
+    /// default route is withdrawn. With `AddrFamily::All`, changes of both
+    /// families are yielded as they happen, so a `None` only says that one of
+    /// them lost its default route.
+    pub fn subscribe_default_gw(
+        &mut self,
+        family: AddrFamily,
+    ) -> Result<impl Iterator<Item = Result<Option<IpAddr>>>> {
+        let families = match family {
+            AddrFamily::V4 => vec![libc::AF_INET],
+            AddrFamily::V6 => vec![libc::AF_INET6],
+            AddrFamily::All => vec![libc::AF_INET, libc::AF_INET6],
+        };
+        let groups: Vec<u32> = families
+            .iter()
+            .map(|&family| match family {
+                libc::AF_INET => libc::RTNLGRP_IPV4_ROUTE,
+                _ => libc::RTNLGRP_IPV6_ROUTE,
+            })
+            .collect();
+
+        let events = EventStream::new(libc::NETLINK_ROUTE, &groups)?;
+        let mut handle = SocketHandle::with_transport(Socket::new(libc::NETLINK_ROUTE, 0, 0)?)?;
+
+        let mut gws = families
+            .iter()
+            .map(|&family| Ok((family, self.default_gw(family)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(events.flat_map(move |event| match event {
+            Ok(Event::Message(msg)) => match default_route_family(&msg) {
+                Some(family) => refresh_default_gws(&mut handle, &mut gws, &[family]),
+                None => vec![],
+            },
+            Ok(Event::Resync) => refresh_default_gws(&mut handle, &mut gws, &families),
+            Err(e) => vec![Err(e)],
+        }))
+    }
+
+    /// Returns the gateway of the preferred default route of the main table,
+    /// or the first nexthop gateway when it is a multipath route.
+    fn default_gw(&mut self, family: i32) -> Result<Option<IpAddr>> {
+        let dst = match family {
+            libc::AF_INET => IpNet::new(Ipv4Addr::UNSPECIFIED.into(), 0)?,
+            _ => IpNet::new(Ipv6Addr::UNSPECIFIED.into(), 0)?,
+        };
+
+        Ok(self
+            .list_sorted_by_metric(dst)?
+            .into_iter()
+            .find(|r| r.table == libc::RT_TABLE_MAIN)
+            .and_then(|r| {
+                r.gw.or_else(|| {
+                    r.nexthops
+                        .iter()
+                        .map(|nh| nh.gateway)
+                        .find(|gw| !gw.is_unspecified())
+                })
+            }))
+    }
+
+    /// Programs an SRv6 End.DX4 function for the SID `dst` in `table`: the
+    /// SRv6 encapsulation of matching packets is removed and the inner IPv4
+    /// packet is forwarded to `nh_v4`. The route is attached to the loopback
+    /// device, like local SIDs usually are.
+    pub fn add_srv6_end_dx4(&mut self, dst: Ipv6Net, nh_v4: Ipv4Addr, table: u32) -> Result<()> {
+        let mut req = Message::new(
+            libc::RTM_NEWROUTE,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        );
+
+        let msg = RouteMessage {
+            family: libc::AF_INET6 as u8,
+            dst_len: dst.prefix_len(),
+            table: u8::try_from(table).unwrap_or(libc::RT_TABLE_UNSPEC),
+            ..RouteMessage::new()
+        };
+
+        req.add(&msg.serialize()?);
+        req.add(&RouteAttr::new(libc::RTA_DST, &dst.addr().octets()).serialize()?);
+        req.add(&RouteAttr::u32(libc::RTA_OIF, LOOPBACK_IFINDEX).serialize()?);
+        req.add(&RouteAttr::u32(libc::RTA_TABLE, table).serialize()?);
+        req.add(&RouteAttr::u16(RTA_ENCAP_TYPE, LWTUNNEL_ENCAP_SEG6_LOCAL).serialize()?);
+        req.add(&end_dx4_encap(nh_v4).serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    /// Creates a nexthop object and returns its id, which the kernel assigns
+    /// when `nh.id` is 0.
+    pub fn add_nexthop_object(&mut self, nh: &NexthopObject) -> Result<u32> {
+        let mut req = Message::new(RTM_NEWNEXTHOP, libc::NLM_F_CREATE | libc::NLM_F_EXCL);
+        req.add(&nh.serialize()?);
+
+        let opts = RequestOptions {
+            ack: true,
+            require_echo: true,
+            ..Default::default()
+        };
+        let res = self.request_with_options(&mut req, RTM_NEWNEXTHOP, opts)?;
+
+        Ok(NexthopObject::try_from(res[0].as_slice())?.id)
+    }
+
+    pub fn del_nexthop_object(&mut self, id: u32) -> Result<()> {
+        let mut req = Message::new(RTM_DELNEXTHOP, libc::NLM_F_ACK);
+        req.add(&NexthopMessage::default().serialize()?);
+        req.add(&RouteAttr::new(NHA_ID, &id.to_ne_bytes()).serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
 
-            attrs.push(RouteAttr::new(libc::RTA_DST, &dst_data));
+    /// Dumps the nexthop groups. Plain nexthop objects are filtered out by
+    /// the kernel.
+    pub fn list_nexthop_groups(&mut self) -> Result<Vec<NexthopGroup>> {
+        let mut req = Message::new(RTM_GETNEXTHOP, libc::NLM_F_DUMP);
+        req.add(&NexthopMessage::default().serialize()?);
+        req.add(&RouteAttr::new(NHA_GROUPS, &[]).serialize()?);
+
+        self.request(&mut req, RTM_NEWNEXTHOP)?
+            .iter()
+            .map(|m| NexthopGroup::try_from(m.as_slice()))
+            .collect()
+    }
+}
+
+/// Builds the request that `RouteHandle::handle` sends for `route`.
+fn route_request(route: &Routing, proto: u16, flags: i32) -> Result<Message> {
+    let mut req = Message::new(proto, flags);
+
+    let mut msg = match proto {
+        libc::RTM_DELROUTE => RouteMessage::new_delete_msg(),
+        _ => RouteMessage::new(),
+    };
+
+    let mut attrs = vec![];
+
+    if proto != libc::RTM_GETROUTE || route.oif_index > 0 {
+        let mut b = [0; 4];
+        b.copy_from_slice(&route.oif_index.to_ne_bytes());
+        attrs.push(RouteAttr::new(libc::RTA_OIF, &b));
+    }
+
+    if let Some(dst) = route.dst {
+        let (family, dst_data) = match dst {
+            IpNet::V4(ip) => (libc::AF_INET, ip.addr().octets().to_vec()),
+            IpNet::V6(ip) => (libc::AF_INET6, ip.addr().octets().to_vec()),
+        };
+        msg.family = family as u8;
+        msg.dst_len = dst.prefix_len();
+
+        attrs.push(RouteAttr::new(libc::RTA_DST, &dst_data));
+    }
+
+    if let Some(src) = route.src {
+        let (family, src_data) = match src {
+            IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+        };
+
+        if msg.family == 0 {
+            msg.family = family as u8;
+        } else if msg.family != family as u8 {
+            bail!("src and dst address family mismatch");
         }
 
-        if let Some(src) = route.src {
-            let (family, src_data) = match src {
-                IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
-                IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
-            };
+        attrs.push(RouteAttr::new(libc::RTA_PREFSRC, &src_data));
+    }
 
-            if msg.family == 0 {
-                msg.family = family as u8;
-            } else if msg.family != family as u8 {
-                bail!("src and dst address family mismatch");
-            }
+    if let Some(gw) = route.gw {
+        let (family, gw_data) = match gw {
+            IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+        };
 
-            attrs.push(RouteAttr::new(libc::RTA_PREFSRC, &src_data));
+        if msg.family == 0 {
+            msg.family = family as u8;
+        } else if msg.family != family as u8 {
+            bail!("gw, src and dst address family mismatch");
         }
 
-        if let Some(gw) = route.gw {
-            let (family, gw_data) = match gw {
-                IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
-                IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+        attrs.push(RouteAttr::new(libc::RTA_GATEWAY, &gw_data));
+    }
+
+    if let Some(via) = &route.via {
+        attrs.push(RouteAttr::new(RTA_VIA, &via.encode()));
+    }
+
+    if !route.nexthops.is_empty() {
+        let mut multipath = vec![];
+
+        for nh in &route.nexthops {
+            let family = match nh.gateway {
+                IpAddr::V4(_) => libc::AF_INET,
+                IpAddr::V6(_) => libc::AF_INET6,
             };
 
             if msg.family == 0 {
                 msg.family = family as u8;
             } else if msg.family != family as u8 {
-                bail!("gw, src and dst address family mismatch");
+                bail!("nexthop and dst address family mismatch");
             }
 
-            attrs.push(RouteAttr::new(libc::RTA_GATEWAY, &gw_data));
+            multipath.extend(nh.encode()?);
         }
 
-        if let Some(via) = &route.via {
-            attrs.push(RouteAttr::new(RTA_VIA, &via.encode()));
-        }
+        attrs.push(RouteAttr::new(libc::RTA_MULTIPATH, &multipath));
+    }
 
-        if let Some(mtu) = route.mtu {
-            let mut b = [0; 4];
-            b.copy_from_slice(&mtu.to_ne_bytes());
-            attrs.push(RouteAttr::new(RTA_MTU, &b));
-        }
+    if let Some(mtu) = route.mtu {
+        let mut b = [0; 4];
+        b.copy_from_slice(&mtu.to_ne_bytes());
+        attrs.push(RouteAttr::new(RTA_MTU, &b));
+    }
 
-        // TODO: more attributes to be added
+    if let Some(priority) = route.priority {
+        attrs.push(RouteAttr::u32(libc::RTA_PRIORITY, priority));
+    }
 
-        msg.flags = route.flags;
-        msg.scope = route.scope;
+    // TODO: more attributes to be added
 
-        req.add(&msg.serialize()?);
+    msg.flags = route.flags;
+    msg.scope = route.scope;
 
-        for attr in attrs {
-            req.add(&attr.serialize()?);
-        }
+    req.add(&msg.serialize()?);
 
-        self.request(&mut req, 0)?;
-
-        Ok(())
+    for attr in attrs {
+        req.add(&attr.serialize()?);
     }
 
-    pub fn get(&mut self, dst: &IpAddr) -> Result<Vec<Routing>> {
-        let mut req = Message::new(libc::RTM_GETROUTE, libc::NLM_F_REQUEST);
-        let (family, dst_data, bit_len) = match dst {
-            IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec(), 32),
-            IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec(), 128),
-        };
+    Ok(req)
+}
 
-        let mut msg = RouteMessage {
-            ..Default::default()
-        };
+/// An `RTM_GETROUTE` that looks up the route to `dst` the way a packet
+/// would be routed, rather than filtering a dump.
+fn lookup_request(dst: &IpAddr) -> Result<Message> {
+    let mut req = Message::new(libc::RTM_GETROUTE, libc::NLM_F_REQUEST);
+    let (family, dst_data, bit_len) = match dst {
+        IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec(), 32),
+        IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec(), 128),
+    };
 
-        msg.family = family as u8;
-        msg.dst_len = bit_len;
-        msg.flags = RTM_F_LOOKUP_TABLE;
+    let msg = RouteMessage {
+        family: family as u8,
+        dst_len: bit_len,
+        flags: RTM_F_LOOKUP_TABLE,
+        ..Default::default()
+    };
 
-        let rta_dst = RouteAttr::new(libc::RTA_DST, &dst_data);
+    req.add(&msg.serialize()?);
+    req.add(&RouteAttr::new(libc::RTA_DST, &dst_data).serialize()?);
 
-        req.add(&msg.serialize()?);
-        req.add(&rta_dst.serialize()?);
+    Ok(req)
+}
 
-        Ok(self
-            .request(&mut req, libc::RTM_NEWROUTE)?
-            .into_iter()
-            .map(|m| Routing::from(m.as_slice()))
-            .collect())
+/// Describes `route` in errors, e.g. `dst 10.0.0.0/24`.
+fn route_context(route: &Routing) -> String {
+    match route.dst {
+        Some(dst) => format!("dst {}", dst),
+        None => "dst default".to_string(),
     }
 }
 
+/// Returns the family of the route in `msg` if it is a default route of the
+/// main table being added or removed.
+fn default_route_family(msg: &Message) -> Option<i32> {
+    match msg.header.nlmsg_type {
+        libc::RTM_NEWROUTE | libc::RTM_DELROUTE => {
+            let rt_msg = RouteMessage::deserialize(msg.payload.as_deref()?).ok()?;
+            (rt_msg.dst_len == 0 && rt_msg.table == libc::RT_TABLE_MAIN)
+                .then_some(rt_msg.family as i32)
+        }
+        _ => None,
+    }
+}
+
+/// Looks up the default gateways of `families` again on `handle` and returns
+/// the ones that differ from the ones last seen in `gws`, which is updated
+/// accordingly. A notification only describes one route, so the preferred
+/// one is dumped instead of being derived from it.
+fn refresh_default_gws(
+    handle: &mut SocketHandle,
+    gws: &mut HashMap<i32, Option<IpAddr>>,
+    families: &[i32],
+) -> Vec<Result<Option<IpAddr>>> {
+    families
+        .iter()
+        .filter_map(|&family| match handle.handle_route().default_gw(family) {
+            Ok(gw) if gws.insert(family, gw) != Some(gw) => Some(Ok(gw)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        test_setup,
-        types::{link::LinkAttrs, routing::Via},
+        core::{hexdump::HexDump, mock::MockTransport},
+        skip_unless_supported, test_setup,
+        types::{
+            addr::{AddrFamily, AddressBuilder},
+            link::{LinkAttrs, VethOpts},
+            nexthop::NexthopGroupMember,
+            routing::Nexthop,
+            seg6::RTA_ENCAP,
+            NLA_F_NESTED,
+        },
     };
 
     use super::*;
 
+    const RTNH_F_ONLINK: u32 = 0x4;
+
+    /// Probes for seg6local with an encap lacking its action. The kernel
+    /// rejects it with `EOPNOTSUPP` without SRv6 support and with `EINVAL`
+    /// otherwise, so no route is left behind.
+    fn probe_seg6local(handle: &mut SocketHandle) -> Result<()> {
+        let mut req = Message::new(
+            libc::RTM_NEWROUTE,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        );
+
+        let msg = RouteMessage {
+            family: libc::AF_INET6 as u8,
+            dst_len: 128,
+            ..RouteMessage::new()
+        };
+
+        req.add(&msg.serialize()?);
+        req.add(&RouteAttr::new(libc::RTA_DST, &Ipv6Addr::LOCALHOST.octets()).serialize()?);
+        req.add(&RouteAttr::u32(libc::RTA_OIF, LOOPBACK_IFINDEX).serialize()?);
+        req.add(&RouteAttr::u16(RTA_ENCAP_TYPE, LWTUNNEL_ENCAP_SEG6_LOCAL).serialize()?);
+        req.add(&RouteAttr::new(RTA_ENCAP | NLA_F_NESTED, &[]).serialize()?);
+
+        handle.request(&mut req, 0)?;
+
+        Ok(())
+    }
+
+    #[rustfmt::skip]
+    static NEWROUTE_REQ: [u8; 52] = [
+        0x34, 0x00, 0x00, 0x00, // nlmsg_len = 52
+        0x18, 0x00, // nlmsg_type = RTM_NEWROUTE
+        0x05, 0x06, // nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK | NLM_F_EXCL | NLM_F_CREATE
+        0x01, 0x00, 0x00, 0x00, // nlmsg_seq = 1
+        0x01, 0x00, 0x00, 0x00, // nlmsg_pid = 1
+        0x02, 0x18, 0x00, 0x00, // family = AF_INET, dst_len = 24, src_len = 0, tos = 0
+        0xfe, 0x03, 0x00, 0x01, // table = RT_TABLE_MAIN, protocol = RTPROT_BOOT, scope = RT_SCOPE_UNIVERSE, type = RTN_UNICAST
+        0x00, 0x00, 0x00, 0x00, // flags = 0
+        0x08, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, // RTA_OIF = 1
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00, // RTA_DST = 10.0.0.0
+        0x08, 0x00, 0x07, 0x00, 0x7f, 0x00, 0x00, 0x02, // RTA_PREFSRC = 127.0.0.2
+    ];
+
+    #[test]
+    fn test_route_handle_mock_serialize() {
+        let mock = MockTransport::default();
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let route = Routing {
+            oif_index: 1,
+            dst: Some("10.0.0.0/24".parse().unwrap()),
+            src: Some("127.0.0.2".parse().unwrap()),
+            ..Default::default()
+        };
+
+        handle
+            .handle_route()
+            .handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let sent = handle.socket.sent();
+        assert_eq!(
+            sent,
+            vec![NEWROUTE_REQ.to_vec()],
+            "sent:\n{}",
+            HexDump(&sent[0])
+        );
+    }
+
+    #[rustfmt::skip]
+    static GETROUTE_UID_REQ: [u8; 44] = [
+        0x2c, 0x00, 0x00, 0x00, // nlmsg_len = 44
+        0x1a, 0x00, // nlmsg_type = RTM_GETROUTE
+        0x01, 0x00, // nlmsg_flags = NLM_F_REQUEST
+        0x01, 0x00, 0x00, 0x00, // nlmsg_seq = 1
+        0x01, 0x00, 0x00, 0x00, // nlmsg_pid = 1
+        0x02, 0x20, 0x00, 0x00, // family = AF_INET, dst_len = 32, src_len = 0, tos = 0
+        0x00, 0x00, 0x00, 0x00, // table = 0, protocol = 0, scope = 0, type = 0
+        0x00, 0x10, 0x00, 0x00, // flags = RTM_F_LOOKUP_TABLE
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x01, // RTA_DST = 10.0.0.1
+        0x08, 0x00, 0x19, 0x00, 0xe8, 0x03, 0x00, 0x00, // RTA_UID = 1000
+    ];
+
+    #[test]
+    fn test_route_get_by_uid_mock() {
+        let msg = RouteMessage {
+            family: libc::AF_INET as u8,
+            dst_len: 32,
+            table: libc::RT_TABLE_MAIN,
+            ..Default::default()
+        };
+
+        let mut res = Message::new(libc::RTM_NEWROUTE, 0);
+        res.add(&msg.serialize().unwrap());
+        res.add(&RouteAttr::u32(libc::RTA_OIF, 2).serialize().unwrap());
+
+        let mock = MockTransport::default();
+        mock.push_response(&res.serialize().unwrap());
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let route = handle
+            .handle_route()
+            .get_by_uid("10.0.0.1".parse().unwrap(), 1000)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(route.oif_index, 2);
+
+        let sent = handle.socket.sent();
+        assert_eq!(
+            sent,
+            vec![GETROUTE_UID_REQ.to_vec()],
+            "sent:\n{}",
+            HexDump(&sent[0])
+        );
+    }
+
     #[test]
-    fn test_route_handle() {
+    fn test_route_batch_mock() {
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL;
+        let mock = MockTransport::default();
+
+        for chunk in (1..=1000u32).collect::<Vec<_>>().chunks(128) {
+            let mut acks = vec![];
+            for &seq in chunk {
+                let err_no = if seq == 500 { -libc::EEXIST } else { 0 };
+                acks.extend_from_slice(&36u32.to_ne_bytes());
+                acks.extend_from_slice(&(libc::NLMSG_ERROR as u16).to_ne_bytes());
+                acks.extend_from_slice(&0u16.to_ne_bytes());
+                acks.extend_from_slice(&seq.to_ne_bytes());
+                acks.extend_from_slice(&1u32.to_ne_bytes());
+                acks.extend_from_slice(&err_no.to_ne_bytes());
+                acks.extend_from_slice(&[0; 16]);
+            }
+            mock.push_response_verbatim(&acks);
+        }
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let mut batch = Batch::new();
+
+        for i in 0..1000u32 {
+            let route = Routing {
+                oif_index: 1,
+                dst: Some(format!("10.{}.{}.0/24", i / 256, i % 256).parse().unwrap()),
+                ..Default::default()
+            };
+            handle
+                .handle_route()
+                .handle_batched(&mut batch, &route, libc::RTM_NEWROUTE, flags)
+                .unwrap();
+        }
+
+        let results = handle.send_batch(batch).unwrap();
+
+        // 1000 requests in 8 round trips instead of 1000.
+        assert_eq!(handle.socket.sent().len(), 8);
+        assert_eq!(results.len(), 1000);
+        assert!(results[499]
+            .as_ref()
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("File exists"));
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 999);
+    }
+
+    #[test]
+    fn test_route_batch() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
         let mut link_handle = handle.handle_link();
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
 
-        let attr = LinkAttrs::new("lo");
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL;
+        let mut batch = Batch::new();
+        let mut route_handle = handle.handle_route();
 
-        let link = link_handle.get(&attr).unwrap();
+        for i in 0..300u32 {
+            let route = Routing {
+                oif_index: link.attrs().index,
+                dst: Some(format!("10.{}.{}.0/24", i / 256, i % 256).parse().unwrap()),
+                ..Default::default()
+            };
+            route_handle
+                .handle_batched(&mut batch, &route, libc::RTM_NEWROUTE, flags)
+                .unwrap();
+        }
 
+        let dup = Routing {
+            oif_index: link.attrs().index,
+            dst: Some("10.0.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+        route_handle
+            .handle_batched(&mut batch, &dup, libc::RTM_NEWROUTE, flags)
+            .unwrap();
+
+        let results = route_handle.send_batch(batch).unwrap();
+
+        assert_eq!(results.len(), 301);
+        assert!(results[..300].iter().all(|r| r.is_ok()));
+        assert!(results[300].is_err());
+
+        let net: IpNet = "10.0.0.0/8".parse().unwrap();
+        let routes = route_handle.list(libc::AF_INET).unwrap();
+        assert_eq!(
+            routes
+                .iter()
+                .filter(|r| r.dst.is_some_and(|d| net.contains(&d)))
+                .count(),
+            300
+        );
+    }
+
+    #[test]
+    fn test_route_add_srv6_end_dx4() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
         link_handle.up(&link).unwrap();
 
-        let route = Routing {
+        skip_unless_supported!(probe_seg6local(&mut handle), "seg6local");
+
+        let sid: Ipv6Net = "fc00::6/128".parse().unwrap();
+        let mut route_handle = handle.handle_route();
+        route_handle
+            .add_srv6_end_dx4(sid, Ipv4Addr::new(10, 0, 0, 1), 100)
+            .unwrap();
+
+        let routes = route_handle.list(libc::AF_INET6).unwrap();
+        let route = routes
+            .iter()
+            .find(|r| r.dst == Some(IpNet::V6(sid)))
+            .unwrap();
+        assert_eq!(route.table, 100);
+        assert_eq!(route.oif_index, LOOPBACK_IFINDEX as i32);
+    }
+
+    #[test]
+    fn test_route_replace() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let mut route = Routing {
             oif_index: link.attrs().index,
             dst: Some("192.168.0.0/24".parse().unwrap()),
             src: Some("127.0.0.2".parse().unwrap()),
@@ -185,49 +788,159 @@ mod tests {
 
         let mut route_handle = handle.handle_route();
 
+        // Adds the route, as there is none to replace yet.
+        route_handle.replace(&route).unwrap();
+
+        route.src = Some("127.0.0.3".parse().unwrap());
+        route_handle.replace(&route).unwrap();
+
+        let routes = route_handle.list(libc::AF_INET).unwrap();
+        let found: Vec<_> = routes.iter().filter(|r| r.dst == route.dst).collect();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].src, route.src);
+    }
+
+    #[test]
+    fn test_route_get_by_uid() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let mut route_handle = handle.handle_route();
+
+        let route = route_handle
+            .get_by_uid("127.0.0.1".parse().unwrap(), 1000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(route.oif_index, link.attrs().index);
+
+        // A fresh namespace has no default route.
+        let route = route_handle
+            .get_by_uid("10.0.0.1".parse().unwrap(), 1000)
+            .unwrap();
+        assert!(route.is_none());
+    }
+
+    #[test]
+    fn test_route_multipath_and_append() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        link_handle
+            .add_veth("foo", "bar", VethOpts::default())
+            .unwrap();
+        link_handle.set_up("foo").unwrap();
+        link_handle.set_up("bar").unwrap();
+        let foo = link_handle.get_by_name("foo").unwrap().unwrap();
+        let ifindex = foo.attrs().index as u32;
+
+        let addr = AddressBuilder::default()
+            .ip("10.1.0.1/24".parse().unwrap())
+            .build()
+            .unwrap();
+        handle
+            .handle_addr()
+            .handle(
+                &foo,
+                &addr,
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let nexthops = vec![
+            Nexthop {
+                gateway: "10.1.0.2".parse().unwrap(),
+                ifindex,
+                weight: 1,
+            },
+            Nexthop {
+                gateway: "10.1.0.3".parse().unwrap(),
+                ifindex,
+                weight: 3,
+            },
+        ];
+
+        let ecmp = Routing {
+            dst: Some("192.168.10.0/24".parse().unwrap()),
+            nexthops: nexthops.clone(),
+            ..Default::default()
+        };
+
+        let mut route_handle = handle.handle_route();
         route_handle
             .handle(
-                &route,
+                &ecmp,
                 libc::RTM_NEWROUTE,
                 libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
             )
             .unwrap();
 
-        let routes = route_handle.get(&route.dst.unwrap().addr()).unwrap();
+        let routes = route_handle.list(libc::AF_INET).unwrap();
+        let found = routes.iter().find(|r| r.dst == ecmp.dst).unwrap();
+        assert_eq!(found.nexthops, nexthops);
 
-        assert_eq!(routes.len(), 1);
-        assert_eq!(routes[0].oif_index, link.attrs().index);
-        assert_eq!(
-            routes[0].dst.unwrap().network(),
-            route.dst.unwrap().network()
-        );
+        let dst = Some("192.168.20.0/24".parse().unwrap());
+        for gw in ["10.1.0.2", "10.1.0.3"] {
+            let route = Routing {
+                dst,
+                gw: Some(gw.parse().unwrap()),
+                oif_index: ifindex as i32,
+                ..Default::default()
+            };
+            route_handle.append(&route).unwrap();
+        }
 
+        let routes = route_handle.list(libc::AF_INET).unwrap();
+        let gws: Vec<_> = routes
+            .iter()
+            .filter(|r| r.dst == dst)
+            .map(|r| r.gw.unwrap().to_string())
+            .collect();
+        assert_eq!(gws, ["10.1.0.2", "10.1.0.3"]);
+
+        let routes = route_handle
+            .list_by_gateway("10.1.0.3".parse().unwrap(), AddrFamily::V4)
+            .unwrap();
+        assert!(routes.iter().any(|r| r.dst == ecmp.dst));
+        assert!(routes.iter().any(|r| r.dst == dst));
+
+        let default = Routing {
+            nexthops,
+            ..Default::default()
+        };
         route_handle
-            .handle(&route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .handle(
+                &default,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
             .unwrap();
 
-        let res = route_handle.get(&route.dst.unwrap().addr()).err();
-        assert!(res.is_some());
+        assert_eq!(
+            route_handle.default_gw(libc::AF_INET).unwrap(),
+            Some("10.1.0.2".parse().unwrap())
+        );
     }
 
     #[test]
-    fn test_route_handle_via() {
+    fn test_route_list_pref_src() {
         test_setup!();
         let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
         let mut link_handle = handle.handle_link();
 
-        let attr = LinkAttrs::new("lo");
-
-        let link = link_handle.get(&attr).unwrap();
-
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
         link_handle.up(&link).unwrap();
 
-        let via = Via::new("2001::1").unwrap();
-
         let route = Routing {
             oif_index: link.attrs().index,
             dst: Some("192.168.0.0/24".parse().unwrap()),
-            via: Some(via),
+            src: Some("127.0.0.2".parse().unwrap()),
             ..Default::default()
         };
 
@@ -241,20 +954,260 @@ mod tests {
             )
             .unwrap();
 
-        let routes = route_handle.get(&route.dst.unwrap().addr()).unwrap();
+        let routes = route_handle.list(libc::AF_INET).unwrap();
+        let found = routes.iter().find(|r| r.dst == route.dst).unwrap();
+
+        assert_eq!(found.src, route.src);
+        assert_eq!(found.table, libc::RT_TABLE_MAIN);
+        assert!(routes.iter().all(|r| r.family == libc::AF_INET as u8));
+    }
+
+    #[test]
+    fn test_route_list_sorted_by_metric() {
+        test_setup!();
+        let mut handle = super::SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let dst: IpNet = "192.168.0.0/24".parse().unwrap();
+        let mut route_handle = handle.handle_route();
+
+        for priority in [300, 100, 200] {
+            let route = Routing {
+                oif_index: link.attrs().index,
+                dst: Some(dst),
+                priority: Some(priority),
+                ..Default::default()
+            };
+
+            route_handle
+                .handle(
+                    &route,
+                    libc::RTM_NEWROUTE,
+                    libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+                )
+                .unwrap();
+        }
+
+        let routes = route_handle.list_sorted_by_metric(dst).unwrap();
+        let metrics: Vec<_> = routes.iter().map(|r| r.priority).collect();
+
+        assert_eq!(metrics, [Some(100), Some(200), Some(300)]);
+        assert!(route_handle
+            .list_sorted_by_metric("192.168.0.0/16".parse().unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_route_add_and_return() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let route = Routing {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let added = handle.handle_route().add_and_return(&route).unwrap();
+
+        assert_eq!(added.dst, route.dst);
+        assert_eq!(added.oif_index, route.oif_index);
+        assert_eq!(added.table, libc::RT_TABLE_MAIN);
+        assert_eq!(added.protocol, libc::RTPROT_BOOT);
+
+        assert!(handle.handle_route().add_and_return(&route).is_err());
+    }
+
+    #[test]
+    fn test_route_list_by_gateway() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let gw: IpAddr = "10.0.0.1".parse().unwrap();
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        let mut route_handle = handle.handle_route();
+
+        for (dst, gw) in [
+            ("192.168.0.0/24", gw),
+            ("192.168.1.0/24", gw),
+            ("192.168.2.0/24", "10.0.0.2".parse().unwrap()),
+        ] {
+            let route = Routing {
+                oif_index: link.attrs().index,
+                dst: Some(dst.parse().unwrap()),
+                gw: Some(gw),
+                flags: RTNH_F_ONLINK,
+                ..Default::default()
+            };
+            route_handle
+                .handle(&route, libc::RTM_NEWROUTE, flags)
+                .unwrap();
+        }
+
+        let mut dsts: Vec<_> = route_handle
+            .list_by_gateway(gw, AddrFamily::V4)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.dst.unwrap().to_string())
+            .collect();
+        dsts.sort();
+
+        assert_eq!(dsts, ["192.168.0.0/24", "192.168.1.0/24"]);
+        assert!(route_handle
+            .list_by_gateway(gw, AddrFamily::V6)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_route_error_context() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let route = Routing {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        let mut route_handle = handle.handle_route();
+        route_handle
+            .handle(&route, libc::RTM_NEWROUTE, flags)
+            .unwrap();
+        let err = route_handle
+            .handle(&route, libc::RTM_NEWROUTE, flags)
+            .unwrap_err();
 
-        assert_eq!(routes.len(), 1);
-        assert_eq!(routes[0].oif_index, link.attrs().index);
         assert_eq!(
-            routes[0].dst.unwrap().network(),
-            route.dst.unwrap().network()
+            err.to_string(),
+            "RTM_NEWROUTE (dst 192.168.0.0/24) failed: EEXIST (File exists)"
         );
+    }
+
+    #[test]
+    fn test_route_subscribe_default_gw() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let link = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&link).unwrap();
+
+        let mut route_handle = handle.handle_route();
+        let mut gws = route_handle.subscribe_default_gw(AddrFamily::V4).unwrap();
+
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+        let default_via = |gw: &str, priority| Routing {
+            oif_index: link.attrs().index,
+            dst: Some("0.0.0.0/0".parse().unwrap()),
+            gw: Some(gw.parse().unwrap()),
+            priority: Some(priority),
+            flags: RTNH_F_ONLINK,
+            ..Default::default()
+        };
 
+        // Routes other than the default route are ignored.
+        let route = Routing {
+            oif_index: link.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+        route_handle
+            .handle(&route, libc::RTM_NEWROUTE, flags)
+            .unwrap();
+
+        let primary = default_via("10.0.0.1", 100);
         route_handle
-            .handle(&route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .handle(&primary, libc::RTM_NEWROUTE, flags)
             .unwrap();
+        assert_eq!(
+            gws.next().unwrap().unwrap(),
+            Some("10.0.0.1".parse().unwrap())
+        );
+
+        let backup = default_via("10.0.0.2", 200);
+        route_handle
+            .handle(&backup, libc::RTM_NEWROUTE, flags)
+            .unwrap();
+        route_handle
+            .handle(&primary, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .unwrap();
+        assert_eq!(
+            gws.next().unwrap().unwrap(),
+            Some("10.0.0.2".parse().unwrap())
+        );
+
+        route_handle
+            .handle(&backup, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .unwrap();
+        assert_eq!(gws.next().unwrap().unwrap(), None);
+    }
+
+    #[test]
+    fn test_route_nexthop_objects() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+
+        let mut route_handle = handle.handle_route();
+        assert!(route_handle.list_nexthop_groups().unwrap().is_empty());
+
+        let nh = NexthopObject {
+            oif: Some(lo.attrs().index as u32),
+            ..Default::default()
+        };
+        let first = route_handle.add_nexthop_object(&nh).unwrap();
+        let second = route_handle
+            .add_nexthop_object(&NexthopObject { id: 100, ..nh })
+            .unwrap();
+        assert_ne!(first, 0);
+        assert_eq!(second, 100);
+
+        let group = NexthopObject {
+            id: 10,
+            group: vec![
+                NexthopGroupMember {
+                    id: first,
+                    weight: 1,
+                },
+                NexthopGroupMember {
+                    id: second,
+                    weight: 3,
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(route_handle.add_nexthop_object(&group).unwrap(), 10);
+
+        let groups = route_handle.list_nexthop_groups().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, 10);
+        assert_eq!(groups[0].group, group.group);
+        assert!(!groups[0].resilient);
 
-        let res = route_handle.get(&route.dst.unwrap().addr()).err();
-        assert!(res.is_some());
+        route_handle.del_nexthop_object(10).unwrap();
+        assert!(route_handle.list_nexthop_groups().unwrap().is_empty());
+        assert!(route_handle.del_nexthop_object(10).is_err());
     }
 }
@@ -0,0 +1,311 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        error::RequestContext,
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{message::RuleMessage, rule::Rule},
+};
+
+use super::{batch::Batch, sock_handle::SocketHandle};
+
+pub struct RuleHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for RuleHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for RuleHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for RuleHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> RuleHandle<'_, S> {
+    /// Sends `rule` as an `RTM_NEWRULE` or `RTM_DELRULE` request. A delete
+    /// removes the first rule that matches every field set in `rule`.
+    pub fn handle(&mut self, rule: &Rule, proto: u16, flags: i32) -> Result<()> {
+        let mut req = Message::new(proto, flags);
+        req.add(&rule.serialize()?);
+
+        self.request(&mut req, 0)
+            .request_context(|| rule_context(rule))?;
+
+        Ok(())
+    }
+
+    /// Like `handle`, but adds the request to `batch` instead of sending it,
+    /// e.g. to install many rules with `SocketHandle::send_batch`.
+    pub fn handle_batched(
+        &self,
+        batch: &mut Batch,
+        rule: &Rule,
+        proto: u16,
+        flags: i32,
+    ) -> Result<()> {
+        let mut req = Message::new(proto, flags);
+        req.add(&rule.serialize()?);
+        batch.add(req);
+
+        Ok(())
+    }
+
+    /// Adds an IPv4 rule that looks up `table` for packets whose fwmark,
+    /// masked with `mask`, equals `mark`, e.g. packets marked by a security
+    /// policy with `nfmark`.
+    pub fn add_fwmark_rule(
+        &mut self,
+        mark: u32,
+        mask: u32,
+        table: u32,
+        priority: u32,
+    ) -> Result<()> {
+        let rule = Rule {
+            priority: Some(priority),
+            table,
+            mark: Some(mark),
+            mask: Some(mask),
+            ..Default::default()
+        };
+
+        self.handle(
+            &rule,
+            libc::RTM_NEWRULE,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        )
+    }
+
+    /// Dumps the rules of `family`, or of all families with `AF_UNSPEC`.
+    pub fn list(&mut self, family: i32) -> Result<Vec<Rule>> {
+        let msg = RuleMessage {
+            family: family as u8,
+            ..Default::default()
+        };
+
+        self.dump(libc::RTM_GETRULE, libc::RTM_NEWRULE, &msg)
+    }
+}
+
+/// Describes `rule` in the errors of `RuleHandle::handle`.
+fn rule_context(rule: &Rule) -> String {
+    let mut context = vec![];
+
+    if let Some(priority) = rule.priority {
+        context.push(format!("priority {}", priority));
+    }
+
+    if rule.table > 0 {
+        context.push(format!("table {}", rule.table));
+    }
+
+    if rule.l3mdev {
+        context.push("l3mdev".to_string());
+    }
+
+    context.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{error::RequestError, hexdump::HexDump, mock::MockTransport},
+        test_setup,
+        types::rule::FR_ACT_TO_TBL,
+    };
+
+    use super::*;
+
+    #[rustfmt::skip]
+    static NEWRULE_REQ: [u8; 44] = [
+        0x2c, 0x00, 0x00, 0x00, // nlmsg_len = 44
+        0x20, 0x00, // nlmsg_type = RTM_NEWRULE
+        0x05, 0x06, // nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK | NLM_F_EXCL | NLM_F_CREATE
+        0x01, 0x00, 0x00, 0x00, // nlmsg_seq = 1
+        0x01, 0x00, 0x00, 0x00, // nlmsg_pid = 1
+        0x02, 0x00, 0x00, 0x00, // family = AF_INET, dst_len = 0, src_len = 0, tos = 0
+        0x00, 0x00, 0x00, 0x01, // table = RT_TABLE_UNSPEC, reserved, action = FR_ACT_TO_TBL
+        0x00, 0x00, 0x00, 0x00, // flags = 0
+        0x08, 0x00, 0x06, 0x00, 0xe8, 0x03, 0x00, 0x00, // FRA_PRIORITY = 1000
+        0x05, 0x00, 0x13, 0x00, 0x01, 0x00, 0x00, 0x00, // FRA_L3MDEV = 1
+    ];
+
+    #[test]
+    fn test_rule_handle_mock_serialize() {
+        let mock = MockTransport::default();
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let rule = Rule {
+            priority: Some(1000),
+            l3mdev: true,
+            ..Default::default()
+        };
+
+        handle
+            .handle_rule()
+            .handle(
+                &rule,
+                libc::RTM_NEWRULE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let sent = handle.socket.sent();
+        assert_eq!(
+            sent,
+            vec![NEWRULE_REQ.to_vec()],
+            "sent:\n{}",
+            HexDump(&sent[0])
+        );
+    }
+
+    #[test]
+    fn test_rule_handle() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut rule_handle = handle.handle_rule();
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        let rule = Rule {
+            priority: Some(100),
+            table: 250,
+            src: Some("10.0.0.0/8".parse().unwrap()),
+            mark: Some(0x10),
+            ..Default::default()
+        };
+        let l3mdev = Rule {
+            priority: Some(1000),
+            l3mdev: true,
+            ..Default::default()
+        };
+
+        rule_handle.handle(&rule, libc::RTM_NEWRULE, flags).unwrap();
+        rule_handle
+            .handle(&l3mdev, libc::RTM_NEWRULE, flags)
+            .unwrap();
+
+        let rules = rule_handle.list(libc::AF_INET).unwrap();
+
+        let found = rules.iter().find(|r| r.priority == Some(100)).unwrap();
+        assert_eq!(found.table, 250);
+        assert_eq!(found.src, rule.src);
+        assert_eq!(found.mark, Some(0x10));
+        assert_eq!(found.action, FR_ACT_TO_TBL);
+        assert!(!found.l3mdev);
+
+        let found = rules.iter().find(|r| r.priority == Some(1000)).unwrap();
+        assert!(found.l3mdev);
+        assert_eq!(found.table, 0);
+
+        let err = rule_handle
+            .handle(&rule, libc::RTM_NEWRULE, flags)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "RTM_NEWRULE (priority 100, table 250) failed: EEXIST (File exists)"
+        );
+        assert_eq!(
+            err.downcast_ref::<RequestError>().unwrap().errno(),
+            libc::EEXIST
+        );
+
+        rule_handle
+            .handle(&rule, libc::RTM_DELRULE, libc::NLM_F_ACK)
+            .unwrap();
+        rule_handle
+            .handle(&l3mdev, libc::RTM_DELRULE, libc::NLM_F_ACK)
+            .unwrap();
+
+        let rules = rule_handle.list(libc::AF_INET).unwrap();
+        assert!(!rules
+            .iter()
+            .any(|r| r.priority == Some(100) || r.priority == Some(1000)));
+    }
+
+    #[test]
+    fn test_rule_batch() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL;
+        let mut batch = Batch::new();
+        let rule_handle = handle.handle_rule();
+
+        for priority in 1000..2000 {
+            let rule = Rule {
+                priority: Some(priority),
+                table: 100,
+                ..Default::default()
+            };
+            rule_handle
+                .handle_batched(&mut batch, &rule, libc::RTM_NEWRULE, flags)
+                .unwrap();
+        }
+
+        let results = handle.send_batch(batch).unwrap();
+        assert!(results.iter().all(Result::is_ok));
+
+        let rules = handle.handle_rule().list(libc::AF_INET).unwrap();
+        assert_eq!(rules.iter().filter(|r| r.table == 100).count(), 1000);
+    }
+
+    #[test]
+    fn test_rule_add_fwmark_rule() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut rule_handle = handle.handle_rule();
+
+        rule_handle
+            .add_fwmark_rule(0x100, 0xff00, 200, 300)
+            .unwrap();
+
+        let rules = rule_handle.list(libc::AF_INET).unwrap();
+        let rule = rules.iter().find(|r| r.priority == Some(300)).unwrap();
+
+        assert_eq!(rule.mark, Some(0x100));
+        assert_eq!(rule.mask, Some(0xff00));
+        assert_eq!(rule.table, 200);
+    }
+
+    #[test]
+    fn test_rule_handle_ip_proto() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut rule_handle = handle.handle_rule();
+
+        let rule = Rule {
+            priority: Some(400),
+            table: 100,
+            ..Default::default()
+        }
+        .with_l4_proto(libc::IPPROTO_UDP as u8);
+
+        rule_handle
+            .handle(
+                &rule,
+                libc::RTM_NEWRULE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let rules = rule_handle.list(libc::AF_INET).unwrap();
+        let found = rules.iter().find(|r| r.priority == Some(400)).unwrap();
+
+        assert_eq!(found.ip_proto, libc::IPPROTO_UDP as u8);
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    fs,
+    ops::{ControlFlow, Deref, DerefMut},
+};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        message::{Message, NlMsg},
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::Attribute,
+        sock_diag::{
+            DiagFamily, DiagSummary, InetDiagTcpResp, Protocol, SctpDiagResp, SockDiagId,
+            SockDiagReq, INET_DIAG_EXT_MEMINFO, INET_DIAG_EXT_SKMEMINFO, SOCK_DIAG_BY_FAMILY,
+            TCP_TIME_WAIT,
+        },
+    },
+};
+
+use super::sock_handle::SocketHandle;
+
+const SOCKSTAT: &str = "/proc/net/sockstat";
+
+pub struct SockDiagHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for SockDiagHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for SockDiagHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for SockDiagHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> SockDiagHandle<'_, S> {
+    pub fn tcp_info(&mut self, family: DiagFamily) -> Result<Vec<InetDiagTcpResp>> {
+        self.dump_diag(&SockDiagReq::request_tcp_info(family))
+    }
+
+    /// Dumps the IPv4 and then the IPv6 TCP sockets, back to back. A socket
+    /// runs one dump at a time, so the two cannot overlap, but nothing else
+    /// is done between them.
+    pub fn tcp_info_both(&mut self) -> Result<(Vec<InetDiagTcpResp>, Vec<InetDiagTcpResp>)> {
+        let v4 = self.tcp_info(DiagFamily::V4)?;
+        let v6 = self.tcp_info(DiagFamily::V6)?;
+
+        Ok((v4, v6))
+    }
+
+    /// Like `tcp_info`, but only asks for the extension attributes selected
+    /// by `ext_mask`, a combination of the `INET_DIAG_EXT_*` bits. Leaving
+    /// out the attributes that are not needed reduces the size of the dump.
+    pub fn tcp_info_attrs(
+        &mut self,
+        family: DiagFamily,
+        ext_mask: u8,
+    ) -> Result<Vec<InetDiagTcpResp>> {
+        let mut req = SockDiagReq::new(family, Protocol::Tcp);
+        req.ext = ext_mask;
+
+        self.dump_diag(&req)
+    }
+
+    pub fn udp_info(&mut self, family: DiagFamily) -> Result<Vec<InetDiagTcpResp>> {
+        self.dump_diag(&SockDiagReq::request_udp_info(family))
+    }
+
+    /// Returns the UDP sockets whose receive queue uses at least
+    /// `threshold_percent` of their receive buffer, together with the used
+    /// fraction, e.g. to spot sockets that are about to drop datagrams. The
+    /// buffer size is taken from `INET_DIAG_SKMEMINFO`, so the sockets do
+    /// not have to be owned by the caller.
+    pub fn udp_rx_queue_pressure(
+        &mut self,
+        family: DiagFamily,
+        threshold_percent: u8,
+    ) -> Result<Vec<(SockDiagId, f64)>> {
+        let mut req = SockDiagReq::new(family, Protocol::Udp);
+        req.ext = INET_DIAG_EXT_MEMINFO | INET_DIAG_EXT_SKMEMINFO;
+
+        let threshold = f64::from(threshold_percent) / 100.0;
+
+        Ok(self
+            .dump_diag::<InetDiagTcpResp>(&req)?
+            .into_iter()
+            .filter_map(|resp| {
+                let skmem = resp.skmem?;
+                if skmem.rcvbuf == 0 {
+                    return None;
+                }
+                let rmem = resp.mem.map_or(skmem.rmem_alloc, |mem| mem.rmem);
+                let pressure = f64::from(rmem) / f64::from(skmem.rcvbuf);
+                (pressure >= threshold).then_some((resp.msg.id, pressure))
+            })
+            .collect())
+    }
+
+    /// Dumps SCTP endpoints and associations, like `ss -S`. Requires the
+    /// `sctp_diag` module.
+    pub fn sctp_info(&mut self, family: DiagFamily) -> Result<Vec<SctpDiagResp>> {
+        self.dump_diag(&SockDiagReq::request_sctp_info(family))
+    }
+
+    /// Looks up a socket by inode, e.g. one taken from `/proc/net/tcp` or
+    /// `/proc/<pid>/fd`. The kernel cannot filter on inode, so both address
+    /// families are dumped and matched here.
+    pub fn by_inode(&mut self, inode: u32, protocol: Protocol) -> Result<Option<InetDiagTcpResp>> {
+        for family in [DiagFamily::V4, DiagFamily::V6] {
+            let req = match protocol {
+                Protocol::Tcp => SockDiagReq::request_tcp_info(family),
+                Protocol::Udp => SockDiagReq::request_udp_info(family),
+                Protocol::Sctp => SockDiagReq::new(family, protocol),
+            };
+
+            let mut found = None;
+
+            self.dump_with(&req, |resp| {
+                if resp.msg.inode != inode {
+                    return ControlFlow::Continue(());
+                }
+                found = Some(resp);
+                ControlFlow::Break(())
+            })?;
+
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Summarizes the TCP sockets, like `ss -s`. The total and `TIME_WAIT`
+    /// counts come from a dump of both address families, the orphaned and
+    /// allocated sockets and the memory, which the dump does not expose,
+    /// from `/proc/net/sockstat`.
+    pub fn summary(&mut self) -> Result<DiagSummary> {
+        let mut summary = DiagSummary::from_sockstat(&fs::read_to_string(SOCKSTAT)?)?;
+
+        let (mut total, mut tw) = (0, 0);
+
+        for family in [DiagFamily::V4, DiagFamily::V6] {
+            self.dump_with(&SockDiagReq::new(family, Protocol::Tcp), |resp| {
+                total += 1;
+                if resp.msg.state == TCP_TIME_WAIT {
+                    tw += 1;
+                }
+                ControlFlow::Continue(())
+            })?;
+        }
+
+        summary.tcp_total = total;
+        summary.tcp_tw = tw;
+
+        Ok(summary)
+    }
+
+    /// Dumps the sockets matching `diag_req`. Every response must be a
+    /// `SOCK_DIAG_BY_FAMILY` message, anything else is an error rather than
+    /// being parsed as a socket.
+    fn dump_diag<Resp>(&mut self, diag_req: &SockDiagReq) -> Result<Vec<Resp>>
+    where
+        Resp: for<'b> TryFrom<&'b [u8], Error = anyhow::Error>,
+    {
+        self.dump_strict(SOCK_DIAG_BY_FAMILY, SOCK_DIAG_BY_FAMILY, diag_req)
+    }
+
+    fn dump_with<F>(&mut self, diag_req: &SockDiagReq, mut f: F) -> Result<()>
+    where
+        F: FnMut(InetDiagTcpResp) -> ControlFlow<()>,
+    {
+        let mut req = Message::new(SOCK_DIAG_BY_FAMILY, libc::NLM_F_DUMP);
+        req.add(&diag_req.serialize()?);
+
+        let mut err = None;
+
+        self.request_with_header(&mut req, 0, |header, payload| {
+            let msg = NlMsg {
+                header: *header,
+                payload: payload.to_vec(),
+            };
+
+            match msg
+                .expect_type(SOCK_DIAG_BY_FAMILY)
+                .and_then(InetDiagTcpResp::try_from)
+            {
+                Ok(resp) => f(resp),
+                Err(e) => {
+                    err = Some(e);
+                    ControlFlow::Break(())
+                }
+            }
+        })?;
+
+        match err {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{TcpListener, UdpSocket},
+        os::fd::{AsRawFd, FromRawFd},
+    };
+
+    use crate::{
+        core::mock::MockTransport, skip_unless_supported, test_setup, types::link::LinkAttrs,
+    };
+
+    use super::*;
+
+    fn socket_inode(fd: i32) -> u32 {
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { libc::fstat(fd, &mut stat) }, 0);
+        stat.st_ino as u32
+    }
+
+    #[test]
+    fn test_sock_diag_by_inode() {
+        test_setup!();
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let inode = socket_inode(listener.as_raw_fd());
+
+        let mut handle = SocketHandle::new(libc::NETLINK_SOCK_DIAG);
+        let mut diag_handle = handle.handle_sock_diag();
+
+        let resp = diag_handle.by_inode(inode, Protocol::Tcp).unwrap().unwrap();
+
+        assert_eq!(resp.msg.inode, inode);
+        assert_eq!(resp.msg.id.src_port, port);
+        assert_eq!(resp.msg.state, 10);
+
+        assert!(diag_handle
+            .by_inode(inode, Protocol::Udp)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_sock_diag_summary() {
+        test_setup!();
+        let _v4 = TcpListener::bind("0.0.0.0:0").unwrap();
+        let _v6 = TcpListener::bind("[::]:0").unwrap();
+
+        let mut handle = SocketHandle::new(libc::NETLINK_SOCK_DIAG);
+        let summary = handle.handle_sock_diag().summary().unwrap();
+
+        assert_eq!(summary.tcp_total, 2);
+        assert_eq!(summary.tcp_tw, 0);
+        assert!(summary.tcp_allocated >= 2);
+    }
+
+    #[test]
+    fn test_sock_diag_sctp_info() {
+        test_setup!();
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, libc::IPPROTO_SCTP) };
+        if fd < 0 {
+            let e = std::io::Error::last_os_error();
+            assert_eq!(e.raw_os_error(), Some(libc::EPROTONOSUPPORT), "{e}");
+            eprintln!("test skipped, requires sctp");
+            return;
+        }
+        let sock = unsafe { std::os::fd::OwnedFd::from_raw_fd(fd) };
+
+        let addr = libc::sockaddr_in {
+            sin_family: libc::AF_INET as u16,
+            sin_port: 9899u16.to_be(),
+            sin_addr: libc::in_addr {
+                s_addr: u32::from_ne_bytes([127, 0, 0, 1]),
+            },
+            sin_zero: [0; 8],
+        };
+        let len = std::mem::size_of_val(&addr) as u32;
+        assert_eq!(
+            unsafe { libc::bind(fd, &addr as *const _ as *const libc::sockaddr, len) },
+            0
+        );
+        assert_eq!(unsafe { libc::listen(fd, 1) }, 0);
+        let inode = socket_inode(sock.as_raw_fd());
+
+        let mut handle = SocketHandle::new(libc::NETLINK_SOCK_DIAG);
+        skip_unless_supported!(
+            handle.handle_sock_diag().sctp_info(DiagFamily::V6),
+            "sctp_diag"
+        );
+
+        let resps = handle.handle_sock_diag().sctp_info(DiagFamily::V4).unwrap();
+
+        let resp = resps.iter().find(|r| r.msg.inode == inode).unwrap();
+        assert_eq!(resp.msg.state, 10);
+        assert_eq!(resp.locals, ["127.0.0.1:9899".parse().unwrap()]);
+        assert!(resp.peers.is_empty());
+    }
+
+    #[test]
+    fn test_sock_diag_tcp_info_both() {
+        test_setup!();
+        let v4 = TcpListener::bind("127.0.0.1:0").unwrap();
+        let v6 = TcpListener::bind("[::]:0").unwrap();
+        let (v4_inode, v6_inode) = (socket_inode(v4.as_raw_fd()), socket_inode(v6.as_raw_fd()));
+
+        let mut handle = SocketHandle::new(libc::NETLINK_SOCK_DIAG);
+        let (v4_resps, v6_resps) = handle.handle_sock_diag().tcp_info_both().unwrap();
+
+        assert!(v4_resps.iter().any(|r| r.msg.inode == v4_inode));
+        assert!(!v4_resps.iter().any(|r| r.msg.inode == v6_inode));
+        assert!(v6_resps.iter().any(|r| r.msg.inode == v6_inode));
+        assert!(!v6_resps.iter().any(|r| r.msg.inode == v4_inode));
+    }
+
+    #[test]
+    fn test_sock_diag_tcp_info_attrs() {
+        test_setup!();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let inode = socket_inode(listener.as_raw_fd());
+
+        let mut handle = SocketHandle::new(libc::NETLINK_SOCK_DIAG);
+        let resps = handle
+            .handle_sock_diag()
+            .tcp_info_attrs(DiagFamily::V4, INET_DIAG_EXT_MEMINFO)
+            .unwrap();
+
+        let resp = resps.iter().find(|r| r.msg.inode == inode).unwrap();
+        assert!(resp.mem.is_some());
+        assert!(resp.tcp_info.is_none());
+        assert!(resp.congestion.is_none());
+        assert!(resp.vegas.is_none() && resp.bbr.is_none());
+    }
+
+    #[test]
+    fn test_sock_diag_udp_rx_queue_pressure() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+
+        let rx = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let size = 4096i32;
+        assert_eq!(
+            unsafe {
+                libc::setsockopt(
+                    rx.as_raw_fd(),
+                    libc::SOL_SOCKET,
+                    libc::SO_RCVBUF,
+                    &size as *const _ as *const libc::c_void,
+                    4,
+                )
+            },
+            0
+        );
+        let port = rx.local_addr().unwrap().port();
+
+        let tx = UdpSocket::bind("127.0.0.1:0").unwrap();
+        tx.send_to(&[0; 512], rx.local_addr().unwrap()).unwrap();
+
+        let mut handle = SocketHandle::new(libc::NETLINK_INET_DIAG);
+        let mut diag = handle.handle_sock_diag();
+
+        let busy = diag.udp_rx_queue_pressure(DiagFamily::V4, 1).unwrap();
+        let (_, pressure) = busy.iter().find(|(id, _)| id.src_port == port).unwrap();
+        assert!(*pressure > 0.0 && *pressure <= 1.0);
+
+        let idle = diag.udp_rx_queue_pressure(DiagFamily::V4, 100).unwrap();
+        assert!(idle.iter().all(|(id, _)| id.src_port != port));
+    }
+
+    #[test]
+    fn test_sock_diag_mock_replay() {
+        // A listener on 127.0.0.1:8080 and both ends of one connection to it.
+        let mock = MockTransport::default();
+        mock.push_response(include_bytes!("../../tests/fixtures/inet_diag_tcp_v4.bin"));
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let resps = handle.handle_sock_diag().tcp_info(DiagFamily::V4).unwrap();
+
+        let req = &handle.socket.sent()[0];
+        assert_eq!(u16::from_ne_bytes([req[4], req[5]]), SOCK_DIAG_BY_FAMILY);
+
+        assert_eq!(resps.len(), 3);
+        assert!(resps
+            .iter()
+            .any(|r| r.msg.state == 10 && r.msg.id.src_port == 8080));
+        assert_eq!(resps.iter().filter(|r| r.msg.state == 1).count(), 2);
+        assert!(resps
+            .iter()
+            .all(|r| r.tcp_info.is_some() && r.congestion.is_some()));
+    }
+
+    #[test]
+    fn test_sock_diag_rejects_unexpected_type() {
+        let mut newlink = vec![0; 32];
+        newlink[..4].copy_from_slice(&32u32.to_ne_bytes());
+        newlink[4..6].copy_from_slice(&libc::RTM_NEWLINK.to_ne_bytes());
+        newlink[6..8].copy_from_slice(&(libc::NLM_F_MULTI as u16).to_ne_bytes());
+
+        let mock = MockTransport::default();
+        mock.push_response(&newlink);
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let err = handle
+            .handle_sock_diag()
+            .tcp_info(DiagFamily::V4)
+            .err()
+            .unwrap();
+
+        assert!(err.to_string().contains("unexpected netlink message type"));
+    }
+}
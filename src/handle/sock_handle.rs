@@ -1,107 +1,1360 @@
+use std::{
+    ops::ControlFlow,
+    os::fd::{AsRawFd, IntoRawFd, RawFd},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
 use anyhow::{bail, Result};
 
-use crate::core::{message::Message, socket::Socket};
+use crate::align_of;
+#[cfg(feature = "trace")]
+use crate::core::hexdump::HexDump;
+use crate::{
+    core::{
+        error::{digest, RequestError},
+        message::{Header, Message, Messages, NlFlags, NlMsg},
+        socket::{recv_error, recv_retry, send_retry, NetlinkTransport, Socket, RECV_BUF_SIZE},
+    },
+    types::message::{Attribute, RouteAttrs},
+};
 
 use super::{
-    addr::AddrHandle, generic::GenericHandle, link::LinkHandle, neigh::NeighHandle,
-    routing::RouteHandle,
+    addr::AddrHandle, batch::Batch, bpf::BpfHandle, conntrack::ConntrackHandle,
+    ethtool::EthtoolHandle, fou::FouHandle, generic::GenericHandle, link::LinkHandle,
+    mptcp::MptcpHandle, ndt::NdtHandle, neigh::NeighHandle, nft::NftHandle, nl80211::Nl80211Handle,
+    qdisc::QdiscHandle, routing::RouteHandle, rule::RuleHandle, sock_diag::SockDiagHandle,
+    tc::TcHandle, xfrm::XfrmHandle,
 };
 
+pub use crate::core::socket::MAX_RECV_BUF_SIZE;
+
 const PID_KERNEL: u32 = 0;
 
 const NLMSG_DONE: u16 = 3;
 const NLMSG_ERROR: u16 = 2;
 
-#[derive(Clone)]
-pub struct SocketHandle {
-    pub socket: Socket,
-    pub seq: u32,
+const NLM_F_CAPPED: u16 = 0x100;
+const NLM_F_ACK_TLVS: u16 = 0x200;
+const NLMSGERR_ATTR_MSG: u16 = 1;
+const NLMSG_HDRLEN: usize = 16;
+const NLMSG_ALIGNTO: usize = 4;
+
+/// A batch is split into several `send` calls so that neither the request
+/// exceeds the send buffer nor the acks overrun the receive buffer.
+const MAX_BATCH_BYTES: usize = 32 << 10;
+const MAX_BATCH_MESSAGES: usize = 128;
+
+/// Controls how a request sent with `request_with_options` is acknowledged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestOptions {
+    /// Sets `NLM_F_ACK`, so the kernel confirms requests that otherwise
+    /// have no reply and the request only returns once it was processed.
+    pub ack: bool,
+    /// Sets `NLM_F_ECHO`, so the kernel sends back the object it created or
+    /// changed, including the fields it filled in such as an ifindex.
+    pub echo: bool,
+    /// Fails if the request succeeds without an echoed message, instead of
+    /// returning no messages.
+    pub require_echo: bool,
+    /// Enables extended acks on the socket, so errors carry the kernel's
+    /// error message and it stays enabled for later requests.
+    pub ext_ack: bool,
+}
+
+/// A netlink socket together with the state of its requests. The socket is
+/// closed when the handle is dropped, or explicitly with `close`. The
+/// protocol handles returned by `handle_link` and friends borrow the
+/// `SocketHandle` mutably, so none of them can outlive it.
+pub struct SocketHandle<S: NetlinkTransport = Socket> {
+    pub socket: S,
+    pub pid: u32,
+    /// The sequence number of the last request, shared with the handles
+    /// created by `try_clone`.
+    pub seq: Arc<AtomicU32>,
+    pub recv_timeout: Option<Duration>,
+    /// Requests fail instead of growing the receive buffer beyond this size.
+    pub max_recv_buf_size: usize,
+    /// The `RTMGRP_*` groups joined with `subscribe`.
+    pub groups: u32,
 }
 
 impl SocketHandle {
+    /// Opens a netlink socket of the given protocol. The socket is bound with
+    /// `nl_pid = 0` so the kernel assigns a port id that is unique within the
+    /// process, which lets any number of handles coexist; the assigned id is
+    /// recorded in `pid` and used to validate responses.
     pub fn new(proto: i32) -> Self {
-        Self {
-            socket: Socket::new(proto, 0, 0).unwrap(),
-            seq: 0,
-        }
+        Self::with_transport(Socket::new(proto, 0, 0).unwrap()).unwrap()
+    }
+
+    /// Like `new`, but clears the `FD_CLOEXEC` every other socket is opened
+    /// with, so programs started with `exec` inherit the socket, e.g. a
+    /// helper that keeps using it.
+    pub fn new_inheritable(proto: i32) -> Result<Self> {
+        let socket = Socket::new(proto, 0, 0)?;
+        socket.set_inheritable(true)?;
+
+        Self::with_transport(socket)
+    }
+
+    /// Returns a handle on a duplicate of the socket fd, e.g. to pass it to
+    /// a child process. Both handles are bound to the same kernel socket, so
+    /// they share its port id and receive queue, and they share the sequence
+    /// counter, so a reply can always be told apart from the replies to the
+    /// other handle. They can be used one after the other, but not for
+    /// concurrent requests.
+    pub fn try_clone(&self) -> Result<Self> {
+        let mut handle = Self::with_transport(self.socket.try_clone()?)?;
+        handle.seq = Arc::clone(&self.seq);
+        handle.recv_timeout = self.recv_timeout;
+        handle.max_recv_buf_size = self.max_recv_buf_size;
+        handle.groups = self.groups;
+
+        Ok(handle)
+    }
+
+    /// Closes the socket and reports the error of `close(2)`, which dropping
+    /// the handle ignores.
+    pub fn close(self) -> Result<()> {
+        Ok(self.socket.close()?)
+    }
+
+    /// Joins the multicast groups in the `RTMGRP_*` bitmask `groups`, e.g.
+    /// `RTMGRP_LINK | RTMGRP_IPV4_ROUTE`, replacing the groups joined before.
+    /// The notifications can be read with `raw_recv`. Requests can still be
+    /// sent on the socket, and they skip the notifications they receive.
+    pub fn subscribe(&mut self, groups: u32) -> Result<()> {
+        self.socket.subscribe(groups)?;
+        self.groups = groups;
+        Ok(())
+    }
+
+    /// Sets `SO_RCVTIMEO` on the underlying socket.
+    ///
+    /// The deadline applies to each individual `recv` call, not to a whole
+    /// request: a multi-part dump is read with several `recv` calls and the
+    /// timer restarts for every one of them, so a request only fails once the
+    /// kernel stays silent for longer than `timeout`. Callers that need to
+    /// bound the total duration of a request have to enforce that themselves.
+    /// An expired timeout is retried a few times with backoff before it
+    /// surfaces as an `EAGAIN` error from `request`.
+    pub fn set_recv_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.socket.set_recv_timeout(timeout)?;
+        self.recv_timeout = Some(timeout);
+        Ok(())
+    }
+
+    /// Builder-style variant of `set_recv_timeout`, e.g.
+    /// `SocketHandle::new(libc::NETLINK_ROUTE).with_timeout(timeout)?`.
+    ///
+    /// `SO_RCVTIMEO` only affects receiving, so sending a request never times
+    /// out. When the kernel does not answer in time, the request fails with
+    /// an `std::io::Error` of kind `ErrorKind::WouldBlock` wrapped in the
+    /// returned `anyhow::Error`, which can be recovered with `downcast_ref`.
+    pub fn with_timeout(&mut self, recv_timeout: Duration) -> Result<&mut Self> {
+        self.set_recv_timeout(recv_timeout)?;
+        Ok(self)
+    }
+
+    /// Sets `SO_RCVBUF` on the underlying socket, e.g. to dump huge routing
+    /// tables without the kernel dropping messages with `ENOBUFS`.
+    ///
+    /// The kernel doubles `size` to make room for its own bookkeeping, so the
+    /// socket reports twice the requested value, and it caps `size` at
+    /// `net.core.rmem_max`. 1 MiB is plenty for most uses, while downloading
+    /// a full BGP table is better done with 32 MiB.
+    pub fn with_recv_buf(&mut self, size: usize) -> Result<&mut Self> {
+        self.socket.set_recv_buf_size(size)?;
+        Ok(self)
+    }
+
+    /// Sets `SO_SNDBUF` on the underlying socket. Like `with_recv_buf`, the
+    /// kernel doubles `size` and caps it, here at `net.core.wmem_max`. A
+    /// larger send buffer allows bigger batches per `send`.
+    pub fn with_send_buf(&mut self, size: usize) -> Result<&mut Self> {
+        self.socket.set_send_buf_size(size)?;
+        Ok(self)
+    }
+
+    /// Sets `NETLINK_GET_STRICT_CHK` (Linux 4.20+), so the kernel validates
+    /// the header and attributes of `GET` requests and rejects malformed ones
+    /// with `EINVAL` instead of silently ignoring what it does not expect.
+    pub fn enable_strict_checking(&mut self) -> Result<()> {
+        Ok(self.socket.set_strict_checking(true)?)
+    }
+}
+
+impl AsRawFd for SocketHandle {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for SocketHandle {
+    /// Releases the fd without closing it, e.g. to hand it to an external
+    /// event loop that takes ownership.
+    fn into_raw_fd(self) -> RawFd {
+        self.socket.into_raw_fd()
+    }
+}
+
+impl<S: NetlinkTransport> SocketHandle<S> {
+    /// Wraps an already bound transport, e.g. a `MockTransport` in tests.
+    pub fn with_transport(socket: S) -> Result<Self> {
+        let pid = socket.pid()?;
+
+        Ok(Self {
+            socket,
+            pid,
+            seq: Arc::new(AtomicU32::new(0)),
+            recv_timeout: None,
+            max_recv_buf_size: MAX_RECV_BUF_SIZE,
+            groups: 0,
+        })
+    }
+
+    /// Returns the port id the socket is bound to, i.e. the `nl_pid` of its
+    /// `sockaddr_nl`, which is put into every request and expected in every
+    /// response.
+    pub fn pid(&self) -> u32 {
+        self.pid
     }
 
     pub fn next_seq(&mut self) -> u32 {
-        self.seq += 1;
-        self.seq
+        self.seq.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
     }
 
-    pub fn handle_link(&mut self) -> LinkHandle<'_> {
+    pub fn handle_link(&mut self) -> LinkHandle<'_, S> {
         LinkHandle::from(self)
     }
 
-    pub fn handle_addr(&mut self) -> AddrHandle<'_> {
+    pub fn handle_addr(&mut self) -> AddrHandle<'_, S> {
         AddrHandle::from(self)
     }
 
-    pub fn handle_route(&mut self) -> RouteHandle<'_> {
+    pub fn handle_route(&mut self) -> RouteHandle<'_, S> {
         RouteHandle::from(self)
     }
 
-    pub fn handle_neigh(&mut self) -> NeighHandle<'_> {
+    pub fn handle_neigh(&mut self) -> NeighHandle<'_, S> {
         NeighHandle::from(self)
     }
 
-    pub fn handle_generic(&mut self) -> GenericHandle<'_> {
+    pub fn handle_rule(&mut self) -> RuleHandle<'_, S> {
+        RuleHandle::from(self)
+    }
+
+    pub fn handle_bpf(&mut self) -> BpfHandle<'_, S> {
+        BpfHandle::from(self)
+    }
+
+    pub fn handle_mptcp(&mut self) -> MptcpHandle<'_, S> {
+        MptcpHandle::from(self)
+    }
+
+    pub fn handle_generic(&mut self) -> GenericHandle<'_, S> {
         GenericHandle::from(self)
     }
 
+    pub fn handle_conntrack(&mut self) -> ConntrackHandle<'_, S> {
+        ConntrackHandle::from(self)
+    }
+
+    pub fn handle_ethtool(&mut self) -> EthtoolHandle<'_, S> {
+        EthtoolHandle::from(self)
+    }
+
+    pub fn handle_fou(&mut self) -> FouHandle<'_, S> {
+        FouHandle::from(self)
+    }
+
+    pub fn handle_ndt(&mut self) -> NdtHandle<'_, S> {
+        NdtHandle::from(self)
+    }
+
+    pub fn handle_nft(&mut self) -> NftHandle<'_, S> {
+        NftHandle::from(self)
+    }
+
+    pub fn handle_nl80211(&mut self) -> Nl80211Handle<'_, S> {
+        Nl80211Handle::from(self)
+    }
+
+    pub fn handle_sock_diag(&mut self) -> SockDiagHandle<'_, S> {
+        SockDiagHandle::from(self)
+    }
+
+    pub fn handle_qdisc(&mut self) -> QdiscHandle<'_, S> {
+        QdiscHandle::from(self)
+    }
+
+    pub fn handle_tc(&mut self) -> TcHandle<'_, S> {
+        TcHandle::from(self)
+    }
+
+    pub fn handle_xfrm(&mut self) -> XfrmHandle<'_, S> {
+        XfrmHandle::from(self)
+    }
+
+    /// Sends `req` as an `NLM_F_DUMP` request of type `proto` and parses
+    /// every response message of type `res_type` into `Resp`, e.g.
+    /// `RTM_NEWADDR` for `RTM_GETADDR`. Messages of other types are skipped,
+    /// or none with a `res_type` of 0.
+    pub fn dump<Req, Resp>(&mut self, proto: u16, res_type: u16, req: &Req) -> Result<Vec<Resp>>
+    where
+        Req: Attribute,
+        Resp: for<'b> TryFrom<&'b [u8]>,
+        for<'b> <Resp as TryFrom<&'b [u8]>>::Error: Into<anyhow::Error>,
+    {
+        let mut msg = Message::new(proto, libc::NLM_F_DUMP);
+        msg.add(&req.serialize()?);
+
+        self.request(&mut msg, res_type)?
+            .iter()
+            .map(|m| Resp::try_from(m.as_slice()).map_err(Into::into))
+            .collect()
+    }
+
+    /// Like `dump`, but every response message must be of type `res_type`.
+    /// A message of another type is an error instead of being skipped, for
+    /// families whose parsers would misread any other payload.
+    pub fn dump_strict<Req, Resp>(
+        &mut self,
+        proto: u16,
+        res_type: u16,
+        req: &Req,
+    ) -> Result<Vec<Resp>>
+    where
+        Req: Attribute,
+        Resp: for<'b> TryFrom<&'b [u8]>,
+        for<'b> <Resp as TryFrom<&'b [u8]>>::Error: Into<anyhow::Error>,
+    {
+        let mut msg = Message::new(proto, libc::NLM_F_DUMP);
+        msg.add(&req.serialize()?);
+
+        self.request_msgs(&mut msg, 0)?
+            .iter()
+            .map(|m| Resp::try_from(m.expect_type(res_type)?).map_err(Into::into))
+            .collect()
+    }
+
+    /// Sends `req` as a request of type `proto` and waits for the kernel to
+    /// acknowledge it. `NLM_F_ACK` is always added to `flags`.
+    pub fn execute<Req: Attribute>(&mut self, proto: u16, flags: i32, req: &Req) -> Result<()> {
+        self.raw_request(
+            proto,
+            NlFlags::from(flags) | NlFlags::ACK,
+            &req.serialize()?,
+        )?;
+
+        Ok(())
+    }
+
+    /// Sends a message built from an already serialized `payload` and returns
+    /// the payloads of all response messages, of any type. This is the stable
+    /// low-level entry point for families that have no handle yet: the
+    /// sequence number, `NLM_F_REQUEST`, multipart reassembly and error
+    /// replies are taken care of, while the payload layout is entirely up to
+    /// the caller.
+    ///
+    /// It is a thin wrapper around `request`, not the primitive the handles
+    /// are built on: they call `request` with the `Message` they build, which
+    /// reaches the same receive path. Of the helpers, only `execute` sends
+    /// through `raw_request`.
+    pub fn raw_request(
+        &mut self,
+        nlmsg_type: u16,
+        flags: NlFlags,
+        payload: &[u8],
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut msg = Message::new(nlmsg_type, flags.bits() as i32);
+        msg.add(payload);
+
+        self.request(&mut msg, 0)
+    }
+
+    /// Sends a message without waiting for a response and returns its
+    /// sequence number. Replies, if any, can be read with `raw_recv`.
+    pub fn raw_send(&mut self, nlmsg_type: u16, flags: NlFlags, payload: &[u8]) -> Result<u32> {
+        let mut msg = Message::new(nlmsg_type, flags.bits() as i32);
+        msg.add(payload);
+        msg.header.nlmsg_seq = self.next_seq();
+        msg.set_pid(self.pid);
+
+        send_retry(&self.socket, &msg.serialize()?)?;
+
+        Ok(msg.header.nlmsg_seq)
+    }
+
+    /// Reads the next datagram from the socket and returns the messages it
+    /// contains as is, without checking the sequence number or the type.
+    pub fn raw_recv(&mut self) -> Result<Messages> {
+        let mut buf = vec![0; RECV_BUF_SIZE];
+
+        loop {
+            let (len, _) =
+                recv_retry(&self.socket, &mut buf, self.recv_timeout).map_err(recv_error)?;
+
+            if len <= buf.len() {
+                return Ok(Messages::from(&buf[..len]));
+            }
+
+            if len > self.max_recv_buf_size {
+                bail!(
+                    "netlink message of {} bytes exceeds the receive buffer limit of {} bytes",
+                    len,
+                    self.max_recv_buf_size
+                );
+            }
+            buf.resize(len, 0);
+        }
+    }
+
+    /// Sends every message of `batch` with `NLM_F_ACK` and returns the result
+    /// of each of them, in the order they were added. The messages are
+    /// written into as few `send` calls as possible and the kernel keeps
+    /// processing the rest of a batch after one of its messages fails.
+    pub fn send_batch(&mut self, batch: Batch) -> Result<Vec<Result<()>>> {
+        let mut results = Vec::with_capacity(batch.len());
+        let mut buf = Vec::with_capacity(MAX_BATCH_BYTES);
+        let mut chunk = 0;
+        let mut first = 0;
+
+        for mut msg in batch {
+            if chunk == MAX_BATCH_MESSAGES
+                || buf.len() + align_of(msg.header.nlmsg_len as usize, NLMSG_ALIGNTO)
+                    > MAX_BATCH_BYTES
+            {
+                self.send_chunk(&buf, first, chunk, &mut results)?;
+                buf.clear();
+                chunk = 0;
+            }
+
+            msg.header.nlmsg_flags |= libc::NLM_F_ACK as u16;
+            msg.header.nlmsg_seq = self.next_seq();
+            if chunk == 0 {
+                first = msg.header.nlmsg_seq;
+            }
+            msg.set_pid(self.pid);
+            msg.serialize_into(&mut buf);
+            chunk += 1;
+        }
+
+        if chunk > 0 {
+            self.send_chunk(&buf, first, chunk, &mut results)?;
+        }
+
+        Ok(results)
+    }
+
+    /// Sends `count` serialized messages with consecutive sequence numbers
+    /// starting at `first` and appends their acks to `results`.
+    fn send_chunk(
+        &mut self,
+        buf: &[u8],
+        first: u32,
+        count: usize,
+        results: &mut Vec<Result<()>>,
+    ) -> Result<()> {
+        send_retry(&self.socket, buf)?;
+
+        let mut acks: Vec<Option<Result<()>>> = (0..count).map(|_| None).collect();
+        let mut pending = count;
+
+        while pending > 0 {
+            for m in self.raw_recv()? {
+                let idx = m.header.nlmsg_seq.wrapping_sub(first) as usize;
+
+                if m.header.nlmsg_pid != self.pid
+                    || m.header.nlmsg_type != NLMSG_ERROR
+                    || idx >= count
+                    || acks[idx].is_some()
+                {
+                    continue;
+                }
+
+                acks[idx] = Some(ack_result(&m, None, self.socket.protocol()));
+                pending -= 1;
+            }
+        }
+
+        results.extend(acks.into_iter().flatten());
+
+        Ok(())
+    }
+
+    /// Like `request`, but sets the ack and echo flags from `opts` instead
+    /// of the caller building them into `msg`.
+    pub fn request_with_options(
+        &mut self,
+        msg: &mut Message,
+        res_type: u16,
+        opts: RequestOptions,
+    ) -> Result<Vec<Vec<u8>>> {
+        if opts.ack {
+            msg.header.nlmsg_flags |= libc::NLM_F_ACK as u16;
+        }
+        if opts.echo || opts.require_echo {
+            msg.header.nlmsg_flags |= libc::NLM_F_ECHO as u16;
+        }
+        if opts.ext_ack {
+            self.socket.set_ext_ack(true)?;
+        }
+
+        let res = self.request(msg, res_type)?;
+
+        if opts.require_echo && res.is_empty() {
+            bail!("the kernel acknowledged the request without echoing it");
+        }
+
+        Ok(res)
+    }
+
+    /// Like `request`, but keeps the header of every response message, e.g.
+    /// to tell apart the message types of a dump that interleaves several.
+    pub fn request_msgs(&mut self, msg: &mut Message, res_type: u16) -> Result<Vec<NlMsg>> {
+        let mut res = Vec::new();
+
+        self.request_with_header(msg, res_type, |header, payload| {
+            res.push(NlMsg {
+                header: *header,
+                payload: payload.to_vec(),
+            });
+            ControlFlow::Continue(())
+        })?;
+
+        Ok(res)
+    }
+
     pub fn request(&mut self, msg: &mut Message, res_type: u16) -> Result<Vec<Vec<u8>>> {
+        let mut res: Vec<Vec<u8>> = Vec::new();
+
+        self.request_with(msg, res_type, |payload| {
+            res.push(payload.to_vec());
+            ControlFlow::Continue(())
+        })?;
+
+        Ok(res)
+    }
+
+    /// Sends `msg` and passes the payload of every response message to `f` as
+    /// soon as the buffer it arrived in is read, instead of collecting them.
+    /// Returning `ControlFlow::Break` stops the callbacks; the remaining parts
+    /// of a multipart response are still read and discarded so the next
+    /// request on this socket does not see them.
+    pub fn request_with<F>(&mut self, msg: &mut Message, res_type: u16, mut f: F) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> ControlFlow<()>,
+    {
+        self.request_with_header(msg, res_type, |_, payload| f(payload))
+    }
+
+    /// Like `request_with`, but also passes the header of every response
+    /// message, e.g. to pick a parser by `nlmsg_type` when a request is
+    /// answered with several message types.
+    pub fn request_with_header<F>(
+        &mut self,
+        msg: &mut Message,
+        res_type: u16,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&Header, &[u8]) -> ControlFlow<()>,
+    {
         let next_seq = self.next_seq();
         msg.header.nlmsg_seq = next_seq;
+        msg.set_pid(self.pid);
 
-        self.socket.send(&msg.serialize()?)?;
+        #[cfg(feature = "trace")]
+        let _span = tracing::debug_span!(
+            "request",
+            msg_type = msg.header.nlmsg_type,
+            flags = format_args!("{:#x}", msg.header.nlmsg_flags),
+            seq = next_seq,
+        )
+        .entered();
 
-        let pid = self.socket.pid()?;
-        let mut res: Vec<Vec<u8>> = Vec::new();
+        let req = msg.serialize()?;
+
+        #[cfg(feature = "trace")]
+        tracing::debug!(len = req.len(), "send\n{}", HexDump(&req));
+
+        send_retry(&self.socket, &req)?;
+
+        let pid = self.pid;
+        // With NLM_F_ACK the reply to a request is followed by the ack, which
+        // has to be read as well so it does not linger on the socket.
+        let acked = msg.header.nlmsg_flags & libc::NLM_F_ACK as u16 != 0;
+        let mut stopped = false;
+        let mut buf = vec![0; RECV_BUF_SIZE];
 
         'done: loop {
-            let (msgs, from) = self.socket.recv()?;
+            let (len, from) =
+                recv_retry(&self.socket, &mut buf, self.recv_timeout).map_err(recv_error)?;
 
-            if from.nl_pid != PID_KERNEL {
-                bail!(
-                    "wrong sender pid: {}, expected: {}",
-                    from.nl_pid,
-                    PID_KERNEL
-                );
+            if len > buf.len() {
+                if len > self.max_recv_buf_size {
+                    bail!(
+                        "netlink message of {} bytes exceeds the receive buffer limit of {} bytes",
+                        len,
+                        self.max_recv_buf_size
+                    );
+                }
+                buf.resize(len, 0);
+                continue;
             }
 
-            for mut m in msgs {
+            if from != PID_KERNEL {
+                bail!("wrong sender pid: {}, expected: {}", from, PID_KERNEL);
+            }
+
+            #[cfg(feature = "trace")]
+            trace_recv(&buf[..len]);
+
+            for m in Messages::from(&buf[..len]) {
+                // Replies left over from an earlier request carry an older
+                // sequence number and are skipped, but a reply addressed to
+                // another port id was delivered to the wrong socket. On a
+                // subscribed socket, notifications, which come from the
+                // kernel or carry the sequence number of someone else's
+                // request, are skipped as well.
+                let notification = self.groups != 0
+                    && (m.header.nlmsg_pid == PID_KERNEL || m.header.nlmsg_seq != next_seq);
+                if m.header.nlmsg_pid != pid && !notification {
+                    bail!(
+                        "response for port id {} received on port id {}",
+                        m.header.nlmsg_pid,
+                        pid
+                    );
+                }
                 if m.verify_header(next_seq, pid).is_err() {
                     continue;
                 }
 
                 match m.header.nlmsg_type {
                     NLMSG_DONE | NLMSG_ERROR => {
-                        let payload = m.payload.as_ref().unwrap();
-                        let err_no = i32::from_ne_bytes(payload[0..4].try_into()?);
-
-                        if err_no == 0 {
-                            break 'done;
-                        }
-
-                        let err_msg = std::io::Error::from_raw_os_error(-err_no);
-                        bail!("{} ({}): {:?}", err_msg, -err_no, &payload[4..]);
+                        ack_result(&m, Some(&msg.header), self.socket.protocol())?;
+                        break 'done;
                     }
                     t if res_type != 0 && t != res_type => {
                         continue;
                     }
+                    _ if stopped => {}
                     _ => {
-                        res.push(m.payload.take().unwrap());
+                        stopped = f(&m.header, m.payload.as_ref().unwrap()).is_break();
                     }
                 }
 
-                if m.check_last_message() {
+                if m.check_last_message() && !acked {
                     break 'done;
                 }
             }
         }
 
-        Ok(res)
+        Ok(())
+    }
+}
+
+/// Turns an `NLMSG_ERROR` or `NLMSG_DONE` message into the result of the
+/// request it answers. An error is a `RequestError` named after the request
+/// header the kernel echoes, or after `req` when it has none, which is the
+/// case for a dump that fails midway.
+fn ack_result(m: &Message, req: Option<&Header>, protocol: Option<i32>) -> Result<()> {
+    let payload = m.payload.as_deref().unwrap_or_default();
+    let err_no = match payload.get(..4) {
+        Some(err_no) => i32::from_ne_bytes(err_no.try_into()?),
+        None => bail!("truncated netlink error message"),
+    };
+
+    if err_no == 0 {
+        return Ok(());
+    }
+
+    #[cfg(feature = "trace")]
+    tracing::debug!(
+        errno = -err_no,
+        "request failed: {}",
+        std::io::Error::from_raw_os_error(-err_no)
+    );
+
+    let echoed = match payload.get(4..4 + NLMSG_HDRLEN) {
+        Some(buf) if m.header.nlmsg_type == NLMSG_ERROR => bincode::deserialize::<Header>(buf).ok(),
+        _ => None,
+    };
+
+    let mut err = match echoed.as_ref().or(req) {
+        Some(header) => RequestError::new(
+            header.nlmsg_type,
+            NlFlags::from_bits_retain(header.nlmsg_flags),
+            -err_no,
+        ),
+        None => RequestError::new(0, NlFlags::empty(), -err_no),
+    };
+    err.protocol = protocol;
+    err.ext_msg = ext_ack_msg(&m.header, payload);
+
+    if let Some(header) = echoed.filter(|_| m.header.nlmsg_flags & NLM_F_CAPPED == 0) {
+        let end = 4 + header.nlmsg_len as usize;
+        err.digest = payload.get(4 + NLMSG_HDRLEN..end).map(digest);
+    }
+
+    Err(err.into())
+}
+
+/// Returns the `NLMSGERR_ATTR_MSG` of an extended ack. The TLVs follow the
+/// header of the failed request, or the whole request unless it is capped.
+fn ext_ack_msg(header: &Header, payload: &[u8]) -> Option<String> {
+    if header.nlmsg_flags & NLM_F_ACK_TLVS == 0 || payload.len() < 8 {
+        return None;
+    }
+
+    let offset = match header.nlmsg_flags & NLM_F_CAPPED {
+        0 => 4 + u32::from_ne_bytes(payload[4..8].try_into().ok()?) as usize,
+        _ => 4 + 16,
+    };
+
+    RouteAttrs::from(payload.get(offset..)?)
+        .into_iter()
+        .find(|a| a.header.rta_type == NLMSGERR_ATTR_MSG)
+        .and_then(|a| a.payload.as_string().ok())
+}
+
+/// Logs every message of a received datagram on its own.
+#[cfg(feature = "trace")]
+fn trace_recv(buf: &[u8]) {
+    let mut offset = 0;
+
+    while offset + NLMSG_HDRLEN <= buf.len() {
+        let len = u32::from_ne_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize;
+        let end = (offset + len).min(buf.len());
+
+        tracing::debug!(len, "recv\n{}", HexDump(&buf[offset..end]));
+
+        if len < NLMSG_HDRLEN {
+            break;
+        }
+        offset += align_of(len, 4);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{error::errno, mock::MockTransport, socket::RTMGRP_LINK},
+        test_setup,
+        types::{
+            addr::Address,
+            link::{Kind, LinkAttrs},
+            message::{IfAddrMsg, IfInfoMsg, RouteAttr, RouteMessage},
+            sock_diag::DiagFamily,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_request_grows_recv_buf() {
+        let len = RECV_BUF_SIZE + 4096;
+        let mut big = vec![0xab; len];
+        big[..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        big[4..6].copy_from_slice(&libc::RTM_NEWLINK.to_ne_bytes());
+        big[6..8].copy_from_slice(&0u16.to_ne_bytes());
+
+        let mock = MockTransport::default();
+        mock.push_response(&big);
+        mock.push_response(&big);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let mut req = Message::new(libc::RTM_GETLINK, 0);
+        let res = handle.request(&mut req, 0).unwrap();
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].len(), len - 16);
+        assert!(res[0].iter().all(|&b| b == 0xab));
+
+        handle.max_recv_buf_size = RECV_BUF_SIZE;
+        let mut req = Message::new(libc::RTM_GETLINK, 0);
+        assert!(handle.request(&mut req, 0).is_err());
+    }
+
+    #[test]
+    fn test_request_with_header() {
+        let route = RouteMessage {
+            family: libc::AF_INET6 as u8,
+            dst_len: 64,
+            ..RouteMessage::new()
+        };
+        let route = Attribute::serialize(&route).unwrap();
+
+        let mut datagram = vec![];
+        for (msg_type, payload) in [
+            (libc::RTM_NEWROUTE, route.as_slice()),
+            (libc::RTM_NEWNEIGH, &[0; 12][..]),
+            (NLMSG_DONE, &[0; 4][..]),
+        ] {
+            let len = 16 + payload.len();
+            datagram.extend_from_slice(&(len as u32).to_ne_bytes());
+            datagram.extend_from_slice(&msg_type.to_ne_bytes());
+            datagram.extend_from_slice(&(libc::NLM_F_MULTI as u16).to_ne_bytes());
+            datagram.extend_from_slice(&[0; 8]);
+            datagram.extend_from_slice(payload);
+        }
+
+        let mock = MockTransport::default();
+        mock.push_response(&datagram);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let mut req = Message::new(libc::RTM_GETROUTE, libc::NLM_F_DUMP);
+        let mut types = vec![];
+        let mut routes = vec![];
+
+        handle
+            .request_with_header(&mut req, 0, |header, payload| {
+                types.push(header.nlmsg_type);
+                if header.nlmsg_type == libc::RTM_NEWROUTE {
+                    routes.push(RouteMessage::deserialize(payload).unwrap());
+                }
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        assert_eq!(types, [libc::RTM_NEWROUTE, libc::RTM_NEWNEIGH]);
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].family, libc::AF_INET6 as u8);
+        assert_eq!(routes[0].dst_len, 64);
+    }
+
+    #[test]
+    fn test_request_pid() {
+        let mock = MockTransport::new(42);
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        assert_eq!(handle.pid(), 42);
+
+        let mut req = Message::new(libc::RTM_NEWLINK, libc::NLM_F_ACK);
+        handle.request(&mut req, 0).unwrap();
+        assert_eq!(handle.socket.sent()[0][12..16], 42u32.to_ne_bytes());
+
+        // A reply for another port id was not meant for this socket.
+        let mut ack = vec![0; 36];
+        ack[..4].copy_from_slice(&36u32.to_ne_bytes());
+        ack[4..6].copy_from_slice(&NLMSG_ERROR.to_ne_bytes());
+        ack[8..12].copy_from_slice(&2u32.to_ne_bytes());
+        ack[12..16].copy_from_slice(&7u32.to_ne_bytes());
+        handle.socket.push_response_verbatim(&ack);
+
+        let err = handle.request(&mut req, 0).err().unwrap();
+        assert!(err.to_string().contains("port id 7"));
+
+        // A subscribed socket skips notifications, but still rejects a reply
+        // to its request for another port id.
+        handle.groups = RTMGRP_LINK;
+        let mut notification = vec![0; 36];
+        notification[..4].copy_from_slice(&36u32.to_ne_bytes());
+        notification[4..6].copy_from_slice(&libc::RTM_NEWLINK.to_ne_bytes());
+        let mut datagram = notification.clone();
+        datagram.extend_from_slice(&ack);
+        datagram[44..48].copy_from_slice(&3u32.to_ne_bytes());
+        handle.socket.push_response_verbatim(&datagram);
+
+        let err = handle.request(&mut req, 0).err().unwrap();
+        assert!(err.to_string().contains("port id 7"));
+
+        let mut ack = ack.clone();
+        ack[8..12].copy_from_slice(&4u32.to_ne_bytes());
+        ack[12..16].copy_from_slice(&42u32.to_ne_bytes());
+        notification.extend_from_slice(&ack);
+        handle.socket.push_response_verbatim(&notification);
+
+        handle.request(&mut req, 0).unwrap();
+    }
+
+    #[test]
+    fn test_request_msgs() {
+        let mut datagram = vec![];
+        for (msg_type, payload_len) in [
+            (libc::RTM_NEWLINK, 16),
+            (libc::RTM_NEWADDR, 8),
+            (NLMSG_DONE, 4),
+        ] {
+            datagram.extend_from_slice(&(16 + payload_len as u32).to_ne_bytes());
+            datagram.extend_from_slice(&msg_type.to_ne_bytes());
+            datagram.extend_from_slice(&(libc::NLM_F_MULTI as u16).to_ne_bytes());
+            datagram.extend_from_slice(&[0; 8]);
+            datagram.extend_from_slice(&vec![0; payload_len]);
+        }
+
+        let mock = MockTransport::default();
+        mock.push_response(&datagram);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let mut req = Message::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+        let msgs = handle.request_msgs(&mut req, 0).unwrap();
+
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[0].header.nlmsg_type, libc::RTM_NEWLINK);
+        assert_eq!(msgs[0].header.nlmsg_seq, req.header.nlmsg_seq);
+        assert_eq!(msgs[0].header.nlmsg_flags, libc::NLM_F_MULTI as u16);
+        assert_eq!(msgs[0].payload.len(), 16);
+        assert_eq!(msgs[1].expect_type(libc::RTM_NEWADDR).unwrap().len(), 8);
+        assert!(msgs[1].expect_type(libc::RTM_NEWLINK).is_err());
+    }
+
+    #[test]
+    fn test_dump_strict_rejects_other_types() {
+        let mut datagram = vec![];
+        for msg_type in [libc::RTM_NEWADDR, libc::RTM_NEWLINK, NLMSG_DONE] {
+            datagram.extend_from_slice(&20u32.to_ne_bytes());
+            datagram.extend_from_slice(&msg_type.to_ne_bytes());
+            datagram.extend_from_slice(&(libc::NLM_F_MULTI as u16).to_ne_bytes());
+            datagram.extend_from_slice(&[0; 12]);
+        }
+
+        let mock = MockTransport::default();
+        mock.push_response(&datagram);
+        mock.push_response(&datagram[..20]);
+        mock.push_response(&datagram[40..]);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let msg = IfAddrMsg::new(libc::AF_UNSPEC);
+
+        let err = handle
+            .dump_strict::<_, Vec<u8>>(libc::RTM_GETADDR, libc::RTM_NEWADDR, &msg)
+            .unwrap_err();
+        assert!(err.to_string().contains("unexpected netlink message type"));
+
+        let addrs = handle
+            .dump_strict::<_, Vec<u8>>(libc::RTM_GETADDR, libc::RTM_NEWADDR, &msg)
+            .unwrap();
+        assert_eq!(addrs, vec![vec![0; 4]]);
+    }
+
+    #[test]
+    fn test_raw_request() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let links = handle
+            .raw_request(
+                libc::RTM_GETLINK,
+                NlFlags::DUMP,
+                &Attribute::serialize(&IfInfoMsg::new(libc::AF_UNSPEC)).unwrap(),
+            )
+            .unwrap();
+
+        // Only lo exists in a fresh namespace.
+        assert_eq!(links.len(), 1);
+        assert_eq!(i32::from_ne_bytes(links[0][4..8].try_into().unwrap()), 1);
+
+        let mut missing = IfInfoMsg::new(libc::AF_UNSPEC);
+        missing.index = 9999;
+        let err = handle
+            .raw_request(
+                libc::RTM_DELLINK,
+                NlFlags::ACK,
+                &Attribute::serialize(&missing).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(errno(&err), Some(libc::ENODEV));
+    }
+
+    #[test]
+    fn test_raw_send_recv() {
+        let mock = MockTransport::default();
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let seq = handle
+            .raw_send(libc::RTM_GETLINK, NlFlags::DUMP, &[0; 16])
+            .unwrap();
+
+        let req = &handle.socket.sent()[0];
+        let header: Header = bincode::deserialize(req).unwrap();
+        assert_eq!(header.nlmsg_len, 32);
+        assert_eq!(header.nlmsg_seq, seq);
+        assert_eq!(
+            NlFlags::from_bits_retain(header.nlmsg_flags),
+            NlFlags::REQUEST | NlFlags::DUMP
+        );
+
+        let msgs = handle.raw_recv().unwrap();
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].header.nlmsg_type, NLMSG_ERROR);
+    }
+
+    #[test]
+    fn test_request_with_options() {
+        let mut reply = Message::new(libc::RTM_NEWLINK, 0);
+        reply.add(&IfInfoMsg::new(libc::AF_UNSPEC).serialize().unwrap());
+
+        let mock = MockTransport::default();
+        mock.push_response(&reply.serialize().unwrap());
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let opts = RequestOptions {
+            ack: true,
+            echo: true,
+            ..Default::default()
+        };
+
+        let mut req = Message::new(libc::RTM_NEWLINK, libc::NLM_F_CREATE);
+        let res = handle.request_with_options(&mut req, 0, opts).unwrap();
+        assert_eq!(res.len(), 1);
+
+        let flags = NlFlags::from_bits_retain(req.header.nlmsg_flags);
+        assert!(flags.contains(NlFlags::ACK | NlFlags::ECHO));
+
+        // The ack following the echo was consumed along with it.
+        handle.socket.push_ack();
+        let opts = RequestOptions {
+            require_echo: true,
+            ..opts
+        };
+        let mut req = Message::new(libc::RTM_NEWLINK, libc::NLM_F_CREATE);
+        assert!(handle.request_with_options(&mut req, 0, opts).is_err());
+    }
+
+    #[test]
+    fn test_request_ext_ack_message() {
+        let text = RouteAttr::string(NLMSGERR_ATTR_MSG, "Unknown device type");
+        let mut payload = (-libc::EOPNOTSUPP).to_ne_bytes().to_vec();
+        payload.extend_from_slice(&Message::new(libc::RTM_NEWLINK, 0).serialize().unwrap());
+        payload.extend(text.serialize().unwrap());
+
+        let mut err = vec![];
+        err.extend_from_slice(&(16 + payload.len() as u32).to_ne_bytes());
+        err.extend_from_slice(&NLMSG_ERROR.to_ne_bytes());
+        err.extend_from_slice(&(NLM_F_CAPPED | NLM_F_ACK_TLVS).to_ne_bytes());
+        err.extend_from_slice(&[0; 8]);
+        err.extend(payload);
+
+        let mock = MockTransport::default();
+        mock.push_response(&err);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let msg = IfInfoMsg::new(libc::AF_UNSPEC);
+        let e = handle
+            .execute(libc::RTM_NEWLINK, libc::NLM_F_CREATE, &msg)
+            .unwrap_err();
+
+        assert_eq!(
+            e.to_string(),
+            "nlmsg_type 16 failed: EOPNOTSUPP (Operation not supported on transport endpoint): Unknown device type"
+        );
+    }
+
+    #[test]
+    fn test_request_error_names_request() {
+        let msg = IfInfoMsg::new(libc::AF_UNSPEC);
+        let mut req = Message::new(libc::RTM_NEWLINK, libc::NLM_F_CREATE | libc::NLM_F_ACK);
+        req.add(&msg.serialize().unwrap());
+
+        // Without NETLINK_CAP_ACK the kernel echoes the whole request.
+        let mut payload = (-libc::EEXIST).to_ne_bytes().to_vec();
+        payload.extend(req.serialize().unwrap());
+
+        let mut err = vec![];
+        err.extend_from_slice(&(16 + payload.len() as u32).to_ne_bytes());
+        err.extend_from_slice(&NLMSG_ERROR.to_ne_bytes());
+        err.extend_from_slice(&[0; 10]);
+        err.extend(payload);
+
+        let mock = MockTransport::default();
+        mock.push_response(&err);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let e = handle
+            .execute(libc::RTM_NEWLINK, libc::NLM_F_CREATE, &msg)
+            .unwrap_err();
+        let e = e.downcast_ref::<RequestError>().unwrap();
+
+        assert_eq!(e.nlmsg_type, libc::RTM_NEWLINK);
+        assert!(e.flags.contains(NlFlags::CREATE | NlFlags::ACK));
+        assert_eq!(e.errno(), libc::EEXIST);
+        assert_eq!(e.digest, Some(digest(&msg.serialize().unwrap())));
+    }
+
+    #[test]
+    fn test_send_batch_pads_messages() {
+        let mut acks = vec![];
+        for seq in 1..=2u32 {
+            acks.extend_from_slice(&36u32.to_ne_bytes());
+            acks.extend_from_slice(&NLMSG_ERROR.to_ne_bytes());
+            acks.extend_from_slice(&0u16.to_ne_bytes());
+            acks.extend_from_slice(&seq.to_ne_bytes());
+            acks.extend_from_slice(&1u32.to_ne_bytes());
+            acks.extend_from_slice(&[0; 20]);
+        }
+
+        let mock = MockTransport::default();
+        mock.push_response_verbatim(&acks);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let mut batch = Batch::new();
+        for _ in 0..2 {
+            let mut msg = Message::new(libc::RTM_NEWLINK, 0);
+            // An IFLA_MTU without the padding after its 1 byte payload.
+            msg.add(&[0x05, 0x00, 0x04, 0x00, 0xff]);
+            batch.add(msg);
+        }
+
+        let results = handle.send_batch(batch).unwrap();
+        assert!(results.iter().all(Result::is_ok));
+
+        let sent = &handle.socket.sent()[0];
+        assert_eq!(sent.len(), 2 * 24);
+        assert_eq!(sent[..4], 21u32.to_ne_bytes());
+
+        let msgs = Messages::from(sent.as_slice());
+        assert_eq!(msgs.len(), 2);
+        assert_eq!(msgs[1].header.nlmsg_seq, 2);
+    }
+
+    #[test]
+    fn test_dump_skips_other_types() {
+        let addr = Attribute::serialize(&IfAddrMsg::new(libc::AF_INET)).unwrap();
+
+        let mut datagram = vec![];
+        for (msg_type, payload) in [
+            (libc::RTM_NEWADDR, addr.as_slice()),
+            // Too short for an `ifaddrmsg`, so it would fail to parse.
+            (libc::RTM_NEWLINK, &[0; 4][..]),
+            (libc::RTM_NEWADDR, addr.as_slice()),
+            (NLMSG_DONE, &[0; 4][..]),
+        ] {
+            let len = 16 + payload.len();
+            datagram.extend_from_slice(&(len as u32).to_ne_bytes());
+            datagram.extend_from_slice(&msg_type.to_ne_bytes());
+            datagram.extend_from_slice(&(libc::NLM_F_MULTI as u16).to_ne_bytes());
+            datagram.extend_from_slice(&[0; 8]);
+            datagram.extend_from_slice(payload);
+        }
+
+        let mock = MockTransport::default();
+        mock.push_response(&datagram);
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let addrs: Vec<Address> = handle
+            .dump(
+                libc::RTM_GETADDR,
+                libc::RTM_NEWADDR,
+                &IfAddrMsg::new(libc::AF_INET),
+            )
+            .unwrap();
+
+        assert_eq!(addrs.len(), 2);
+    }
+
+    #[test]
+    fn test_execute_adds_ack() {
+        let mock = MockTransport::default();
+        mock.push_ack();
+
+        let mut handle = SocketHandle::with_transport(mock).unwrap();
+        let msg = IfInfoMsg::new(libc::AF_UNSPEC);
+        handle
+            .execute(libc::RTM_NEWLINK, libc::NLM_F_CREATE, &msg)
+            .unwrap();
+
+        let req = &handle.socket.sent()[0];
+        let flags = u16::from_ne_bytes([req[6], req[7]]) as i32;
+        assert_eq!(
+            flags,
+            libc::NLM_F_REQUEST | libc::NLM_F_CREATE | libc::NLM_F_ACK
+        );
+        assert_eq!(&req[16..], msg.serialize().unwrap().as_slice());
+    }
+
+    #[test]
+    fn test_concurrent_handles() {
+        test_setup!();
+        let mut route = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut diag = SocketHandle::new(libc::NETLINK_INET_DIAG);
+        let mut route2 = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        // Port ids are only unique per protocol.
+        assert_ne!(route.pid, 0);
+        assert_ne!(diag.pid, 0);
+        assert_ne!(route.pid, route2.pid);
+
+        for _ in 0..2 {
+            assert!(!route.handle_link().list().unwrap().is_empty());
+            diag.handle_sock_diag().tcp_info(DiagFamily::V4).unwrap();
+            assert!(!route2.handle_link().list().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_request_with_early_stop() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let all = handle.handle_link().list().unwrap().len();
+
+        let mut seen = 0;
+        let mut req = Message::new(libc::RTM_GETLINK, libc::NLM_F_DUMP);
+        req.add(&IfInfoMsg::new(libc::AF_UNSPEC).serialize().unwrap());
+
+        handle
+            .request_with(&mut req, libc::RTM_NEWLINK, |_| {
+                seen += 1;
+                ControlFlow::Break(())
+            })
+            .unwrap();
+
+        assert_eq!(seen, 1);
+
+        // The socket must be clean for the next request.
+        assert_eq!(handle.handle_link().list().unwrap().len(), all);
+    }
+
+    #[test]
+    fn test_enable_strict_checking() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut msg = IfAddrMsg::new(libc::AF_UNSPEC);
+        msg.prefix_len = 24;
+
+        // Without strict checking the unexpected prefix length is ignored.
+        handle
+            .dump::<_, Address>(libc::RTM_GETADDR, libc::RTM_NEWADDR, &msg)
+            .unwrap();
+
+        // Kernels before 4.20 do not know the option.
+        match handle.enable_strict_checking() {
+            Err(e)
+                if e.downcast_ref::<std::io::Error>()
+                    .and_then(std::io::Error::raw_os_error)
+                    == Some(libc::ENOPROTOOPT) =>
+            {
+                eprintln!("test skipped, requires NETLINK_GET_STRICT_CHK: {e}");
+                return;
+            }
+            res => res.unwrap(),
+        }
+
+        assert!(handle
+            .dump::<_, Address>(libc::RTM_GETADDR, libc::RTM_NEWADDR, &msg)
+            .is_err());
+        assert!(!handle.handle_link().list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_with_timeout() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let handle = handle.with_timeout(Duration::from_millis(10)).unwrap();
+        assert_eq!(handle.recv_timeout, Some(Duration::from_millis(10)));
+
+        // Nothing was requested, so the kernel never answers.
+        let err = handle.raw_recv().err().unwrap();
+        let err = err.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+        assert!(!handle.handle_link().list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_with_buf_sizes() {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let handle = handle
+            .with_recv_buf(64 << 10)
+            .unwrap()
+            .with_send_buf(64 << 10)
+            .unwrap();
+
+        // The kernel doubles the requested size.
+        assert_eq!(handle.socket.recv_buf_size().unwrap(), 128 << 10);
+        assert!(!handle.handle_link().list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let links = handle.handle_link().list().unwrap().len();
+
+        let mut clone = handle.try_clone().unwrap();
+        assert_ne!(clone.as_raw_fd(), handle.as_raw_fd());
+        assert_eq!(clone.pid(), handle.pid());
+
+        // The handles share the sequence counter, so their requests never
+        // carry the same sequence number.
+        let seq = handle.next_seq();
+        assert_eq!(clone.next_seq(), seq + 1);
+
+        assert_eq!(clone.handle_link().list().unwrap().len(), links);
+        assert_eq!(handle.handle_link().list().unwrap().len(), links);
+
+        // Dropping one handle only closes its own fd.
+        handle.close().unwrap();
+        assert_eq!(clone.handle_link().list().unwrap().len(), links);
+    }
+
+    #[test]
+    fn test_fd_reuse_after_drop() {
+        let handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let clone = handle.try_clone().unwrap();
+        let fd = handle.as_raw_fd();
+        drop(handle);
+
+        assert_eq!(unsafe { libc::fcntl(fd, libc::F_GETFD) }, -1);
+        assert_eq!(
+            std::io::Error::last_os_error().raw_os_error(),
+            Some(libc::EBADF)
+        );
+
+        // A new socket may get the freed number; dropping the clone must not
+        // close it.
+        let mut reused = SocketHandle::new(libc::NETLINK_ROUTE);
+        drop(clone);
+
+        assert!(!reused.handle_link().list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_into_raw_fd() {
+        let handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let fd = handle.into_raw_fd();
+
+        // The fd is still open after the handle is gone and is closed once.
+        assert_ne!(unsafe { libc::fcntl(fd, libc::F_GETFD) }, -1);
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+
+    #[test]
+    fn test_subscribe() {
+        test_setup!();
+        let mut events = SocketHandle::new(libc::NETLINK_ROUTE);
+        events.subscribe(RTMGRP_LINK).unwrap();
+        events.set_recv_timeout(Duration::from_secs(1)).unwrap();
+
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+
+        let msgs = events.raw_recv().unwrap();
+        assert_eq!(msgs[0].header.nlmsg_type, libc::RTM_NEWLINK);
+
+        // Requests skip the notifications queued on a subscribed socket.
+        let veth = Kind::Veth {
+            attrs: LinkAttrs::new("foo"),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+        handle
+            .handle_link()
+            .add(
+                &veth,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+        assert!(events.handle_link().get(&LinkAttrs::new("foo")).is_ok());
     }
 }
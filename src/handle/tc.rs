@@ -0,0 +1,318 @@
+use std::ops::{Deref, DerefMut};
+
+use anyhow::Result;
+
+use crate::{
+    core::{
+        error::errno,
+        message::Message,
+        socket::{NetlinkTransport, Socket},
+    },
+    types::{
+        message::{Attribute, RouteAttr, TcMessage},
+        tc::{
+            cake_options, matchall_options, skbedit_action, FqCodelParams, Qdisc, TCA_KIND,
+            TC_H_CLSACT, TC_H_MIN_EGRESS, TC_H_ROOT,
+        },
+    },
+};
+
+use super::{
+    sock_handle::{RequestOptions, SocketHandle},
+    zero_terminated,
+};
+
+pub struct TcHandle<'a, S: NetlinkTransport = Socket> {
+    pub socket: &'a mut SocketHandle<S>,
+}
+
+impl<S: NetlinkTransport> Deref for TcHandle<'_, S> {
+    type Target = SocketHandle<S>;
+
+    fn deref(&self) -> &Self::Target {
+        self.socket
+    }
+}
+
+impl<S: NetlinkTransport> DerefMut for TcHandle<'_, S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.socket
+    }
+}
+
+impl<'a, S: NetlinkTransport> From<&'a mut SocketHandle<S>> for TcHandle<'a, S> {
+    fn from(socket: &'a mut SocketHandle<S>) -> Self {
+        Self { socket }
+    }
+}
+
+impl<S: NetlinkTransport> TcHandle<'_, S> {
+    /// Attaches an `fq_codel` qdisc as the root qdisc of the link.
+    pub fn add_fq_codel(&mut self, ifindex: u32, params: FqCodelParams) -> Result<()> {
+        self.handle_qdisc()
+            .add_fq_codel(ifindex, 0, TC_H_ROOT, params)
+    }
+
+    /// Attaches a `cake` qdisc as the root qdisc of the link, shaping egress
+    /// traffic to `bandwidth_bps` bits per second. `nat` makes flow isolation
+    /// look up the conntrack entry of each packet and `wash` clears the DSCP
+    /// bits after classification.
+    pub fn add_cake(
+        &mut self,
+        ifindex: u32,
+        bandwidth_bps: u64,
+        nat: bool,
+        wash: bool,
+    ) -> Result<()> {
+        self.add_root_qdisc(ifindex, "cake", cake_options(bandwidth_bps, nat, wash))
+    }
+
+    /// Sets the fwmark, the `skb->priority` and the tx queue of every packet
+    /// the link sends, with a `skbedit` action on a `matchall` filter on its
+    /// egress hook. The `clsact` qdisc holding the hook is added first unless
+    /// the link already has one. At least one of the values must be given.
+    pub fn add_skbedit_action(
+        &mut self,
+        ifindex: u32,
+        mark: Option<u32>,
+        prio: Option<u32>,
+        queue_mapping: Option<u16>,
+    ) -> Result<()> {
+        let action = skbedit_action(1, mark, prio, queue_mapping)?;
+
+        match self
+            .handle_qdisc()
+            .add(ifindex, 0xffff_0000, TC_H_CLSACT, "clsact", None)
+        {
+            Err(e) if errno(&e) != Some(libc::EEXIST) => return Err(e),
+            _ => {}
+        }
+
+        self.add_filter(
+            ifindex,
+            TC_H_CLSACT & 0xffff_0000 | TC_H_MIN_EGRESS,
+            "matchall",
+            matchall_options(vec![action]),
+        )
+    }
+
+    /// Looks up the qdisc with `handle` on the link with a single
+    /// `RTM_GETQDISC` instead of a dump, e.g. to poll its counters, or
+    /// returns `None` if the link has none. The kernel fills `parent` from the
+    /// request, so it is always 0 here.
+    pub fn get_qdisc(&mut self, ifindex: u32, handle: u32) -> Result<Option<Qdisc>> {
+        let mut req = Message::new(libc::RTM_GETQDISC, 0);
+
+        let msg = TcMessage {
+            family: libc::AF_UNSPEC as u8,
+            index: ifindex as i32,
+            handle,
+            ..Default::default()
+        };
+
+        req.add(&msg.serialize()?);
+
+        // tc_get_qdisc only unicasts the reply when the request carries
+        // NLM_F_ECHO; without it the qdisc goes out to RTNLGRP_TC alone.
+        let opts = RequestOptions {
+            ack: true,
+            echo: true,
+            ..Default::default()
+        };
+
+        match self.request_with_options(&mut req, libc::RTM_NEWQDISC, opts) {
+            Ok(res) => res
+                .first()
+                .map(|m| Qdisc::try_from(m.as_slice()))
+                .transpose(),
+            Err(e) if errno(&e) == Some(libc::ENOENT) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn add_root_qdisc(&mut self, ifindex: u32, kind: &str, options: RouteAttr) -> Result<()> {
+        self.handle_qdisc()
+            .add(ifindex, 0, TC_H_ROOT, kind, Some(options))
+    }
+
+    /// Adds a filter for every protocol below `parent`, letting the kernel
+    /// pick its handle and priority.
+    fn add_filter(
+        &mut self,
+        ifindex: u32,
+        parent: u32,
+        kind: &str,
+        options: RouteAttr,
+    ) -> Result<()> {
+        let mut req = Message::new(
+            libc::RTM_NEWTFILTER,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        );
+
+        let msg = TcMessage {
+            family: libc::AF_UNSPEC as u8,
+            index: ifindex as i32,
+            parent,
+            // The priority in the upper and the protocol in the lower 16 bits.
+            info: (libc::ETH_P_ALL as u16).to_be() as u32,
+            ..Default::default()
+        };
+
+        req.add(&msg.serialize()?);
+        req.add(&RouteAttr::new(TCA_KIND, &zero_terminated(kind)).serialize()?);
+        req.add(&options.serialize()?);
+
+        self.request(&mut req, 0)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        skip_unless_supported, test_setup,
+        types::{link::LinkAttrs, message::RouteAttrs, tc::TCA_ACT_KIND},
+    };
+
+    use super::*;
+
+    /// Returns the kind of the root qdisc of the link, if any.
+    fn root_qdisc_kind<S: NetlinkTransport>(
+        handle: &mut SocketHandle<S>,
+        ifindex: u32,
+    ) -> Option<String> {
+        let mut req = Message::new(libc::RTM_GETQDISC, libc::NLM_F_DUMP);
+        req.add(&TcMessage::default().serialize().unwrap());
+
+        handle
+            .request(&mut req, libc::RTM_NEWQDISC)
+            .unwrap()
+            .iter()
+            .filter(|m| {
+                let msg: TcMessage = bincode::deserialize(m).unwrap();
+                msg.index == ifindex as i32 && msg.parent == TC_H_ROOT
+            })
+            .find_map(|m| {
+                RouteAttrs::from(&m[20..])
+                    .into_iter()
+                    .find(|a| a.header.rta_type == TCA_KIND)
+                    .map(|a| a.payload.to_string().unwrap())
+            })
+    }
+
+    /// Probes for `matchall` and `skbedit` with a filter whose action lacks
+    /// its parameters. The kernel rejects it with `ENOENT` when either module
+    /// is missing and with `EINVAL` otherwise.
+    fn probe_skbedit(handle: &mut SocketHandle, ifindex: u32) -> Result<()> {
+        match handle
+            .handle_qdisc()
+            .add(ifindex, 0xffff_0000, TC_H_CLSACT, "clsact", None)
+        {
+            Err(e) if errno(&e) != Some(libc::EEXIST) => return Err(e),
+            _ => {}
+        }
+
+        let mut action = RouteAttr::new(1, &[]);
+        action.add_attribute(Box::new(RouteAttr::string(TCA_ACT_KIND, "skbedit")));
+
+        handle.handle_tc().add_filter(
+            ifindex,
+            TC_H_CLSACT & 0xffff_0000 | TC_H_MIN_EGRESS,
+            "matchall",
+            matchall_options(vec![action]),
+        )
+    }
+
+    #[test]
+    fn test_tc_add_fq_codel() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+        let ifindex = lo.attrs().index as u32;
+
+        let params = FqCodelParams {
+            target: Some(Duration::from_millis(5)),
+            interval: Some(Duration::from_millis(100)),
+            limit: Some(1000),
+            flows: Some(1024),
+            quantum: Some(1514),
+            ecn: Some(true),
+        };
+
+        skip_unless_supported!(
+            handle.handle_qdisc().probe(ifindex, "fq_codel"),
+            "sch_fq_codel"
+        );
+
+        handle
+            .handle_tc()
+            .add_fq_codel(ifindex, params.clone())
+            .unwrap();
+
+        assert_eq!(
+            root_qdisc_kind(&mut handle, ifindex).as_deref(),
+            Some("fq_codel")
+        );
+        assert!(handle.handle_tc().add_fq_codel(ifindex, params).is_err());
+    }
+
+    #[test]
+    fn test_tc_add_cake() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+        let ifindex = lo.attrs().index as u32;
+
+        skip_unless_supported!(handle.handle_qdisc().probe(ifindex, "cake"), "sch_cake");
+
+        handle
+            .handle_tc()
+            .add_cake(ifindex, 100_000_000, true, true)
+            .unwrap();
+
+        assert_eq!(
+            root_qdisc_kind(&mut handle, ifindex).as_deref(),
+            Some("cake")
+        );
+    }
+
+    #[test]
+    fn test_tc_add_skbedit_action() {
+        test_setup!();
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+
+        let mut link_handle = handle.handle_link();
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+        let ifindex = lo.attrs().index as u32;
+
+        skip_unless_supported!(
+            probe_skbedit(&mut handle, ifindex),
+            "cls_matchall and act_skbedit"
+        );
+
+        handle
+            .handle_tc()
+            .add_skbedit_action(ifindex, Some(0x10), Some(7), None)
+            .unwrap();
+
+        // The clsact qdisc of the first call is reused.
+        handle
+            .handle_tc()
+            .add_skbedit_action(ifindex, None, None, Some(0))
+            .unwrap();
+        assert!(handle
+            .handle_tc()
+            .add_skbedit_action(ifindex, None, None, None)
+            .is_err());
+    }
+}
@@ -1,28 +1,42 @@
-use anyhow::{anyhow, Result};
+#[cfg(target_os = "linux")]
+use anyhow::Result;
 
+#[cfg(target_os = "linux")]
+use crate::types::link::MacAddr;
+
+// The sockets and the handles built on them only exist on Linux, while the
+// message and attribute types in `types` are plain data and compile anywhere.
+#[cfg(target_os = "linux")]
 pub mod core;
+#[cfg(target_os = "linux")]
 pub mod handle;
+#[cfg(target_os = "linux")]
 pub mod netlink;
+#[cfg(target_os = "linux")]
+pub mod proc;
 pub mod types;
 
+#[cfg(target_os = "linux")]
 const RTA_MTU: u16 = 0x2;
+#[cfg(target_os = "linux")]
 const RTA_VIA: u16 = 18;
+#[cfg(target_os = "linux")]
+const RTA_UID: u16 = 25;
 
 pub fn align_of(len: usize, align_to: usize) -> usize {
     (len + align_to - 1) & !(align_to - 1)
 }
 
-pub fn parse_mac(mac: &str) -> Result<Vec<u8>> {
-    let mac = mac
-        .split(':')
-        .map(|s| u8::from_str_radix(s, 16))
-        .collect::<Result<Vec<u8>, _>>()?;
-
-    if mac.len() != 6 {
-        return Err(anyhow!("Invalid MAC address"));
-    }
+pub fn zero_terminated(s: &str) -> Vec<u8> {
+    let mut v = Vec::with_capacity(s.len() + 1);
+    v.extend_from_slice(s.as_bytes());
+    v.push(0);
+    v
+}
 
-    Ok(mac)
+#[cfg(target_os = "linux")]
+pub fn parse_mac(mac: &str) -> Result<Vec<u8>> {
+    Ok(mac.parse::<MacAddr>()?.0.to_vec())
 }
 
 #[cfg(test)]
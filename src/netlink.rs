@@ -8,7 +8,7 @@ use crate::{
     types::{
         addr::{AddrCmd, AddrFamily, Address},
         generic::{GenlFamilies, GenlFamily},
-        link::{Link, LinkAttrs},
+        link::{Link, LinkAttrs, LinkRef, Namespace},
         neigh::Neighbor,
         routing::{Routing, RtCmd},
     },
@@ -102,7 +102,10 @@ impl Netlink {
             .entry(libc::NETLINK_ROUTE)
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE))
             .handle_link()
-            .set_master(link, master_index)
+            .set_master(
+                link,
+                (master_index > 0).then_some(LinkRef::Index(master_index as u32)),
+            )
     }
 
     pub fn link_set_ns<T: Link + ?Sized>(&mut self, link: &T, ns: i32) -> Result<()> {
@@ -110,7 +113,7 @@ impl Netlink {
             .entry(libc::NETLINK_ROUTE)
             .or_insert(SocketHandle::new(libc::NETLINK_ROUTE))
             .handle_link()
-            .set_ns(link, ns)
+            .set_ns(link, Namespace::Fd(ns), None)
     }
 
     pub fn link_set_name<T: Link + ?Sized>(&mut self, link: &T, name: &str) -> Result<()> {
@@ -244,7 +247,7 @@ impl Netlink {
 mod tests {
     use crate::{
         test_setup,
-        types::link::{Kind, VxlanAttrs},
+        types::link::{Kind, OperState, VxlanAttrs},
     };
 
     use super::*;
@@ -263,7 +266,7 @@ mod tests {
         netlink.link_up(&link).unwrap();
 
         let link = netlink.link_get(&LinkAttrs::new("foo")).unwrap();
-        assert_ne!(link.attrs().oper_state, 2);
+        assert_ne!(link.attrs().oper_state, OperState::Down);
     }
 
     #[test]
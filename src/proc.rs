@@ -0,0 +1,85 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+
+const PROC: &str = "/proc";
+
+/// Finds the process holding a socket, e.g. one returned by
+/// `SockDiagHandle::tcp_info`, by scanning `/proc/<pid>/fd` for a link to
+/// `socket:[<inode>]`. Processes that exit or whose descriptors are not
+/// readable by the caller are skipped, so without `CAP_SYS_PTRACE` only the
+/// caller's own processes are found. When several processes share the socket,
+/// the one with the lowest pid is returned.
+pub fn inode_to_pid(inode: u32) -> Result<Option<u32>> {
+    let proc = Path::new(PROC);
+    let target = format!("socket:[{}]", inode);
+
+    let mut pids: Vec<u32> = fs::read_dir(proc)?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort_unstable();
+
+    for pid in pids {
+        let fds = match fs::read_dir(proc.join(pid.to_string()).join("fd")) {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+
+        for fd in fds.flatten() {
+            if fs::read_link(fd.path()).is_ok_and(|link| link.as_os_str() == target.as_str()) {
+                return Ok(Some(pid));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the command line of the process holding the socket with `inode`,
+/// with its arguments separated by spaces.
+pub fn inode_to_cmdline(inode: u32) -> Result<Option<String>> {
+    let pid = match inode_to_pid(inode)? {
+        Some(pid) => pid,
+        None => return Ok(None),
+    };
+
+    let cmdline = match fs::read(Path::new(PROC).join(pid.to_string()).join("cmdline")) {
+        Ok(cmdline) => cmdline,
+        // The process exited in between.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let args: Vec<_> = cmdline
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(String::from_utf8_lossy)
+        .collect();
+
+    Ok(Some(args.join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, os::fd::AsRawFd};
+
+    use super::*;
+
+    #[test]
+    fn test_inode_to_pid() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { libc::fstat(listener.as_raw_fd(), &mut stat) }, 0);
+        let inode = stat.st_ino as u32;
+
+        assert_eq!(inode_to_pid(inode).unwrap(), Some(std::process::id()));
+
+        let cmdline = inode_to_cmdline(inode).unwrap().unwrap();
+        let exe = std::env::args().next().unwrap();
+        assert!(cmdline.starts_with(&exe));
+
+        drop(listener);
+        assert_eq!(inode_to_pid(inode).unwrap(), None);
+    }
+}
@@ -1,14 +1,26 @@
 use std::net::IpAddr;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use derive_builder::Builder;
 use ipnet::IpNet;
 
 use super::{
-    message::{AddressMessage, Attribute, RouteAttrs},
+    message::{Attribute, IfAddrMsg, PrefixMessage, RouteAttrs},
     vec_to_addr,
 };
 
+pub const PREFIX_ADDRESS: u16 = 1;
+pub const PREFIX_CACHEINFO: u16 = 2;
+
+pub const IFA_FLAGS: u16 = 8;
+
+pub const IFA_F_NODAD: u32 = 0x02;
+pub const IFA_F_PERMANENT: u32 = 0x80;
+pub const IFA_F_NOPREFIXROUTE: u32 = 0x200;
+
+pub const IF_PREFIX_ONLINK: u8 = 0x01;
+pub const IF_PREFIX_AUTOCONF: u8 = 0x02;
+
 pub enum AddrCmd {
     Add,
     Change,
@@ -44,7 +56,9 @@ pub struct Address {
     pub index: i32,
     pub ip: IpNet,
     pub label: String,
-    pub flags: u8,
+    /// The `IFA_F_*` flags. Those that do not fit the 8 bits of the
+    /// `ifaddrmsg`, e.g. `IFA_F_NOPREFIXROUTE`, are carried in `IFA_FLAGS`.
+    pub flags: u32,
     pub scope: u8,
     pub broadcast: Option<IpAddr>,
     pub peer: Option<IpNet>,
@@ -54,11 +68,12 @@ pub struct Address {
 
 impl From<&[u8]> for Address {
     fn from(buf: &[u8]) -> Self {
-        let addr_msg: AddressMessage = bincode::deserialize(buf).unwrap();
+        let addr_msg: IfAddrMsg = bincode::deserialize(buf).unwrap();
         let attrs = RouteAttrs::from(&buf[addr_msg.len()..]);
 
         let mut addr = Self {
             index: addr_msg.index,
+            flags: addr_msg.flags as u32,
             scope: addr_msg.scope,
             ..Default::default()
         };
@@ -70,6 +85,7 @@ impl From<&[u8]> for Address {
                         .unwrap();
                 }
                 libc::IFA_LOCAL => {}
+                IFA_FLAGS => addr.flags = attr.payload.to_u32().unwrap(),
                 _ => {}
             }
         }
@@ -86,6 +102,62 @@ impl Address {
     }
 }
 
+#[repr(C)]
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug, Clone, Copy, PartialEq)]
+pub struct PrefixCacheInfo {
+    pub preferred_time: u32,
+    pub valid_time: u32,
+}
+
+/// A prefix learned from a Router Advertisement, as announced by
+/// `RTM_NEWPREFIX` on `RTNLGRP_IPV6_PREFIX`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefixEvent {
+    pub index: i32,
+    pub prefix: IpNet,
+    pub flags: u8,
+    pub cache_info: PrefixCacheInfo,
+}
+
+impl PrefixEvent {
+    pub fn on_link(&self) -> bool {
+        self.flags & IF_PREFIX_ONLINK != 0
+    }
+
+    pub fn autoconf(&self) -> bool {
+        self.flags & IF_PREFIX_AUTOCONF != 0
+    }
+}
+
+impl TryFrom<&[u8]> for PrefixEvent {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg: PrefixMessage = bincode::deserialize(buf)?;
+        let attrs = RouteAttrs::from(&buf[msg.len()..]);
+
+        let mut prefix = None;
+        let mut cache_info = PrefixCacheInfo::default();
+
+        for attr in attrs {
+            match attr.header.rta_type {
+                PREFIX_ADDRESS => {
+                    prefix = Some(IpNet::new(vec_to_addr(&attr.payload)?, msg.prefix_len)?)
+                }
+                PREFIX_CACHEINFO => cache_info = bincode::deserialize(&attr.payload)?,
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            index: msg.index,
+            prefix: prefix.ok_or_else(|| anyhow!("prefix message without PREFIX_ADDRESS"))?,
+            flags: msg.flags,
+            cache_info,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::types::message::{Payload, RouteAttr, RouteAttrHeader};
@@ -127,7 +199,7 @@ mod tests {
 
     #[test]
     fn test_from_bytes() {
-        let addr_msg = AddressMessage {
+        let addr_msg = IfAddrMsg {
             index: 1,
             scope: 2,
             prefix_len: 24,
@@ -143,7 +215,7 @@ mod tests {
             attributes: None,
         });
 
-        let mut buf = AddressMessage::serialize(&addr_msg).unwrap();
+        let mut buf = IfAddrMsg::serialize(&addr_msg).unwrap();
         buf.extend_from_slice(RouteAttrs::serialize(&rt_attrs).unwrap().as_slice());
 
         let address = Address::from(&buf[..]);
@@ -151,4 +223,43 @@ mod tests {
         assert_eq!(address.index, addr_msg.index);
         assert_eq!(address.scope, addr_msg.scope);
     }
+
+    #[test]
+    fn test_prefix_event_try_from() {
+        let msg = PrefixMessage {
+            family: libc::AF_INET6 as u8,
+            index: 3,
+            prefix_type: 3,
+            prefix_len: 64,
+            flags: IF_PREFIX_ONLINK | IF_PREFIX_AUTOCONF,
+            ..Default::default()
+        };
+        let address: std::net::Ipv6Addr = "2001:db8:1::".parse().unwrap();
+        let cache_info = PrefixCacheInfo {
+            preferred_time: 1800,
+            valid_time: 3600,
+        };
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(
+            RouteAttr::new(PREFIX_ADDRESS, &address.octets())
+                .serialize()
+                .unwrap(),
+        );
+        buf.extend(
+            RouteAttr::new(PREFIX_CACHEINFO, &bincode::serialize(&cache_info).unwrap())
+                .serialize()
+                .unwrap(),
+        );
+
+        let event = PrefixEvent::try_from(&buf[..]).unwrap();
+
+        assert_eq!(event.index, 3);
+        assert_eq!(event.prefix, "2001:db8:1::/64".parse::<IpNet>().unwrap());
+        assert_eq!(event.cache_info, cache_info);
+        assert!(event.on_link());
+        assert!(event.autoconf());
+
+        assert!(PrefixEvent::try_from(&buf[..msg.len()]).is_err());
+    }
 }
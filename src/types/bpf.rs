@@ -0,0 +1,141 @@
+use std::os::fd::RawFd;
+
+use anyhow::Result;
+use bitflags::bitflags;
+
+use super::{
+    message::{RouteAttr, RouteAttrs},
+    NLA_F_NESTED,
+};
+
+pub const IFLA_XDP: u16 = 43;
+
+pub const IFLA_XDP_FD: u16 = 1;
+pub const IFLA_XDP_ATTACHED: u16 = 2;
+pub const IFLA_XDP_FLAGS: u16 = 3;
+pub const IFLA_XDP_PROG_ID: u16 = 4;
+pub const IFLA_XDP_DRV_PROG_ID: u16 = 5;
+pub const IFLA_XDP_SKB_PROG_ID: u16 = 6;
+pub const IFLA_XDP_HW_PROG_ID: u16 = 7;
+
+pub const XDP_ATTACHED_NONE: u8 = 0;
+pub const XDP_ATTACHED_DRV: u8 = 1;
+pub const XDP_ATTACHED_SKB: u8 = 2;
+pub const XDP_ATTACHED_HW: u8 = 3;
+pub const XDP_ATTACHED_MULTI: u8 = 4;
+
+bitflags! {
+    /// The `XDP_FLAGS_*` passed along with an XDP program. Without a mode
+    /// flag the kernel picks the driver mode if supported and falls back to
+    /// the generic SKB mode otherwise.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct XdpFlags: u32 {
+        /// Fails instead of replacing a program that is already attached.
+        const UPDATE_IF_NOEXIST = 1 << 0;
+        /// Runs the program in the generic, driver independent mode.
+        const SKB_MODE = 1 << 1;
+        /// Runs the program natively in the driver.
+        const DRV_MODE = 1 << 2;
+        /// Offloads the program to the NIC.
+        const HW_MODE = 1 << 3;
+        const REPLACE = 1 << 4;
+    }
+}
+
+/// Builds the nested `IFLA_XDP` attribute attaching `prog_fd`, or detaching
+/// the current program if `prog_fd` is -1.
+pub fn xdp_attr(prog_fd: RawFd, flags: XdpFlags) -> RouteAttr {
+    let mut xdp = RouteAttr::new(IFLA_XDP | NLA_F_NESTED, &[]);
+
+    xdp.add(IFLA_XDP_FD, &prog_fd.to_ne_bytes());
+    if !flags.is_empty() {
+        xdp.add(IFLA_XDP_FLAGS, &flags.bits().to_ne_bytes());
+    }
+
+    xdp
+}
+
+/// The XDP state of a link, parsed from the nested `IFLA_XDP` of a link
+/// dump. With `XDP_ATTACHED_MULTI` programs are attached in several modes at
+/// once and `prog_id` is 0, so only the per mode ids are set.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct XdpInfo {
+    /// One of the `XDP_ATTACHED_*` modes.
+    pub attached: u8,
+    pub prog_id: u32,
+    pub drv_prog_id: u32,
+    pub skb_prog_id: u32,
+    pub hw_prog_id: u32,
+}
+
+impl XdpInfo {
+    pub fn is_attached(&self) -> bool {
+        self.prog_id != 0 || self.drv_prog_id != 0 || self.skb_prog_id != 0 || self.hw_prog_id != 0
+    }
+}
+
+impl TryFrom<&[u8]> for XdpInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let mut info = XdpInfo::default();
+
+        for attr in RouteAttrs::from(buf) {
+            match attr.header.rta_type {
+                IFLA_XDP_ATTACHED => info.attached = attr.payload.as_u8()?,
+                IFLA_XDP_PROG_ID => info.prog_id = attr.payload.as_u32()?,
+                IFLA_XDP_DRV_PROG_ID => info.drv_prog_id = attr.payload.as_u32()?,
+                IFLA_XDP_SKB_PROG_ID => info.skb_prog_id = attr.payload.as_u32()?,
+                IFLA_XDP_HW_PROG_ID => info.hw_prog_id = attr.payload.as_u32()?,
+                _ => {}
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::message::Attribute;
+
+    use super::*;
+
+    #[rustfmt::skip]
+    static XDP_DETACH: [u8; 20] = [
+        0x14, 0x00, 0x2b, 0x80, // xdp L=20,T=43|NLA_F_NESTED
+        0x08, 0x00, 0x01, 0x00, 0xff, 0xff, 0xff, 0xff, // fd = -1
+        0x08, 0x00, 0x03, 0x00, 0x02, 0x00, 0x00, 0x00, // flags = SKB_MODE
+    ];
+
+    #[test]
+    fn test_xdp_attr() {
+        assert_eq!(
+            xdp_attr(-1, XdpFlags::SKB_MODE).serialize().unwrap(),
+            XDP_DETACH
+        );
+        assert_eq!(
+            xdp_attr(3, XdpFlags::empty()).serialize().unwrap().len(),
+            12
+        );
+    }
+
+    #[rustfmt::skip]
+    static XDP_SKB_ATTACHED: [u8; 20] = [
+        0x05, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, // attached = XDP_ATTACHED_SKB
+        0x08, 0x00, 0x04, 0x00, 0x2a, 0x00, 0x00, 0x00, // prog_id = 42
+        0x00, 0x00, 0x00, 0x00, // trailing padding
+    ];
+
+    #[test]
+    fn test_xdp_info() {
+        let info = XdpInfo::try_from(&XDP_SKB_ATTACHED[..16]).unwrap();
+        assert_eq!(info.attached, XDP_ATTACHED_SKB);
+        assert_eq!(info.prog_id, 42);
+        assert!(info.is_attached());
+
+        let none = XdpInfo::try_from(&XDP_SKB_ATTACHED[..8]).unwrap();
+        assert_eq!(none.attached, XDP_ATTACHED_SKB);
+        assert!(!none.is_attached());
+    }
+}
@@ -0,0 +1,269 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use anyhow::{anyhow, Result};
+
+use super::{
+    message::{NfGenMessage, RouteAttrs},
+    vec_to_addr, NLA_TYPE_MASK,
+};
+
+pub const IPCTNL_MSG_CT_NEW: u16 = 0;
+pub const IPCTNL_MSG_CT_GET: u16 = 1;
+
+pub const CTA_TUPLE_ORIG: u16 = 1;
+pub const CTA_TUPLE_REPLY: u16 = 2;
+pub const CTA_STATUS: u16 = 3;
+pub const CTA_PROTOINFO: u16 = 4;
+pub const CTA_TIMEOUT: u16 = 7;
+pub const CTA_MARK: u16 = 8;
+pub const CTA_COUNTERS_ORIG: u16 = 9;
+pub const CTA_COUNTERS_REPLY: u16 = 10;
+pub const CTA_ID: u16 = 12;
+
+pub const CTA_TUPLE_IP: u16 = 1;
+pub const CTA_TUPLE_PROTO: u16 = 2;
+
+pub const CTA_IP_V4_SRC: u16 = 1;
+pub const CTA_IP_V4_DST: u16 = 2;
+pub const CTA_IP_V6_SRC: u16 = 3;
+pub const CTA_IP_V6_DST: u16 = 4;
+
+pub const CTA_PROTO_NUM: u16 = 1;
+pub const CTA_PROTO_SRC_PORT: u16 = 2;
+pub const CTA_PROTO_DST_PORT: u16 = 3;
+
+pub const CTA_PROTOINFO_TCP: u16 = 1;
+pub const CTA_PROTOINFO_TCP_STATE: u16 = 1;
+
+pub const CTA_COUNTERS_PACKETS: u16 = 1;
+pub const CTA_COUNTERS_BYTES: u16 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConntrackTuple {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub protocol: u8,
+    pub src_port: u16,
+    pub dst_port: u16,
+}
+
+impl Default for ConntrackTuple {
+    fn default() -> Self {
+        Self {
+            src: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            dst: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            protocol: 0,
+            src_port: 0,
+            dst_port: 0,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for ConntrackTuple {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let mut tuple = ConntrackTuple::default();
+
+        for attr in RouteAttrs::from(buf) {
+            match attr.header.rta_type & NLA_TYPE_MASK {
+                CTA_TUPLE_IP => {
+                    for a in RouteAttrs::from(attr.payload.as_slice()) {
+                        match a.header.rta_type & NLA_TYPE_MASK {
+                            CTA_IP_V4_SRC | CTA_IP_V6_SRC => tuple.src = vec_to_addr(&a.payload)?,
+                            CTA_IP_V4_DST | CTA_IP_V6_DST => tuple.dst = vec_to_addr(&a.payload)?,
+                            _ => {}
+                        }
+                    }
+                }
+                CTA_TUPLE_PROTO => {
+                    for a in RouteAttrs::from(attr.payload.as_slice()) {
+                        match a.header.rta_type & NLA_TYPE_MASK {
+                            CTA_PROTO_NUM => tuple.protocol = a.payload.as_u8()?,
+                            CTA_PROTO_SRC_PORT => tuple.src_port = a.payload.as_u16_be()?,
+                            CTA_PROTO_DST_PORT => tuple.dst_port = a.payload.as_u16_be()?,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(tuple)
+    }
+}
+
+/// Packet and byte counters of one direction, only reported when
+/// `net.netfilter.nf_conntrack_acct` is enabled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConntrackCounters {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+impl TryFrom<&[u8]> for ConntrackCounters {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let mut counters = ConntrackCounters::default();
+
+        for attr in RouteAttrs::from(buf) {
+            match attr.header.rta_type & NLA_TYPE_MASK {
+                CTA_COUNTERS_PACKETS => counters.packets = attr.payload.as_u64_be()?,
+                CTA_COUNTERS_BYTES => counters.bytes = attr.payload.as_u64_be()?,
+                _ => {}
+            }
+        }
+
+        Ok(counters)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConntrackEntry {
+    pub family: u8,
+    pub id: u32,
+    pub original: ConntrackTuple,
+    pub reply: ConntrackTuple,
+    pub status: u32,
+    pub tcp_state: Option<u8>,
+    pub timeout: u32,
+    pub mark: u32,
+    pub counters_original: Option<ConntrackCounters>,
+    pub counters_reply: Option<ConntrackCounters>,
+}
+
+impl TryFrom<&[u8]> for ConntrackEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg: NfGenMessage = bincode::deserialize(buf)?;
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("conntrack message too short: {} bytes", buf.len()))?,
+        );
+
+        let mut entry = ConntrackEntry {
+            family: msg.family,
+            ..Default::default()
+        };
+        let mut has_tuple = false;
+
+        for attr in attrs {
+            let be_u32 = || -> Result<u32> { Ok(u32::from_be(attr.payload.to_u32()?)) };
+
+            match attr.header.rta_type & NLA_TYPE_MASK {
+                CTA_TUPLE_ORIG => {
+                    entry.original = ConntrackTuple::try_from(attr.payload.as_slice())?;
+                    has_tuple = true;
+                }
+                CTA_TUPLE_REPLY => entry.reply = ConntrackTuple::try_from(attr.payload.as_slice())?,
+                CTA_STATUS => entry.status = be_u32()?,
+                CTA_TIMEOUT => entry.timeout = be_u32()?,
+                CTA_MARK => entry.mark = be_u32()?,
+                CTA_ID => entry.id = be_u32()?,
+                CTA_PROTOINFO => {
+                    for a in RouteAttrs::from(attr.payload.as_slice()) {
+                        if a.header.rta_type & NLA_TYPE_MASK != CTA_PROTOINFO_TCP {
+                            continue;
+                        }
+                        for tcp in RouteAttrs::from(a.payload.as_slice()) {
+                            if tcp.header.rta_type & NLA_TYPE_MASK == CTA_PROTOINFO_TCP_STATE {
+                                entry.tcp_state = Some(tcp.payload.as_u8()?);
+                            }
+                        }
+                    }
+                }
+                CTA_COUNTERS_ORIG => {
+                    entry.counters_original =
+                        Some(ConntrackCounters::try_from(attr.payload.as_slice())?)
+                }
+                CTA_COUNTERS_REPLY => {
+                    entry.counters_reply =
+                        Some(ConntrackCounters::try_from(attr.payload.as_slice())?)
+                }
+                _ => {}
+            }
+        }
+
+        if !has_tuple {
+            return Err(anyhow!("conntrack entry without CTA_TUPLE_ORIG"));
+        }
+
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static CT_NEW_MSG: [u8; 140] = [
+        0x02, 0x00, 0x00, 0x00, // nfgenmsg: family = AF_INET, version = 0, res_id = 0
+        0x34, 0x00, 0x01, 0x80, // tuple orig L=52,T=1|NLA_F_NESTED
+        0x14, 0x00, 0x01, 0x80, // ip L=20,T=1|NLA_F_NESTED
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x01, // v4 src 10.0.0.1
+        0x08, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x00, 0x02, // v4 dst 10.0.0.2
+        0x1c, 0x00, 0x02, 0x80, // proto L=28,T=2|NLA_F_NESTED
+        0x05, 0x00, 0x01, 0x00, 0x06, 0x00, 0x00, 0x00, // num = TCP
+        0x06, 0x00, 0x02, 0x00, 0x9c, 0x40, 0x00, 0x00, // src port 40000 (be)
+        0x06, 0x00, 0x03, 0x00, 0x00, 0x50, 0x00, 0x00, // dst port 80 (be)
+        0x34, 0x00, 0x02, 0x80, // tuple reply L=52,T=2|NLA_F_NESTED
+        0x14, 0x00, 0x01, 0x80,
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x02,
+        0x08, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x00, 0x01,
+        0x1c, 0x00, 0x02, 0x80,
+        0x05, 0x00, 0x01, 0x00, 0x06, 0x00, 0x00, 0x00,
+        0x06, 0x00, 0x02, 0x00, 0x00, 0x50, 0x00, 0x00,
+        0x06, 0x00, 0x03, 0x00, 0x9c, 0x40, 0x00, 0x00,
+        0x08, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x8e, // status 0x18e (be)
+        0x08, 0x00, 0x07, 0x00, 0x00, 0x06, 0x97, 0x78, // timeout 431992 (be)
+        0x10, 0x00, 0x04, 0x80, // protoinfo L=16,T=4|NLA_F_NESTED
+        0x0c, 0x00, 0x01, 0x80, // tcp L=12,T=1|NLA_F_NESTED
+        0x05, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00, // state = ESTABLISHED
+    ];
+
+    #[test]
+    fn test_conntrack_entry_try_from() {
+        let entry = ConntrackEntry::try_from(&CT_NEW_MSG[..]).unwrap();
+
+        assert_eq!(entry.family, libc::AF_INET as u8);
+        assert_eq!(
+            entry.original,
+            ConntrackTuple {
+                src: "10.0.0.1".parse().unwrap(),
+                dst: "10.0.0.2".parse().unwrap(),
+                protocol: libc::IPPROTO_TCP as u8,
+                src_port: 40000,
+                dst_port: 80,
+            }
+        );
+        assert_eq!(entry.reply.src, entry.original.dst);
+        assert_eq!(entry.reply.src_port, 80);
+        assert_eq!(entry.status, 0x18e);
+        assert_eq!(entry.timeout, 431992);
+        assert_eq!(entry.tcp_state, Some(3));
+        assert_eq!(entry.counters_original, None);
+
+        assert!(ConntrackEntry::try_from(&CT_NEW_MSG[..4]).is_err());
+        assert!(ConntrackEntry::try_from(&CT_NEW_MSG[..2]).is_err());
+
+        // CTA_PROTOINFO_TCP_STATE without a payload.
+        let mut empty_state = CT_NEW_MSG;
+        empty_state[132] = 0x04;
+        assert!(ConntrackEntry::try_from(&empty_state[..]).is_err());
+    }
+
+    #[test]
+    fn test_conntrack_truncated_attrs() {
+        // CTA_TUPLE_PROTO with an empty CTA_PROTO_NUM.
+        let tuple = [0x08, 0x00, 0x02, 0x80, 0x04, 0x00, 0x01, 0x00];
+        assert!(ConntrackTuple::try_from(&tuple[..]).is_err());
+
+        // CTA_COUNTERS_PACKETS with a 4 byte payload.
+        let counters = [0x08, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01];
+        assert!(ConntrackCounters::try_from(&counters[..]).is_err());
+    }
+}
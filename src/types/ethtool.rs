@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use anyhow::{anyhow, Result};
+
+use super::{message::RouteAttrs, NLA_TYPE_MASK};
+
+pub const ETHTOOL_GENL_NAME: &str = "ethtool";
+pub const ETHTOOL_GENL_VERSION: u8 = 1;
+
+pub const ETHTOOL_MSG_LINKSTATE_GET: u8 = 6;
+pub const ETHTOOL_MSG_STATS_GET: u8 = 32;
+
+pub const ETHTOOL_A_HEADER_DEV_INDEX: u16 = 1;
+
+pub const ETHTOOL_A_LINKSTATE_HEADER: u16 = 1;
+pub const ETHTOOL_A_LINKSTATE_LINK: u16 = 2;
+
+pub const ETHTOOL_A_STATS_HEADER: u16 = 1;
+pub const ETHTOOL_A_STATS_GROUPS: u16 = 2;
+pub const ETHTOOL_A_STATS_GRP: u16 = 3;
+
+pub const ETHTOOL_A_STATS_GRP_ID: u16 = 2;
+pub const ETHTOOL_A_STATS_GRP_STAT: u16 = 4;
+
+pub const ETHTOOL_A_BITSET_NOMASK: u16 = 1;
+pub const ETHTOOL_A_BITSET_SIZE: u16 = 2;
+pub const ETHTOOL_A_BITSET_VALUE: u16 = 4;
+
+pub const ETHTOOL_STATS_ETH_PHY: u32 = 0;
+pub const ETHTOOL_STATS_ETH_MAC: u32 = 1;
+pub const ETHTOOL_STATS_ETH_CTRL: u32 = 2;
+pub const ETHTOOL_STATS_RMON: u32 = 3;
+pub const ETHTOOL_STATS_CNT: u32 = 4;
+
+const STATS_ETH_PHY_NAMES: &[&str] = &["SymbolErrorDuringCarrier"];
+
+const STATS_ETH_MAC_NAMES: &[&str] = &[
+    "FramesTransmittedOK",
+    "SingleCollisionFrames",
+    "MultipleCollisionFrames",
+    "FramesReceivedOK",
+    "FrameCheckSequenceErrors",
+    "AlignmentErrors",
+    "OctetsTransmittedOK",
+    "FramesWithDeferredXmissions",
+    "LateCollisions",
+    "FramesAbortedDueToXSColls",
+    "FramesLostDueToIntMACXmitError",
+    "CarrierSenseErrors",
+    "OctetsReceivedOK",
+    "FramesLostDueToIntMACRcvError",
+    "MulticastFramesXmittedOK",
+    "BroadcastFramesXmittedOK",
+    "FramesWithExcessiveDeferral",
+    "MulticastFramesReceivedOK",
+    "BroadcastFramesReceivedOK",
+    "InRangeLengthErrors",
+    "OutOfRangeLengthField",
+    "FrameTooLongErrors",
+];
+
+const STATS_ETH_CTRL_NAMES: &[&str] = &[
+    "MACControlFramesTransmitted",
+    "MACControlFramesReceived",
+    "UnsupportedOpcodesReceived",
+];
+
+const STATS_RMON_NAMES: &[&str] = &[
+    "etherStatsUndersizePkts",
+    "etherStatsOversizePkts",
+    "etherStatsFragments",
+    "etherStatsJabbers",
+];
+
+/// Standard statistics of a device keyed by their IEEE 802.3 / RMON names,
+/// as reported by `ETHTOOL_MSG_STATS_GET`.
+#[derive(Debug, Default, Clone)]
+pub struct EthtoolStats(HashMap<String, u64>);
+
+impl Deref for EthtoolStats {
+    type Target = HashMap<String, u64>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for EthtoolStats {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<EthtoolStats> for HashMap<String, u64> {
+    fn from(stats: EthtoolStats) -> Self {
+        stats.0
+    }
+}
+
+impl TryFrom<&[u8]> for EthtoolStats {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("ethtool message too short: {} bytes", buf.len()))?,
+        );
+        let mut stats = EthtoolStats::default();
+
+        for attr in attrs.iter() {
+            if attr.header.rta_type & NLA_TYPE_MASK != ETHTOOL_A_STATS_GRP {
+                continue;
+            }
+
+            let grp_attrs = RouteAttrs::from(attr.payload.as_slice());
+            let mut grp_id = None;
+            let mut values = Vec::new();
+
+            for a in grp_attrs {
+                match a.header.rta_type & NLA_TYPE_MASK {
+                    ETHTOOL_A_STATS_GRP_ID => grp_id = Some(a.payload.to_u32()?),
+                    ETHTOOL_A_STATS_GRP_STAT => {
+                        for stat in RouteAttrs::from(a.payload.as_slice()) {
+                            values.push((stat.header.rta_type, stat.payload.as_u64()?));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let (group, names) = match grp_id {
+                Some(ETHTOOL_STATS_ETH_PHY) => ("eth-phy", STATS_ETH_PHY_NAMES),
+                Some(ETHTOOL_STATS_ETH_MAC) => ("eth-mac", STATS_ETH_MAC_NAMES),
+                Some(ETHTOOL_STATS_ETH_CTRL) => ("eth-ctrl", STATS_ETH_CTRL_NAMES),
+                Some(ETHTOOL_STATS_RMON) => ("rmon", STATS_RMON_NAMES),
+                _ => ("unknown", &[][..]),
+            };
+
+            for (index, value) in values {
+                let name = match names.get(index as usize) {
+                    Some(name) => name.to_string(),
+                    None => format!("{}-{}", group, index),
+                };
+                stats.insert(name, value);
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static STATS_REPLY: [u8; 60] = [
+        0x20, 0x01, 0x00, 0x00, // genlmsghdr: cmd = STATS_GET_REPLY, version = 1
+        0x10, 0x00, 0x01, 0x00, // header L=16,T=1
+        0x0c, 0x00, 0x02, 0x00, 0x6c, 0x6f, 0x00, 0x00, // dev name (ignored)
+        0x00, 0x00, 0x00, 0x00,
+        0x28, 0x00, 0x03, 0x80, // grp L=40,T=3|NLA_F_NESTED
+        0x08, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, // id L=8,T=2,V=eth-mac
+        0x1c, 0x00, 0x04, 0x80, // stat L=28,T=4|NLA_F_NESTED
+        0x0c, 0x00, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // [0] = 42
+        0x0c, 0x00, 0x2a, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // [42] = 7
+    ];
+
+    #[test]
+    fn test_ethtool_stats_try_from() {
+        let stats = EthtoolStats::try_from(&STATS_REPLY[..]).unwrap();
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["FramesTransmittedOK"], 42);
+        assert_eq!(stats["eth-mac-42"], 7);
+
+        assert!(EthtoolStats::try_from(&STATS_REPLY[..2]).is_err());
+
+        // A stat with a 4 byte payload.
+        let mut short = STATS_REPLY;
+        short[36] = 0x08;
+        assert!(EthtoolStats::try_from(&short[..]).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Result};
+
+use super::message::RouteAttrs;
+
+pub const FOU_GENL_NAME: &str = "fou";
+pub const FOU_GENL_VERSION: u8 = 1;
+
+pub const FOU_CMD_ADD: u8 = 1;
+pub const FOU_CMD_DEL: u8 = 2;
+pub const FOU_CMD_GET: u8 = 3;
+
+pub const FOU_ATTR_PORT: u16 = 1;
+pub const FOU_ATTR_AF: u16 = 2;
+pub const FOU_ATTR_IPPROTO: u16 = 3;
+pub const FOU_ATTR_TYPE: u16 = 4;
+
+pub const FOU_ENCAP_DIRECT: u8 = 1;
+pub const FOU_ENCAP_GUE: u8 = 2;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FouEntry {
+    pub port: u16,
+    pub family: u8,
+    pub protocol: u8,
+    pub gue: bool,
+}
+
+impl TryFrom<&[u8]> for FouEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("fou message too short: {} bytes", buf.len()))?,
+        );
+        let mut entry = FouEntry::default();
+
+        for attr in attrs {
+            match attr.header.rta_type {
+                FOU_ATTR_PORT => entry.port = attr.payload.as_u16_be()?,
+                FOU_ATTR_AF => entry.family = attr.payload.as_u8()?,
+                FOU_ATTR_IPPROTO => entry.protocol = attr.payload.as_u8()?,
+                FOU_ATTR_TYPE => entry.gue = attr.payload.as_u8()? == FOU_ENCAP_GUE,
+                _ => {}
+            }
+        }
+
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static FOU_GET_MSG: [u8; 28] = [
+        0x03, 0x01, 0x00, 0x00, // genlmsghdr: cmd = FOU_CMD_GET, version = 1
+        0x06, 0x00, 0x01, 0x00, 0x15, 0xb3, 0x00, 0x00, // port L=6,T=1,V=5555 (be)
+        0x05, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, // af L=5,T=2,V=AF_INET
+        0x05, 0x00, 0x04, 0x00, 0x02, 0x00, 0x00, 0x00, // type L=5,T=4,V=GUE
+    ];
+
+    #[test]
+    fn test_fou_entry_try_from() {
+        let entry = FouEntry::try_from(&FOU_GET_MSG[..]).unwrap();
+
+        assert_eq!(entry.port, 5555);
+        assert_eq!(entry.family, libc::AF_INET as u8);
+        assert_eq!(entry.protocol, 0);
+        assert!(entry.gue);
+
+        assert!(FouEntry::try_from(&FOU_GET_MSG[..2]).is_err());
+    }
+}
@@ -1,21 +1,592 @@
+use std::{fmt, net::IpAddr, os::fd::RawFd, path::PathBuf, str::FromStr};
+
+use bitflags::bitflags;
+use derive_builder::Builder;
+use thiserror::Error;
+
 use super::{
-    message::{Attribute, LinkMessage, RouteAttrMap, RouteAttrs},
-    IFLA_VXLAN_AGEING, IFLA_VXLAN_FLOWBASED, IFLA_VXLAN_GBP, IFLA_VXLAN_GROUP, IFLA_VXLAN_GROUP6,
-    IFLA_VXLAN_ID, IFLA_VXLAN_L2MISS, IFLA_VXLAN_L3MISS, IFLA_VXLAN_LEARNING, IFLA_VXLAN_LIMIT,
-    IFLA_VXLAN_LINK, IFLA_VXLAN_LOCAL, IFLA_VXLAN_LOCAL6, IFLA_VXLAN_PORT, IFLA_VXLAN_PORT_RANGE,
-    IFLA_VXLAN_PROXY, IFLA_VXLAN_RSC, IFLA_VXLAN_TOS, IFLA_VXLAN_TTL, IFLA_VXLAN_UDP_CSUM,
-    IFLA_VXLAN_UDP_ZERO_CSUM6_RX, IFLA_VXLAN_UDP_ZERO_CSUM6_TX,
+    bpf::{XdpInfo, IFLA_XDP},
+    message::{Attribute, IfInfoMsg, RouteAttrMap, RouteAttrs},
+    vec_to_addr, IFLA_VXLAN_AGEING, IFLA_VXLAN_FLOWBASED, IFLA_VXLAN_GBP, IFLA_VXLAN_GROUP,
+    IFLA_VXLAN_GROUP6, IFLA_VXLAN_ID, IFLA_VXLAN_L2MISS, IFLA_VXLAN_L3MISS, IFLA_VXLAN_LEARNING,
+    IFLA_VXLAN_LIMIT, IFLA_VXLAN_LINK, IFLA_VXLAN_LOCAL, IFLA_VXLAN_LOCAL6, IFLA_VXLAN_PORT,
+    IFLA_VXLAN_PORT_RANGE, IFLA_VXLAN_PROXY, IFLA_VXLAN_RSC, IFLA_VXLAN_TOS, IFLA_VXLAN_TTL,
+    IFLA_VXLAN_UDP_CSUM, IFLA_VXLAN_UDP_ZERO_CSUM6_RX, IFLA_VXLAN_UDP_ZERO_CSUM6_TX,
 };
 
+pub const IFLA_BR_FORWARD_DELAY: u16 = 0x1;
 pub const IFLA_BR_HELLO_TIME: u16 = 0x2;
 pub const IFLA_BR_AGEING_TIME: u16 = 0x4;
+pub const IFLA_BR_STP_STATE: u16 = 0x5;
 pub const IFLA_BR_VLAN_FILTERING: u16 = 0x7;
 pub const IFLA_BR_MCAST_SNOOPING: u16 = 0x17;
+pub const IFLA_BR_VLAN_DEFAULT_PVID: u16 = 0x27;
 
-#[derive(Debug)]
+pub const IFLA_BOND_MODE: u16 = 1;
+pub const IFLA_BOND_ACTIVE_SLAVE: u16 = 2;
+pub const IFLA_BOND_MIIMON: u16 = 3;
+pub const IFLA_BOND_UPDELAY: u16 = 4;
+pub const IFLA_BOND_DOWNDELAY: u16 = 5;
+pub const IFLA_BOND_PRIMARY: u16 = 11;
+pub const IFLA_BOND_XMIT_HASH_POLICY: u16 = 14;
+pub const IFLA_BOND_MIN_LINKS: u16 = 18;
+pub const IFLA_BOND_AD_LACP_RATE: u16 = 21;
+
+pub const IFLA_MIN_MTU: u16 = 50;
+pub const IFLA_MAX_MTU: u16 = 51;
+pub const IFLA_PERM_ADDRESS: u16 = 54;
+
+pub const IFLA_INFO_SLAVE_KIND: u16 = 4;
+pub const IFLA_INFO_SLAVE_DATA: u16 = 5;
+
+/// `IFLA_BRPORT_STATE` of a bridge port and `IFLA_BOND_SLAVE_STATE` of a
+/// bond slave, which share the type.
+const IFLA_SLAVE_STATE: u16 = 1;
+
+pub const IFLA_HSR_SLAVE1: u16 = 1;
+pub const IFLA_HSR_SLAVE2: u16 = 2;
+pub const IFLA_HSR_MULTICAST_SPEC: u16 = 3;
+pub const IFLA_HSR_PROTOCOL: u16 = 7;
+
+pub const VETH_INFO_PEER: u16 = 1;
+
+pub const IFLA_VLAN_ID: u16 = 1;
+pub const IFLA_VLAN_FLAGS: u16 = 2;
+pub const IFLA_VLAN_EGRESS_QOS: u16 = 3;
+pub const IFLA_VLAN_INGRESS_QOS: u16 = 4;
+pub const IFLA_VLAN_PROTOCOL: u16 = 5;
+pub const IFLA_VLAN_QOS_MAPPING: u16 = 1;
+
+pub const IFLA_GENEVE_ID: u16 = 1;
+pub const IFLA_GENEVE_REMOTE: u16 = 2;
+pub const IFLA_GENEVE_TOS: u16 = 4;
+pub const IFLA_GENEVE_PORT: u16 = 5;
+pub const IFLA_GENEVE_REMOTE6: u16 = 7;
+
+/// The redundancy protocol of an `hsr` link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HsrProtocol {
+    /// High-availability Seamless Redundancy, IEC 62439-3 clause 5.
+    Hsr = 0,
+    /// Parallel Redundancy Protocol, IEC 62439-3 clause 4.
+    Prp = 1,
+}
+
+bitflags! {
+    /// The `IFF_*` device flags of a link, i.e. its `ifi_flags`.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct LinkFlags: u32 {
+        const UP = libc::IFF_UP as u32;
+        const BROADCAST = libc::IFF_BROADCAST as u32;
+        const LOOPBACK = libc::IFF_LOOPBACK as u32;
+        const POINTOPOINT = libc::IFF_POINTOPOINT as u32;
+        /// The link is up and has a carrier, see also `LOWER_UP`.
+        const RUNNING = libc::IFF_RUNNING as u32;
+        const NOARP = libc::IFF_NOARP as u32;
+        const PROMISC = libc::IFF_PROMISC as u32;
+        const ALLMULTI = libc::IFF_ALLMULTI as u32;
+        const MASTER = libc::IFF_MASTER as u32;
+        const SLAVE = libc::IFF_SLAVE as u32;
+        const MULTICAST = libc::IFF_MULTICAST as u32;
+        const LOWER_UP = libc::IFF_LOWER_UP as u32;
+        const DORMANT = libc::IFF_DORMANT as u32;
+    }
+}
+
+bitflags! {
+    /// The `VLAN_FLAG_*` flags of a VLAN link.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct VlanFlags: u32 {
+        /// Removes the VLAN header on receive, which the kernel does by
+        /// default.
+        const REORDER_HDR = 0x1;
+        const GVRP = 0x2;
+        /// Does not follow the operational state of the parent link.
+        const LOOSE_BINDING = 0x4;
+        const MVRP = 0x8;
+        const BRIDGE_BINDING = 0x10;
+    }
+}
+
+/// The tag protocol of a VLAN link.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VlanProtocol {
+    /// IEEE 802.1Q, `ETH_P_8021Q`.
+    #[default]
+    Dot1Q = 0x8100,
+    /// IEEE 802.1ad, the outer tag of QinQ, `ETH_P_8021AD`.
+    Dot1Ad = 0x88a8,
+}
+
+impl TryFrom<u16> for VlanProtocol {
+    type Error = anyhow::Error;
+
+    fn try_from(protocol: u16) -> anyhow::Result<Self> {
+        match protocol {
+            0x8100 => Ok(Self::Dot1Q),
+            0x88a8 => Ok(Self::Dot1Ad),
+            _ => anyhow::bail!("unknown vlan protocol: {:#06x}", protocol),
+        }
+    }
+}
+
+/// How a bond spreads traffic over its slaves, `IFLA_BOND_MODE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondMode {
+    BalanceRr = 0,
+    ActiveBackup = 1,
+    BalanceXor = 2,
+    Broadcast = 3,
+    /// IEEE 802.3ad dynamic link aggregation, i.e. LACP.
+    Ieee8023Ad = 4,
+    BalanceTlb = 5,
+    BalanceAlb = 6,
+}
+
+impl TryFrom<u8> for BondMode {
+    type Error = anyhow::Error;
+
+    fn try_from(mode: u8) -> anyhow::Result<Self> {
+        match mode {
+            0 => Ok(Self::BalanceRr),
+            1 => Ok(Self::ActiveBackup),
+            2 => Ok(Self::BalanceXor),
+            3 => Ok(Self::Broadcast),
+            4 => Ok(Self::Ieee8023Ad),
+            5 => Ok(Self::BalanceTlb),
+            6 => Ok(Self::BalanceAlb),
+            _ => anyhow::bail!("unknown bond mode: {}", mode),
+        }
+    }
+}
+
+/// The fields a bond hashes to pick the slave of a packet in the
+/// `BalanceXor` and `Ieee8023Ad` modes, `IFLA_BOND_XMIT_HASH_POLICY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondXmitHashPolicy {
+    Layer2 = 0,
+    Layer34 = 1,
+    Layer23 = 2,
+    Encap23 = 3,
+    Encap34 = 4,
+    VlanSrcMac = 5,
+}
+
+impl TryFrom<u8> for BondXmitHashPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(policy: u8) -> anyhow::Result<Self> {
+        match policy {
+            0 => Ok(Self::Layer2),
+            1 => Ok(Self::Layer34),
+            2 => Ok(Self::Layer23),
+            3 => Ok(Self::Encap23),
+            4 => Ok(Self::Encap34),
+            5 => Ok(Self::VlanSrcMac),
+            _ => anyhow::bail!("unknown bond xmit hash policy: {}", policy),
+        }
+    }
+}
+
+/// How often the partner of an 802.3ad bond is asked to send LACPDUs,
+/// `IFLA_BOND_AD_LACP_RATE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BondLacpRate {
+    /// Every 30 seconds.
+    Slow = 0,
+    /// Every second.
+    Fast = 1,
+}
+
+impl TryFrom<u8> for BondLacpRate {
+    type Error = anyhow::Error;
+
+    fn try_from(rate: u8) -> anyhow::Result<Self> {
+        match rate {
+            0 => Ok(Self::Slow),
+            1 => Ok(Self::Fast),
+            _ => anyhow::bail!("unknown bond lacp rate: {}", rate),
+        }
+    }
+}
+
+/// The RFC 2863 operational state of a link, `IFLA_OPERSTATE`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OperState {
+    #[default]
+    Unknown = 0,
+    NotPresent = 1,
+    Down = 2,
+    LowerLayerDown = 3,
+    Testing = 4,
+    Dormant = 5,
+    Up = 6,
+}
+
+impl From<u8> for OperState {
+    fn from(state: u8) -> Self {
+        match state {
+            1 => Self::NotPresent,
+            2 => Self::Down,
+            3 => Self::LowerLayerDown,
+            4 => Self::Testing,
+            5 => Self::Dormant,
+            6 => Self::Up,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// An Ethernet MAC address, written as `aa:bb:cc:dd:ee:ff`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MacAddr(pub [u8; 6]);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid MAC address: {0}")]
+pub struct ParseMacAddrError(pub String);
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(
+            f,
+            "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+            a, b, c, d, e, g
+        )
+    }
+}
+
+impl FromStr for MacAddr {
+    type Err = ParseMacAddrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseMacAddrError(s.to_string());
+
+        let mut mac = [0; 6];
+        let mut octets = s.split(':');
+
+        for octet in mac.iter_mut() {
+            // `from_str_radix` also takes a sign, e.g. "+a".
+            let hex = octets
+                .next()
+                .filter(|hex| hex.len() == 2 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or_else(err)?;
+            *octet = u8::from_str_radix(hex, 16).map_err(|_| err())?;
+        }
+
+        match octets.next() {
+            Some(_) => Err(err()),
+            None => Ok(Self(mac)),
+        }
+    }
+}
+
+impl From<[u8; 6]> for MacAddr {
+    fn from(mac: [u8; 6]) -> Self {
+        Self(mac)
+    }
+}
+
+impl TryFrom<&[u8]> for MacAddr {
+    type Error = ParseMacAddrError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, Self::Error> {
+        buf.try_into()
+            .map(Self)
+            .map_err(|_| ParseMacAddrError(format!("{:02x?}", buf)))
+    }
+}
+
+/// Refers to an existing link by its index or by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkRef {
+    Index(u32),
+    Name(String),
+}
+
+impl fmt::Display for LinkRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "index {}", index),
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+impl From<u32> for LinkRef {
+    fn from(index: u32) -> Self {
+        Self::Index(index)
+    }
+}
+
+impl From<&str> for LinkRef {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
+
+impl From<String> for LinkRef {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl<T: Link + ?Sized> From<&T> for LinkRef {
+    fn from(link: &T) -> Self {
+        Self::Index(link.attrs().index as u32)
+    }
+}
+
+/// A network namespace, e.g. to move a link to with `LinkHandle::set_ns`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Namespace {
+    /// The namespace of a process.
     Pid(i32),
+    /// An open namespace file, e.g. of `/proc/<pid>/ns/net`.
     Fd(i32),
+    /// A namespace file, e.g. `/run/netns/blue` as created by `ip netns`,
+    /// which is opened for the duration of the request.
+    Path(PathBuf),
+}
+
+/// The options of `LinkHandle::add_veth`. Unset ones are left to the
+/// kernel, e.g. a random MAC address.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VethOpts {
+    /// The MTU of both ends.
+    pub mtu: Option<u32>,
+    pub mac: Option<MacAddr>,
+    pub peer_mac: Option<MacAddr>,
+    /// An open namespace file, e.g. of `/proc/<pid>/ns/net`, to create the
+    /// peer in instead of the namespace of the socket.
+    pub peer_ns: Option<RawFd>,
+}
+
+/// The options of `LinkHandle::add_vlan`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VlanOpts {
+    pub protocol: VlanProtocol,
+    /// The flags to set, all others are cleared. `None` keeps the kernel's
+    /// default of `REORDER_HDR`.
+    pub flags: Option<VlanFlags>,
+    /// Maps the priority of a received tag to a packet priority, as
+    /// `(from, to)` pairs.
+    pub ingress_qos: Vec<(u32, u32)>,
+    /// Maps a packet priority to the priority of the sent tag, as
+    /// `(from, to)` pairs.
+    pub egress_qos: Vec<(u32, u32)>,
+}
+
+/// The options of `LinkHandle::add_vxlan`, best built with
+/// `VxlanOptsBuilder`. The defaults are the IANA port 4789 and learning on.
+#[derive(Debug, Clone, PartialEq, Eq, Builder)]
+#[builder(default)]
+pub struct VxlanOpts {
+    /// The VNI, at most 24 bits. Ignored with `collect_metadata`.
+    pub vni: u32,
+    pub local: Option<IpAddr>,
+    /// The multicast group, or the default remote of a unicast VXLAN. Of the
+    /// same family as `local`.
+    pub group: Option<IpAddr>,
+    /// The UDP destination port.
+    pub port: u16,
+    /// The underlay link.
+    pub link: Option<LinkRef>,
+    pub learning: bool,
+    /// The TTL of the outer header, 0 uses the route's hop limit.
+    pub ttl: u8,
+    /// The TOS of the outer header, where 1 inherits it from the inner one.
+    pub tos: u8,
+    /// Computes the UDP checksum of IPv4 tunnels.
+    pub udp_csum: bool,
+    /// Sends IPv6 tunnel packets with a zero UDP checksum.
+    pub udp_zero_csum6_tx: bool,
+    /// Accepts IPv6 tunnel packets with a zero UDP checksum.
+    pub udp_zero_csum6_rx: bool,
+    /// The external mode of `ip link add ... type vxlan external`, where the
+    /// VNI and remote are set per packet, e.g. by OVS or a BPF program.
+    pub collect_metadata: bool,
+}
+
+impl Default for VxlanOpts {
+    fn default() -> Self {
+        Self {
+            vni: 0,
+            local: None,
+            group: None,
+            port: 4789,
+            link: None,
+            learning: true,
+            ttl: 0,
+            tos: 0,
+            udp_csum: true,
+            udp_zero_csum6_tx: false,
+            udp_zero_csum6_rx: false,
+            collect_metadata: false,
+        }
+    }
+}
+
+/// The options of a bridge, for `LinkHandle::add_bridge` and
+/// `LinkHandle::set_bridge_opts`. Unset ones keep the kernel's defaults, or
+/// their current value. The times are in hundredths of a second.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BridgeOpts {
+    pub stp_state: Option<bool>,
+    pub vlan_filtering: Option<bool>,
+    /// The PVID of new ports, or 0 to leave them untagged.
+    pub vlan_default_pvid: Option<u16>,
+    pub forward_delay: Option<u32>,
+    pub hello_time: Option<u32>,
+    pub ageing_time: Option<u32>,
+    pub multicast_snooping: Option<bool>,
+}
+
+impl BridgeOpts {
+    /// Parses the `IFLA_INFO_DATA` of a bridge.
+    fn from_data(data: &RouteAttrs) -> Self {
+        let map = RouteAttrMap::from(data);
+
+        Self {
+            stp_state: map.get_u32(&IFLA_BR_STP_STATE).map(|v| v != 0),
+            vlan_filtering: map.get_bool(&IFLA_BR_VLAN_FILTERING),
+            vlan_default_pvid: map.get_u16(&IFLA_BR_VLAN_DEFAULT_PVID),
+            forward_delay: map.get_u32(&IFLA_BR_FORWARD_DELAY),
+            hello_time: map.get_u32(&IFLA_BR_HELLO_TIME),
+            ageing_time: map.get_u32(&IFLA_BR_AGEING_TIME),
+            multicast_snooping: map.get_bool(&IFLA_BR_MCAST_SNOOPING),
+        }
+    }
+}
+
+/// The options of a bond, for `LinkHandle::add_bond` and
+/// `LinkHandle::set_bond_opts`. Unset ones keep the kernel's defaults, or
+/// their current value. The mode can only change while the bond is down
+/// and has no slaves.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BondOpts {
+    pub mode: Option<BondMode>,
+    /// The ifindex of the slave that carries the traffic of an
+    /// `ActiveBackup` bond.
+    pub active_slave: Option<u32>,
+    /// The interval of the MII link monitoring in milliseconds, 0 disables
+    /// it.
+    pub miimon: Option<u32>,
+    /// The milliseconds a slave has to be up before it is used.
+    pub updelay: Option<u32>,
+    /// The milliseconds a slave has to be down before it is disabled.
+    pub downdelay: Option<u32>,
+    /// The ifindex of the slave an `ActiveBackup` bond prefers whenever it
+    /// is up.
+    pub primary: Option<u32>,
+    pub xmit_hash_policy: Option<BondXmitHashPolicy>,
+    pub lacp_rate: Option<BondLacpRate>,
+    /// The number of slaves that have to be up for an 802.3ad bond to
+    /// have a carrier.
+    pub min_links: Option<u32>,
+}
+
+impl BondOpts {
+    /// Parses the `IFLA_INFO_DATA` of a bond.
+    fn from_data(data: &RouteAttrs) -> Self {
+        let map = RouteAttrMap::from(data);
+
+        Self {
+            mode: map
+                .get_u8(&IFLA_BOND_MODE)
+                .and_then(|m| BondMode::try_from(m).ok()),
+            active_slave: map.get_u32(&IFLA_BOND_ACTIVE_SLAVE),
+            miimon: map.get_u32(&IFLA_BOND_MIIMON),
+            updelay: map.get_u32(&IFLA_BOND_UPDELAY),
+            downdelay: map.get_u32(&IFLA_BOND_DOWNDELAY),
+            primary: map.get_u32(&IFLA_BOND_PRIMARY),
+            xmit_hash_policy: map
+                .get_u8(&IFLA_BOND_XMIT_HASH_POLICY)
+                .and_then(|p| BondXmitHashPolicy::try_from(p).ok()),
+            lacp_rate: map
+                .get_u8(&IFLA_BOND_AD_LACP_RATE)
+                .and_then(|r| BondLacpRate::try_from(r).ok()),
+            min_links: map.get_u32(&IFLA_BOND_MIN_LINKS),
+        }
+    }
+}
+
+/// The tag and QoS mappings of a VLAN link.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VlanInfo {
+    pub id: u16,
+    pub protocol: VlanProtocol,
+    pub flags: VlanFlags,
+    /// The `(from, to)` pairs of the ingress mapping.
+    pub ingress_qos: Vec<(u32, u32)>,
+    /// The `(from, to)` pairs of the egress mapping.
+    pub egress_qos: Vec<(u32, u32)>,
+}
+
+impl VlanInfo {
+    /// Parses the `IFLA_INFO_DATA` of a VLAN link.
+    fn from_data(data: &RouteAttrs) -> Self {
+        let map = RouteAttrMap::from(data);
+
+        let qos = |key| {
+            map.get(&key).map_or_else(Vec::new, |nest| {
+                RouteAttrs::from(*nest)
+                    .iter()
+                    .filter(|a| a.header.rta_type == IFLA_VLAN_QOS_MAPPING && a.payload.len() >= 8)
+                    .map(|a| {
+                        (
+                            u32::from_ne_bytes(a.payload[..4].try_into().unwrap()),
+                            u32::from_ne_bytes(a.payload[4..8].try_into().unwrap()),
+                        )
+                    })
+                    .collect()
+            })
+        };
+
+        Self {
+            id: map.get_u16(&IFLA_VLAN_ID).unwrap_or_default(),
+            protocol: map
+                .get_u16_be(&IFLA_VLAN_PROTOCOL)
+                .and_then(|p| VlanProtocol::try_from(p).ok())
+                .unwrap_or_default(),
+            flags: map
+                .get_u32(&IFLA_VLAN_FLAGS)
+                .map(VlanFlags::from_bits_retain)
+                .unwrap_or_default(),
+            ingress_qos: qos(IFLA_VLAN_INGRESS_QOS),
+            egress_qos: qos(IFLA_VLAN_EGRESS_QOS),
+        }
+    }
+}
+
+/// The VNI and addressing of a GENEVE link, see
+/// `LinkHandle::list_with_geneve_info`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GeneveInfo {
+    pub vni: u32,
+    /// The remote endpoint, or `None` for a link in collect metadata mode.
+    pub remote: Option<IpAddr>,
+    /// The UDP destination port.
+    pub port: Option<u16>,
+    /// The TOS of the outer header, where 1 inherits it from the inner one.
+    pub tos: u8,
+}
+
+impl GeneveInfo {
+    /// Parses the `IFLA_INFO_DATA` of a GENEVE link.
+    fn from_data(data: &RouteAttrs) -> Self {
+        let map = RouteAttrMap::from(data);
+
+        Self {
+            vni: map.get_u32(&IFLA_GENEVE_ID).unwrap_or_default(),
+            remote: map
+                .get(&IFLA_GENEVE_REMOTE)
+                .or(map.get(&IFLA_GENEVE_REMOTE6))
+                .and_then(|remote| vec_to_addr(remote).ok()),
+            port: map.get_u16_be(&IFLA_GENEVE_PORT),
+            tos: map.get_u8(&IFLA_GENEVE_TOS).unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Default, Debug)]
@@ -42,6 +613,43 @@ pub struct VxlanAttrs {
     pub flow_based: bool,
 }
 
+impl VxlanAttrs {
+    /// The VNI and addressing of the link, with the addresses typed.
+    pub fn info(&self) -> VxlanInfo {
+        VxlanInfo {
+            vni: self.id,
+            group: self.group.as_deref().and_then(|g| vec_to_addr(g).ok()),
+            local: self.src_addr.as_deref().and_then(|l| vec_to_addr(l).ok()),
+            port: self.port,
+            learning: self.learning,
+            link: self.vtep_index,
+            ttl: self.ttl,
+            tos: self.tos,
+            udp_csum: self.udp_csum,
+            collect_metadata: self.flow_based,
+        }
+    }
+}
+
+/// The VNI and addressing of a VXLAN link, see
+/// `LinkHandle::list_with_vxlan_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VxlanInfo {
+    pub vni: u32,
+    /// The multicast group, or the default remote of a unicast VXLAN.
+    pub group: Option<IpAddr>,
+    pub local: Option<IpAddr>,
+    /// The UDP destination port.
+    pub port: Option<u16>,
+    pub learning: bool,
+    /// The index of the underlay link.
+    pub link: Option<u32>,
+    pub ttl: u8,
+    pub tos: u8,
+    pub udp_csum: bool,
+    pub collect_metadata: bool,
+}
+
 #[derive(Debug)]
 pub enum Kind {
     Dummy(LinkAttrs),
@@ -51,6 +659,10 @@ pub enum Kind {
         ageing_time: Option<u32>,
         vlan_filtering: Option<bool>,
         multicast_snooping: Option<bool>,
+        stp_state: Option<bool>,
+        forward_delay: Option<u32>,
+        /// The PVID of new ports, or 0 to leave them untagged.
+        vlan_default_pvid: Option<u16>,
     },
     Veth {
         attrs: LinkAttrs,
@@ -62,6 +674,18 @@ pub enum Kind {
         attrs: LinkAttrs,
         vxlan_attrs: VxlanAttrs,
     },
+    Vlan {
+        attrs: LinkAttrs,
+        info: VlanInfo,
+    },
+    Geneve {
+        attrs: LinkAttrs,
+        info: GeneveInfo,
+    },
+    Bond {
+        attrs: LinkAttrs,
+        opts: BondOpts,
+    },
     Wireguard(LinkAttrs),
     GenericLink {
         attrs: LinkAttrs,
@@ -71,7 +695,7 @@ pub enum Kind {
 
 impl From<&[u8]> for Kind {
     fn from(buf: &[u8]) -> Self {
-        let link_msg: LinkMessage = bincode::deserialize(buf).unwrap();
+        let link_msg: IfInfoMsg = bincode::deserialize(buf).unwrap();
         let attrs = RouteAttrs::from(&buf[link_msg.len()..]);
 
         let mut base = LinkAttrs::from(link_msg);
@@ -84,39 +708,55 @@ impl From<&[u8]> for Kind {
                         match a.header.rta_type {
                             libc::IFLA_INFO_KIND => base.link_type = a.payload.to_string().unwrap(),
                             libc::IFLA_INFO_DATA => data = RouteAttrs::from(a.payload.as_slice()),
+                            IFLA_INFO_SLAVE_KIND => base.slave_kind = a.payload.to_string().ok(),
+                            IFLA_INFO_SLAVE_DATA => {
+                                base.slave_state = RouteAttrs::from(a.payload.as_slice())
+                                    .into_iter()
+                                    .find(|sa| sa.header.rta_type == IFLA_SLAVE_STATE)
+                                    .and_then(|sa| sa.payload.first().copied());
+                            }
                             _ => {}
                         }
                     }
                 }
                 libc::IFLA_ADDRESS => base.hw_addr = (*attr.payload).to_vec(),
+                libc::IFLA_BROADCAST => base.broadcast = MacAddr::try_from(&attr.payload[..]).ok(),
+                IFLA_PERM_ADDRESS => base.perm_hw_addr = MacAddr::try_from(&attr.payload[..]).ok(),
                 libc::IFLA_IFNAME => base.name = attr.payload.to_string().unwrap(),
                 libc::IFLA_MTU => base.mtu = attr.payload.to_u32().unwrap(),
+                IFLA_MIN_MTU => base.min_mtu = attr.payload.to_u32().unwrap(),
+                IFLA_MAX_MTU => base.max_mtu = attr.payload.to_u32().unwrap(),
                 libc::IFLA_LINK => base.parent_index = attr.payload.to_i32().unwrap(),
                 libc::IFLA_MASTER => base.master_index = attr.payload.to_i32().unwrap(),
                 libc::IFLA_TXQLEN => base.tx_queue_len = attr.payload.to_i32().unwrap(),
                 libc::IFLA_IFALIAS => base.alias = attr.payload.to_string().unwrap(),
-                libc::IFLA_OPERSTATE => base.oper_state = attr.payload[0],
+                libc::IFLA_OPERSTATE => base.oper_state = attr.payload[0].into(),
+                libc::IFLA_CARRIER => base.carrier = attr.payload[0] != 0,
                 libc::IFLA_PHYS_SWITCH_ID => base.phys_switch_id = attr.payload.to_i32().unwrap(),
-                libc::IFLA_LINK_NETNSID => base.netns_id = attr.payload.to_i32().unwrap(),
+                libc::IFLA_LINK_NETNSID => base.netns_id = Some(attr.payload.to_i32().unwrap()),
                 libc::IFLA_GSO_MAX_SIZE => base.gso_max_size = attr.payload.to_u32().unwrap(),
                 libc::IFLA_GSO_MAX_SEGS => base.gso_max_segs = attr.payload.to_u32().unwrap(),
                 libc::IFLA_GRO_MAX_SIZE => base.gro_max_size = attr.payload.to_u32().unwrap(),
                 libc::IFLA_NUM_TX_QUEUES => base.num_tx_queues = attr.payload.to_i32().unwrap(),
                 libc::IFLA_NUM_RX_QUEUES => base.num_rx_queues = attr.payload.to_i32().unwrap(),
                 libc::IFLA_GROUP => base.group = attr.payload.to_u32().unwrap(),
+                IFLA_XDP => base.xdp = XdpInfo::try_from(attr.payload.as_slice()).ok(),
                 _ => {}
             }
         }
 
         match &base.link_type[..] {
             "bridge" => {
-                let map = RouteAttrMap::from(&data);
+                let opts = BridgeOpts::from_data(&data);
                 Kind::Bridge {
                     attrs: base,
-                    hello_time: map.get_u32(&IFLA_BR_HELLO_TIME),
-                    ageing_time: map.get_u32(&IFLA_BR_AGEING_TIME),
-                    vlan_filtering: map.get_bool(&IFLA_BR_VLAN_FILTERING),
-                    multicast_snooping: map.get_bool(&IFLA_BR_MCAST_SNOOPING),
+                    hello_time: opts.hello_time,
+                    ageing_time: opts.ageing_time,
+                    vlan_filtering: opts.vlan_filtering,
+                    multicast_snooping: opts.multicast_snooping,
+                    stp_state: opts.stp_state,
+                    forward_delay: opts.forward_delay,
+                    vlan_default_pvid: opts.vlan_default_pvid,
                 }
             }
             "veth" => Kind::Veth {
@@ -143,12 +783,12 @@ impl From<&[u8]> for Kind {
                         learning: map.get_bool(&IFLA_VXLAN_LEARNING).unwrap(),
                         ageing: map.get_u32(&IFLA_VXLAN_AGEING),
                         limit: map.get_u32(&IFLA_VXLAN_LIMIT),
-                        port_range: map.get_u16_tuple(&IFLA_VXLAN_PORT_RANGE),
+                        port_range: map.get_u16_tuple_be(&IFLA_VXLAN_PORT_RANGE),
                         proxy: map.get_bool(&IFLA_VXLAN_PROXY).unwrap_or_default(),
                         rsc: map.get_bool(&IFLA_VXLAN_RSC).unwrap_or_default(),
                         l2miss: map.get_bool(&IFLA_VXLAN_L2MISS).unwrap_or_default(),
                         l3miss: map.get_bool(&IFLA_VXLAN_L3MISS).unwrap_or_default(),
-                        port: map.get_u16(&IFLA_VXLAN_PORT),
+                        port: map.get_u16_be(&IFLA_VXLAN_PORT),
                         udp_csum: map.get_bool(&IFLA_VXLAN_UDP_CSUM).unwrap_or_default(),
                         udp_zero_csum6_tx: map
                             .get_bool(&IFLA_VXLAN_UDP_ZERO_CSUM6_TX)
@@ -161,6 +801,18 @@ impl From<&[u8]> for Kind {
                     },
                 }
             }
+            "vlan" => Kind::Vlan {
+                info: VlanInfo::from_data(&data),
+                attrs: base,
+            },
+            "geneve" => Kind::Geneve {
+                info: GeneveInfo::from_data(&data),
+                attrs: base,
+            },
+            "bond" => Kind::Bond {
+                opts: BondOpts::from_data(&data),
+                attrs: base,
+            },
             "wireguard" => Kind::Wireguard(base),
             "dummy" => Kind::Dummy(base),
             _ => Kind::GenericLink {
@@ -208,17 +860,33 @@ pub struct LinkAttrs {
     pub index: i32,
     pub name: String,
     pub hw_addr: Vec<u8>,
+    /// The broadcast address of an Ethernet-like link.
+    pub broadcast: Option<MacAddr>,
+    /// The address burnt into the device, which `hw_addr` may override.
+    pub perm_hw_addr: Option<MacAddr>,
     pub mtu: u32,
+    /// The smallest MTU the device accepts, or 0 if it is not reported.
+    pub min_mtu: u32,
+    /// The largest MTU the device accepts, or 0 if it is not reported.
+    pub max_mtu: u32,
     pub flags: u32,
     pub raw_flags: u32,
     pub parent_index: i32,
     pub master_index: i32,
+    /// The kind of the master of a port, e.g. `bridge` or `bond`.
+    pub slave_kind: Option<String>,
+    /// The `BR_STATE_*` of a bridge port, or the `BOND_STATE_*` of a bond
+    /// slave.
+    pub slave_state: Option<u8>,
     pub tx_queue_len: i32,
     pub alias: String,
     pub prot_info: String,
-    pub oper_state: u8,
+    pub oper_state: OperState,
+    pub carrier: bool,
     pub phys_switch_id: i32,
-    pub netns_id: i32,
+    /// The namespace id, relative to the queried namespace, of the peer or
+    /// underlying device when it lives in another namespace.
+    pub netns_id: Option<i32>,
     pub gso_max_size: u32,
     pub gso_max_segs: u32,
     pub gro_max_size: u32,
@@ -227,6 +895,8 @@ pub struct LinkAttrs {
     pub num_rx_queues: i32,
     pub group: u32,
     pub statistics: String,
+    /// The XDP state, reported for every link that supports XDP.
+    pub xdp: Option<XdpInfo>,
 }
 
 impl LinkAttrs {
@@ -237,7 +907,31 @@ impl LinkAttrs {
         }
     }
 
-    fn from(link_msg: LinkMessage) -> Self {
+    /// `hw_addr` as a MAC address, or `None` for links whose address is not
+    /// one, e.g. the IPv4 address of an `ipip` tunnel.
+    pub fn mac(&self) -> Option<MacAddr> {
+        MacAddr::try_from(&self.hw_addr[..]).ok()
+    }
+
+    /// The device flags the kernel reported in `raw_flags`.
+    pub fn link_flags(&self) -> LinkFlags {
+        LinkFlags::from_bits_retain(self.raw_flags)
+    }
+
+    /// Whether the link is administratively up.
+    pub fn is_up(&self) -> bool {
+        self.link_flags().contains(LinkFlags::UP)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.link_flags().contains(LinkFlags::RUNNING)
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        self.link_flags().contains(LinkFlags::LOOPBACK)
+    }
+
+    fn from(link_msg: IfInfoMsg) -> Self {
         Self {
             index: link_msg.index,
             raw_flags: link_msg.flags,
@@ -253,6 +947,9 @@ impl Link for Kind {
             Kind::Bridge { .. } => "bridge",
             Kind::Veth { .. } => "veth",
             Kind::Vxlan { .. } => "vxlan",
+            Kind::Vlan { .. } => "vlan",
+            Kind::Geneve { .. } => "geneve",
+            Kind::Bond { .. } => "bond",
             Kind::Wireguard(_) => "wireguard",
             Kind::GenericLink {
                 attrs: _,
@@ -267,6 +964,9 @@ impl Link for Kind {
             Kind::Bridge { attrs, .. } => attrs,
             Kind::Veth { attrs, .. } => attrs,
             Kind::Vxlan { attrs, .. } => attrs,
+            Kind::Vlan { attrs, .. } => attrs,
+            Kind::Geneve { attrs, .. } => attrs,
+            Kind::Bond { attrs, .. } => attrs,
             Kind::Wireguard(attrs) => attrs,
             Kind::GenericLink { attrs, .. } => attrs,
         }
@@ -278,6 +978,9 @@ impl Link for Kind {
             Kind::Bridge { attrs, .. } => attrs,
             Kind::Veth { attrs, .. } => attrs,
             Kind::Vxlan { attrs, .. } => attrs,
+            Kind::Vlan { attrs, .. } => attrs,
+            Kind::Geneve { attrs, .. } => attrs,
+            Kind::Bond { attrs, .. } => attrs,
             Kind::Wireguard(attrs) => attrs,
             Kind::GenericLink { attrs, .. } => attrs,
         }
@@ -296,12 +999,17 @@ impl Kind {
             ageing_time: None,
             vlan_filtering: None,
             multicast_snooping: None,
+            stp_state: None,
+            forward_delay: None,
+            vlan_default_pvid: None,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::types::message::RouteAttr;
+
     use super::*;
 
     static NETLINK_MSG: [u8; 1752] = [
@@ -434,6 +1142,17 @@ mod tests {
         assert_eq!(attrs.name, "docker0");
         assert_eq!(attrs.mtu, 1500);
         assert_eq!(attrs.raw_flags, 0x1003);
+        assert_eq!(
+            attrs.link_flags(),
+            LinkFlags::UP | LinkFlags::BROADCAST | LinkFlags::MULTICAST
+        );
+        assert!(attrs.is_up());
+        assert!(!attrs.is_running());
+        assert!(!attrs.is_loopback());
+        assert_eq!(attrs.oper_state, OperState::Down);
+        assert_eq!(attrs.mac().unwrap().to_string(), "02:42:3b:14:a7:98");
+        assert_eq!(attrs.broadcast, Some(MacAddr([0xff; 6])));
+        assert_eq!(attrs.perm_hw_addr, None);
 
         match link.kind() {
             Kind::Bridge {
@@ -442,13 +1161,145 @@ mod tests {
                 ageing_time,
                 vlan_filtering,
                 multicast_snooping,
+                stp_state,
+                forward_delay,
+                vlan_default_pvid,
             } => {
                 assert_eq!(hello_time.unwrap(), 200);
                 assert_eq!(ageing_time.unwrap(), 30000);
                 assert!(!vlan_filtering.unwrap());
                 assert!(multicast_snooping.unwrap());
+                assert_eq!(*stp_state, Some(false));
+                assert_eq!(*forward_delay, Some(1500));
+                assert_eq!(*vlan_default_pvid, Some(1));
             }
             _ => panic!("Expected bridge link"),
         }
     }
+
+    #[test]
+    fn test_mac_addr_parse() {
+        let mac: MacAddr = "aa:bb:cc:0d:ee:ff".parse().unwrap();
+        assert_eq!(mac, MacAddr([0xaa, 0xbb, 0xcc, 0x0d, 0xee, 0xff]));
+        assert_eq!(mac.to_string(), "aa:bb:cc:0d:ee:ff");
+        assert_eq!("AA:BB:CC:0D:EE:FF".parse(), Ok(mac));
+
+        for invalid in [
+            "",
+            "aa:bb:cc:dd:ee",
+            "aa:bb:cc:dd:ee:ff:00",
+            "a:bb:cc:dd:ee:fff",
+            "gg:bb:cc:dd:ee:ff",
+            "+a:bb:cc:dd:ee:ff",
+        ] {
+            assert_eq!(
+                invalid.parse::<MacAddr>(),
+                Err(ParseMacAddrError(invalid.to_string()))
+            );
+        }
+
+        assert!(MacAddr::try_from(&[1, 2, 3, 4][..]).is_err());
+    }
+
+    #[test]
+    fn test_link_deserialize_slave_info() {
+        let mut msg = IfInfoMsg::new(libc::AF_UNSPEC);
+        msg.index = 5;
+
+        let mut slave_data = RouteAttr::new(IFLA_INFO_SLAVE_DATA, &[]);
+        slave_data.add(IFLA_SLAVE_STATE, &[3]);
+
+        let mut link_info = RouteAttr::new(libc::IFLA_LINKINFO, &[]);
+        link_info.add_attribute(Box::new(RouteAttr::string(libc::IFLA_INFO_KIND, "veth")));
+        link_info.add_attribute(Box::new(RouteAttr::string(IFLA_INFO_SLAVE_KIND, "bridge")));
+        link_info.add_attribute(Box::new(slave_data));
+
+        let mut buf = msg.serialize().unwrap();
+        buf.extend(RouteAttr::u32(libc::IFLA_MASTER, 3).serialize().unwrap());
+        buf.extend(link_info.serialize().unwrap());
+
+        let link = Kind::from(buf.as_slice());
+        let attrs = link.attrs();
+
+        assert_eq!(attrs.link_type, "veth");
+        assert_eq!(attrs.master_index, 3);
+        assert_eq!(attrs.slave_kind.as_deref(), Some("bridge"));
+        // BR_STATE_FORWARDING
+        assert_eq!(attrs.slave_state, Some(3));
+    }
+
+    #[test]
+    fn test_link_deserialize_vlan() {
+        let mut qos = RouteAttr::new(IFLA_VLAN_EGRESS_QOS, &[]);
+        qos.add(IFLA_VLAN_QOS_MAPPING, &[1, 0, 0, 0, 2, 0, 0, 0]);
+        qos.add(IFLA_VLAN_QOS_MAPPING, &[5, 0, 0, 0, 6, 0, 0, 0]);
+
+        let mut data = RouteAttr::new(libc::IFLA_INFO_DATA, &[]);
+        data.add_attribute(Box::new(RouteAttr::u16(IFLA_VLAN_ID, 10)));
+        data.add_attribute(Box::new(RouteAttr::u16_be(IFLA_VLAN_PROTOCOL, 0x88a8)));
+        // flags = REORDER_HDR | GVRP, mask = all
+        data.add(IFLA_VLAN_FLAGS, &[3, 0, 0, 0, 0x1f, 0, 0, 0]);
+        data.add_attribute(Box::new(RouteAttr::new(IFLA_VLAN_INGRESS_QOS, &[])));
+        data.add_attribute(Box::new(qos));
+
+        let mut link_info = RouteAttr::new(libc::IFLA_LINKINFO, &[]);
+        link_info.add_attribute(Box::new(RouteAttr::string(libc::IFLA_INFO_KIND, "vlan")));
+        link_info.add_attribute(Box::new(data));
+
+        let mut buf = IfInfoMsg::new(libc::AF_UNSPEC).serialize().unwrap();
+        buf.extend(RouteAttr::u32(libc::IFLA_LINK, 2).serialize().unwrap());
+        buf.extend(link_info.serialize().unwrap());
+
+        let link = Kind::from(buf.as_slice());
+        assert_eq!(link.link_type(), "vlan");
+        assert_eq!(link.attrs().parent_index, 2);
+
+        match link {
+            Kind::Vlan { info, .. } => assert_eq!(
+                info,
+                VlanInfo {
+                    id: 10,
+                    protocol: VlanProtocol::Dot1Ad,
+                    flags: VlanFlags::REORDER_HDR | VlanFlags::GVRP,
+                    ingress_qos: vec![],
+                    egress_qos: vec![(1, 2), (5, 6)],
+                }
+            ),
+            _ => panic!("Expected vlan link"),
+        }
+    }
+
+    #[test]
+    fn test_link_deserialize_bond() {
+        let mut data = RouteAttr::new(libc::IFLA_INFO_DATA, &[]);
+        data.add_attribute(Box::new(RouteAttr::u8(IFLA_BOND_MODE, 1)));
+        data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_ACTIVE_SLAVE, 5)));
+        data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_MIIMON, 100)));
+        data.add_attribute(Box::new(RouteAttr::u8(IFLA_BOND_XMIT_HASH_POLICY, 1)));
+        data.add_attribute(Box::new(RouteAttr::u8(IFLA_BOND_AD_LACP_RATE, 0)));
+
+        let mut link_info = RouteAttr::new(libc::IFLA_LINKINFO, &[]);
+        link_info.add_attribute(Box::new(RouteAttr::string(libc::IFLA_INFO_KIND, "bond")));
+        link_info.add_attribute(Box::new(data));
+
+        let mut buf = IfInfoMsg::new(libc::AF_UNSPEC).serialize().unwrap();
+        buf.extend(link_info.serialize().unwrap());
+
+        let link = Kind::from(buf.as_slice());
+        assert_eq!(link.link_type(), "bond");
+        match link.kind() {
+            Kind::Bond { opts, .. } => assert_eq!(
+                *opts,
+                BondOpts {
+                    mode: Some(BondMode::ActiveBackup),
+                    active_slave: Some(5),
+                    miimon: Some(100),
+                    xmit_hash_policy: Some(BondXmitHashPolicy::Layer34),
+                    lacp_rate: Some(BondLacpRate::Slow),
+                    ..Default::default()
+                }
+            ),
+            _ => panic!("Expected bond link"),
+        }
+    }
 }
@@ -1,42 +1,55 @@
 use std::{
     collections::HashMap,
     mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     ops::{Deref, DerefMut},
     vec,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use bincode::deserialize;
+use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    align_of,
-    handle::zero_terminated,
-    types::{
-        IFLA_VXLAN_AGEING, IFLA_VXLAN_FLOWBASED, IFLA_VXLAN_GBP, IFLA_VXLAN_GROUP,
-        IFLA_VXLAN_GROUP6, IFLA_VXLAN_ID, IFLA_VXLAN_L2MISS, IFLA_VXLAN_L3MISS,
-        IFLA_VXLAN_LEARNING, IFLA_VXLAN_LIMIT, IFLA_VXLAN_LINK, IFLA_VXLAN_LOCAL,
-        IFLA_VXLAN_LOCAL6, IFLA_VXLAN_PORT, IFLA_VXLAN_PORT_RANGE, IFLA_VXLAN_PROXY,
-        IFLA_VXLAN_RSC, IFLA_VXLAN_TOS, IFLA_VXLAN_TTL, IFLA_VXLAN_UDP_CSUM,
-        IFLA_VXLAN_UDP_ZERO_CSUM6_RX, IFLA_VXLAN_UDP_ZERO_CSUM6_TX,
+use crate::{align_of, zero_terminated};
+
+#[cfg(target_os = "linux")]
+use super::{
+    link::{
+        BondOpts, BridgeOpts, Kind, LinkAttrs, Namespace, VxlanAttrs, IFLA_BOND_ACTIVE_SLAVE,
+        IFLA_BOND_AD_LACP_RATE, IFLA_BOND_DOWNDELAY, IFLA_BOND_MIIMON, IFLA_BOND_MIN_LINKS,
+        IFLA_BOND_MODE, IFLA_BOND_PRIMARY, IFLA_BOND_UPDELAY, IFLA_BOND_XMIT_HASH_POLICY,
+        IFLA_BR_AGEING_TIME, IFLA_BR_FORWARD_DELAY, IFLA_BR_HELLO_TIME, IFLA_BR_MCAST_SNOOPING,
+        IFLA_BR_STP_STATE, IFLA_BR_VLAN_DEFAULT_PVID, IFLA_BR_VLAN_FILTERING, VETH_INFO_PEER,
     },
+    IFLA_VXLAN_AGEING, IFLA_VXLAN_FLOWBASED, IFLA_VXLAN_GBP, IFLA_VXLAN_GROUP, IFLA_VXLAN_GROUP6,
+    IFLA_VXLAN_ID, IFLA_VXLAN_L2MISS, IFLA_VXLAN_L3MISS, IFLA_VXLAN_LEARNING, IFLA_VXLAN_LIMIT,
+    IFLA_VXLAN_LINK, IFLA_VXLAN_LOCAL, IFLA_VXLAN_LOCAL6, IFLA_VXLAN_PORT, IFLA_VXLAN_PORT_RANGE,
+    IFLA_VXLAN_PROXY, IFLA_VXLAN_RSC, IFLA_VXLAN_TOS, IFLA_VXLAN_TTL, IFLA_VXLAN_UDP_CSUM,
+    IFLA_VXLAN_UDP_ZERO_CSUM6_RX, IFLA_VXLAN_UDP_ZERO_CSUM6_TX,
 };
-
 use super::{
-    link::{Kind, LinkAttrs, Namespace, VxlanAttrs},
-    GENL_CTRL_CMD_GETFAMILY, GENL_CTRL_VERSION,
+    AF_INET, AF_INET6, GENL_CTRL_CMD_GETFAMILY, GENL_CTRL_VERSION, NFNETLINK_V0, RTN_UNICAST,
+    RTPROT_BOOT, RT_SCOPE_NOWHERE, RT_SCOPE_UNIVERSE, RT_TABLE_MAIN,
 };
 
 const RTA_ALIGNTO: usize = 0x4;
 const RT_ATTR_HDR_SIZE: usize = 0x4;
 
-const VETH_INFO_PEER: u16 = 1;
-
 pub trait Attribute {
     fn len(&self) -> usize;
 
     fn serialize(&self) -> Result<Vec<u8>>;
 
+    /// Like `serialize`, padded with zeros to `RTA_ALIGNTO`, so the next
+    /// attribute of a message or a nest starts aligned.
+    fn serialize_aligned(&self) -> Result<Vec<u8>> {
+        let mut buf = self.serialize()?;
+        buf.resize(align_of(buf.len(), RTA_ALIGNTO), 0);
+
+        Ok(buf)
+    }
+
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -85,6 +98,15 @@ impl RouteAttrMap<'_> {
         })
     }
 
+    pub fn get_u16_be(&self, key: &u16) -> Option<u16> {
+        self.get_u16(key).map(u16::from_be)
+    }
+
+    pub fn get_u16_tuple_be(&self, key: &u16) -> Option<(u16, u16)> {
+        self.get_u16_tuple(key)
+            .map(|(a, b)| (u16::from_be(a), u16::from_be(b)))
+    }
+
     pub fn get_u32(&self, key: &u16) -> Option<u32> {
         self.get(key)
             .map(|v| u32::from_ne_bytes(v[..4].try_into().unwrap_or([0; 4])))
@@ -103,10 +125,19 @@ impl From<&[u8]> for RouteAttrs {
         let mut attrs = Vec::new();
 
         while buf.len() >= RT_ATTR_HDR_SIZE {
-            let attr = RouteAttr::from(buf);
-            let len = align_of(attr.header.rta_len as usize, RTA_ALIGNTO);
+            // Like the kernel's `nla_ok`, stop at the first attribute whose
+            // length does not fit, instead of reading past it.
+            let rta_len = u16::from_ne_bytes([buf[0], buf[1]]) as usize;
+            if rta_len < RT_ATTR_HDR_SIZE || rta_len > buf.len() {
+                break;
+            }
+
+            let Some(attr) = RouteAttr::try_from(buf).ok() else {
+                break;
+            };
             attrs.push(attr);
 
+            let len = align_of(rta_len, RTA_ALIGNTO).min(buf.len());
             buf = &buf[len..];
         }
 
@@ -141,7 +172,7 @@ impl RouteAttrs {
     pub fn serialize(&self) -> Result<Vec<u8>> {
         self.0
             .iter()
-            .map(|attr| attr.serialize())
+            .map(|attr| attr.serialize_aligned())
             .collect::<Result<Vec<_>, _>>()
             .map(|v| v.concat())
     }
@@ -174,7 +205,7 @@ impl Attribute for RouteAttr {
 
         if let Some(attrs) = &self.attributes {
             for attr in attrs {
-                buf.extend_from_slice(&attr.serialize()?);
+                buf.extend_from_slice(&attr.serialize_aligned()?);
             }
 
             let len = buf.len();
@@ -185,16 +216,26 @@ impl Attribute for RouteAttr {
     }
 }
 
-impl From<&[u8]> for RouteAttr {
-    fn from(buf: &[u8]) -> Self {
-        let header: RouteAttrHeader = deserialize(buf).expect("Failed to deserialize header");
-        let payload = Payload::from(&buf[RT_ATTR_HDR_SIZE..header.rta_len as usize]);
-
-        Self {
+impl TryFrom<&[u8]> for RouteAttr {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let header: RouteAttrHeader = deserialize(buf)?;
+        let payload = buf
+            .get(RT_ATTR_HDR_SIZE..header.rta_len as usize)
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid attribute length {} for a {} byte buffer",
+                    header.rta_len,
+                    buf.len()
+                )
+            })?;
+
+        Ok(Self {
             header,
-            payload,
+            payload: Payload::from(payload),
             attributes: None,
-        }
+        })
     }
 }
 
@@ -203,16 +244,28 @@ pub const BR_AGEING_TIME: u16 = 0x4;
 pub const BR_VLAN_FILTERING: u16 = 0x7;
 pub const BR_MCAST_SNOOPING: u16 = 0x17;
 
+#[cfg(target_os = "linux")]
 impl From<&Kind> for Option<RouteAttr> {
     fn from(kind: &Kind) -> Self {
         match kind {
             Kind::Bridge {
                 attrs: _,
-                hello_time: ht,
-                ageing_time: at,
-                vlan_filtering: vf,
-                multicast_snooping: ms,
-            } => RouteAttr::from_bridge(ht, at, vf, ms),
+                hello_time,
+                ageing_time,
+                vlan_filtering,
+                multicast_snooping,
+                stp_state,
+                forward_delay,
+                vlan_default_pvid,
+            } => Some(RouteAttr::from_bridge_opts(&BridgeOpts {
+                stp_state: *stp_state,
+                vlan_filtering: *vlan_filtering,
+                vlan_default_pvid: *vlan_default_pvid,
+                forward_delay: *forward_delay,
+                hello_time: *hello_time,
+                ageing_time: *ageing_time,
+                multicast_snooping: *multicast_snooping,
+            })),
             Kind::Veth {
                 attrs: base,
                 peer_name,
@@ -223,16 +276,14 @@ impl From<&Kind> for Option<RouteAttr> {
                 attrs: _,
                 vxlan_attrs,
             } => RouteAttr::from_vxlan(vxlan_attrs),
+            Kind::Bond { attrs: _, opts } => Some(RouteAttr::from_bond_opts(opts)),
             _ => None,
         }
     }
 }
 
+#[cfg(target_os = "linux")]
 impl RouteAttr {
-    pub fn new(rta_type: u16, payload: &[u8]) -> Self {
-        Self::with_attrs(rta_type, payload, None)
-    }
-
     pub fn from_bridge(
         ht: &Option<u32>,
         at: &Option<u32>,
@@ -256,6 +307,102 @@ impl RouteAttr {
         Some(Self::with_attrs(libc::IFLA_INFO_DATA, &[], sub_attrs))
     }
 
+    /// The `IFLA_INFO_DATA` of a bridge with the options set in `opts`.
+    pub fn from_bridge_opts(opts: &BridgeOpts) -> Self {
+        let mut data = RouteAttr::new(libc::IFLA_INFO_DATA, &[]);
+
+        if let Some(forward_delay) = opts.forward_delay {
+            data.add_attribute(Box::new(RouteAttr::u32(
+                IFLA_BR_FORWARD_DELAY,
+                forward_delay,
+            )));
+        }
+
+        if let Some(hello_time) = opts.hello_time {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BR_HELLO_TIME, hello_time)));
+        }
+
+        if let Some(ageing_time) = opts.ageing_time {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BR_AGEING_TIME, ageing_time)));
+        }
+
+        if let Some(stp_state) = opts.stp_state {
+            data.add_attribute(Box::new(RouteAttr::u32(
+                IFLA_BR_STP_STATE,
+                stp_state as u32,
+            )));
+        }
+
+        if let Some(vlan_filtering) = opts.vlan_filtering {
+            data.add_attribute(Box::new(RouteAttr::u8(
+                IFLA_BR_VLAN_FILTERING,
+                vlan_filtering as u8,
+            )));
+        }
+
+        if let Some(multicast_snooping) = opts.multicast_snooping {
+            data.add_attribute(Box::new(RouteAttr::u8(
+                IFLA_BR_MCAST_SNOOPING,
+                multicast_snooping as u8,
+            )));
+        }
+
+        if let Some(pvid) = opts.vlan_default_pvid {
+            data.add_attribute(Box::new(RouteAttr::u16(IFLA_BR_VLAN_DEFAULT_PVID, pvid)));
+        }
+
+        data
+    }
+
+    /// The `IFLA_INFO_DATA` of a bond with the options set in `opts`.
+    pub fn from_bond_opts(opts: &BondOpts) -> Self {
+        let mut data = RouteAttr::new(libc::IFLA_INFO_DATA, &[]);
+
+        if let Some(mode) = opts.mode {
+            data.add_attribute(Box::new(RouteAttr::u8(IFLA_BOND_MODE, mode as u8)));
+        }
+
+        if let Some(active_slave) = opts.active_slave {
+            data.add_attribute(Box::new(RouteAttr::u32(
+                IFLA_BOND_ACTIVE_SLAVE,
+                active_slave,
+            )));
+        }
+
+        if let Some(miimon) = opts.miimon {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_MIIMON, miimon)));
+        }
+
+        if let Some(updelay) = opts.updelay {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_UPDELAY, updelay)));
+        }
+
+        if let Some(downdelay) = opts.downdelay {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_DOWNDELAY, downdelay)));
+        }
+
+        if let Some(primary) = opts.primary {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_PRIMARY, primary)));
+        }
+
+        if let Some(policy) = opts.xmit_hash_policy {
+            data.add_attribute(Box::new(RouteAttr::u8(
+                IFLA_BOND_XMIT_HASH_POLICY,
+                policy as u8,
+            )));
+        }
+
+        if let Some(rate) = opts.lacp_rate {
+            data.add_attribute(Box::new(RouteAttr::u8(IFLA_BOND_AD_LACP_RATE, rate as u8)));
+        }
+
+        if let Some(min_links) = opts.min_links {
+            data.add_attribute(Box::new(RouteAttr::u32(IFLA_BOND_MIN_LINKS, min_links)));
+        }
+
+        data
+    }
+
     pub fn from_veth(
         attrs: &LinkAttrs,
         peer_name: &str,
@@ -265,7 +412,7 @@ impl RouteAttr {
         let mut sub_attrs = Vec::new();
         let mut peer_info = RouteAttr::new(VETH_INFO_PEER, &[]);
 
-        peer_info.add_attribute(Box::new(LinkMessage::new(libc::AF_UNSPEC)));
+        peer_info.add_attribute(Box::new(IfInfoMsg::new(libc::AF_UNSPEC)));
         peer_info.add(libc::IFLA_IFNAME, &zero_terminated(peer_name));
 
         if attrs.mtu > 0 {
@@ -291,7 +438,8 @@ impl RouteAttr {
         match peer_ns {
             Some(Namespace::Pid(pid)) => peer_info.add(libc::IFLA_NET_NS_PID, &pid.to_ne_bytes()),
             Some(Namespace::Fd(fd)) => peer_info.add(libc::IFLA_NET_NS_FD, &fd.to_ne_bytes()),
-            _ => (),
+            // Opened and passed as a `Namespace::Fd` by `LinkHandle::add`.
+            Some(Namespace::Path(_)) | None => (),
         }
 
         sub_attrs.push(Box::new(peer_info) as Box<dyn Attribute>);
@@ -382,14 +530,64 @@ impl RouteAttr {
         if let Some((low, high)) = vxlan_attrs.port_range {
             if low > 0 || high > 0 {
                 let mut buf = [0; 4];
-                buf[..2].copy_from_slice(&low.to_ne_bytes());
-                buf[2..].copy_from_slice(&high.to_ne_bytes());
+                buf[..2].copy_from_slice(&low.to_be_bytes());
+                buf[2..].copy_from_slice(&high.to_be_bytes());
                 add_attr(true, IFLA_VXLAN_PORT_RANGE, &buf);
             }
         }
 
         Some(Self::with_attrs(libc::IFLA_INFO_DATA, &[], Some(attrs)))
     }
+}
+
+impl RouteAttr {
+    pub fn new(rta_type: u16, payload: &[u8]) -> Self {
+        Self::with_attrs(rta_type, payload, None)
+    }
+
+    pub fn u8(rta_type: u16, v: u8) -> Self {
+        Self::new(rta_type, &[v])
+    }
+
+    pub fn u16(rta_type: u16, v: u16) -> Self {
+        Self::new(rta_type, &v.to_ne_bytes())
+    }
+
+    /// A `u16` in network byte order, e.g. a port.
+    pub fn u16_be(rta_type: u16, v: u16) -> Self {
+        Self::new(rta_type, &v.to_be_bytes())
+    }
+
+    pub fn u32(rta_type: u16, v: u32) -> Self {
+        Self::new(rta_type, &v.to_ne_bytes())
+    }
+
+    /// A `u32` in network byte order, e.g. a tunnel key.
+    pub fn u32_be(rta_type: u16, v: u32) -> Self {
+        Self::new(rta_type, &v.to_be_bytes())
+    }
+
+    pub fn u64(rta_type: u16, v: u64) -> Self {
+        Self::new(rta_type, &v.to_ne_bytes())
+    }
+
+    /// A `u64` in network byte order, e.g. an `FRA_TUN_ID`.
+    pub fn u64_be(rta_type: u16, v: u64) -> Self {
+        Self::new(rta_type, &v.to_be_bytes())
+    }
+
+    /// A NUL-terminated string.
+    pub fn string(rta_type: u16, s: &str) -> Self {
+        Self::new(rta_type, &zero_terminated(s))
+    }
+
+    /// The 4 or 16 address bytes, depending on the address family.
+    pub fn ip(rta_type: u16, ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => Self::new(rta_type, &ip.octets()),
+            IpAddr::V6(ip) => Self::new(rta_type, &ip.octets()),
+        }
+    }
 
     fn with_attrs(rta_type: u16, payload: &[u8], attrs: Option<Vec<Box<dyn Attribute>>>) -> Self {
         Self {
@@ -445,41 +643,99 @@ impl Deref for Payload {
 impl Payload {
     pub fn to_string(&self) -> Result<String> {
         let mut buf = self.to_vec();
-        buf.truncate(self.len() - 1);
+        buf.truncate(self.len().saturating_sub(1));
         String::from_utf8(buf).map_err(|e| e.into())
     }
 
     pub fn to_u16(&self) -> Result<u16> {
-        let mut buf = self.to_vec();
-        buf.truncate(2);
-        Ok(u16::from_ne_bytes(buf.try_into().unwrap()))
+        Ok(u16::from_ne_bytes(self.prefix()?))
     }
 
     pub fn to_u32(&self) -> Result<u32> {
-        let mut buf = self.to_vec();
-        buf.truncate(4);
-        Ok(u32::from_ne_bytes(buf.try_into().unwrap()))
+        Ok(u32::from_ne_bytes(self.prefix()?))
     }
 
     pub fn to_i32(&self) -> Result<i32> {
-        let mut buf = self.to_vec();
-        buf.truncate(4);
-        Ok(i32::from_ne_bytes(buf.try_into().unwrap()))
+        Ok(i32::from_ne_bytes(self.prefix()?))
+    }
+
+    pub fn as_u8(&self) -> Result<u8> {
+        Ok(self.as_array::<1>()?[0])
+    }
+
+    pub fn as_u16(&self) -> Result<u16> {
+        Ok(u16::from_ne_bytes(self.as_array()?))
+    }
+
+    pub fn as_u32(&self) -> Result<u32> {
+        Ok(u32::from_ne_bytes(self.as_array()?))
+    }
+
+    pub fn as_u64(&self) -> Result<u64> {
+        Ok(u64::from_ne_bytes(self.as_array()?))
+    }
+
+    pub fn as_u16_be(&self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.as_array()?))
+    }
+
+    pub fn as_u32_be(&self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.as_array()?))
+    }
+
+    pub fn as_u64_be(&self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.as_array()?))
+    }
+
+    /// Reads a NUL-terminated string. Unlike `to_string`, a payload without
+    /// the terminator is rejected instead of losing its last character.
+    pub fn as_string(&self) -> Result<String> {
+        match self.split_last() {
+            Some((0, s)) => Ok(String::from_utf8(s.to_vec())?),
+            _ => bail!("string attribute is not NUL-terminated"),
+        }
+    }
+
+    /// Reads an address of the given family, `AF_INET` or `AF_INET6`.
+    pub fn as_ip(&self, family: u8) -> Result<IpAddr> {
+        match family {
+            AF_INET => Ok(Ipv4Addr::from(self.as_array::<4>()?).into()),
+            AF_INET6 => Ok(Ipv6Addr::from(self.as_array::<16>()?).into()),
+            _ => bail!("unsupported address family: {}", family),
+        }
+    }
+
+    /// The first `N` bytes, for the lenient `to_*` readers which ignore any
+    /// trailing bytes.
+    fn prefix<const N: usize>(&self) -> Result<[u8; N]> {
+        match self.get(..N) {
+            Some(buf) => Ok(buf.try_into()?),
+            None => bail!("attribute too short: {}, expected: {}", self.len(), N),
+        }
+    }
+
+    fn as_array<const N: usize>(&self) -> Result<[u8; N]> {
+        match self.as_slice().try_into() {
+            Ok(buf) => Ok(buf),
+            Err(_) => bail!("invalid attribute length: {}, expected: {}", self.len(), N),
+        }
     }
 }
 
+/// `ifinfomsg`, the body of the `RTM_*LINK` messages. `change` selects the
+/// bits of `flags` an `RTM_NEWLINK` or `RTM_SETLINK` modifies.
 #[repr(C)]
-#[derive(Serialize, Deserialize, Default)]
-pub struct LinkMessage {
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct IfInfoMsg {
     pub family: u8,
     pub _pad: u8,
-    pub dev_type: u16,
+    pub if_type: u16,
     pub index: i32,
     pub flags: u32,
-    pub change_mask: u32,
+    pub change: u32,
 }
 
-impl Attribute for LinkMessage {
+impl Attribute for IfInfoMsg {
     fn len(&self) -> usize {
         16
     }
@@ -489,7 +745,7 @@ impl Attribute for LinkMessage {
     }
 }
 
-impl LinkMessage {
+impl IfInfoMsg {
     pub fn new(family: i32) -> Self {
         Self {
             family: family as u8,
@@ -498,9 +754,11 @@ impl LinkMessage {
     }
 }
 
+/// `ifaddrmsg`, the body of the `RTM_*ADDR` messages. `prefix_len` is the
+/// `ifa_prefixlen` and `index` the `ifa_index` of the link.
 #[repr(C)]
-#[derive(Serialize, Deserialize, Default)]
-pub struct AddressMessage {
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct IfAddrMsg {
     pub family: u8,
     pub prefix_len: u8,
     pub flags: u8,
@@ -508,7 +766,7 @@ pub struct AddressMessage {
     pub index: i32,
 }
 
-impl Attribute for AddressMessage {
+impl Attribute for IfAddrMsg {
     fn len(&self) -> usize {
         8
     }
@@ -518,7 +776,7 @@ impl Attribute for AddressMessage {
     }
 }
 
-impl AddressMessage {
+impl IfAddrMsg {
     pub fn new(family: i32) -> Self {
         Self {
             family: family as u8,
@@ -528,7 +786,30 @@ impl AddressMessage {
 }
 
 #[repr(C)]
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct PrefixMessage {
+    pub family: u8,
+    pub _pad1: u8,
+    pub _pad2: u16,
+    pub index: i32,
+    pub prefix_type: u8,
+    pub prefix_len: u8,
+    pub flags: u8,
+    pub _pad3: u8,
+}
+
+impl Attribute for PrefixMessage {
+    fn len(&self) -> usize {
+        12
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 pub struct RouteMessage {
     pub family: u8,
     pub dst_len: u8,
@@ -554,35 +835,103 @@ impl Attribute for RouteMessage {
 impl RouteMessage {
     pub fn new() -> Self {
         Self {
-            table: libc::RT_TABLE_MAIN,
-            protocol: libc::RTPROT_BOOT,
-            scope: libc::RT_SCOPE_UNIVERSE,
-            route_type: libc::RTN_UNICAST,
+            table: RT_TABLE_MAIN,
+            protocol: RTPROT_BOOT,
+            scope: RT_SCOPE_UNIVERSE,
+            route_type: RTN_UNICAST,
             ..Default::default()
         }
     }
 
     pub fn new_delete_msg() -> Self {
         Self {
-            table: libc::RT_TABLE_MAIN,
-            scope: libc::RT_SCOPE_NOWHERE,
+            table: RT_TABLE_MAIN,
+            scope: RT_SCOPE_NOWHERE,
             ..Default::default()
         }
     }
+
+    /// Parses the `rtmsg` at the start of an `RTM_NEWROUTE` payload.
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let msg = Self::default();
+        if buf.len() < msg.len() {
+            bail!("rtmsg too short: {} < {}", buf.len(), msg.len());
+        }
+
+        Ok(deserialize(buf)?)
+    }
 }
 
+/// `fib_rule_hdr`, the header of `RTM_NEWRULE`, `RTM_DELRULE` and
+/// `RTM_GETRULE`. It has the layout of `rtmsg`, but the bytes of the
+/// protocol and the scope are reserved and the type is the action.
 #[repr(C)]
-#[derive(Serialize, Deserialize, Default)]
-pub struct NeighborMessage {
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct RuleMessage {
+    pub family: u8,
+    pub dst_len: u8,
+    pub src_len: u8,
+    pub tos: u8,
+    pub table: u8,
+    pub _res1: u8,
+    pub _res2: u8,
+    pub action: u8,
+    pub flags: u32,
+}
+
+impl Attribute for RuleMessage {
+    fn len(&self) -> usize {
+        12
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+impl RuleMessage {
+    /// Parses the `fib_rule_hdr` at the start of an `RTM_NEWRULE` payload.
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        let msg = Self::default();
+        if buf.len() < msg.len() {
+            bail!("fib_rule_hdr too short: {} < {}", buf.len(), msg.len());
+        }
+
+        Ok(deserialize(buf)?)
+    }
+}
+
+bitflags! {
+    /// The `NUD_*` state of a neighbor entry. Most entries are in exactly one
+    /// state, but the kernel keeps them as bits so requests can match several.
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct NudState: u16 {
+        const INCOMPLETE = 0x01;
+        const REACHABLE = 0x02;
+        const STALE = 0x04;
+        const DELAY = 0x08;
+        const PROBE = 0x10;
+        const FAILED = 0x20;
+        /// The entry needs no resolution, e.g. on a link without ARP.
+        const NOARP = 0x40;
+        /// A static entry, which is never aged out.
+        const PERMANENT = 0x80;
+    }
+}
+
+/// `ndmsg`, the body of the `RTM_*NEIGH` messages.
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct NdMsg {
     pub family: u8,
     pub _pad: [u8; 3],
     pub index: u32,
-    pub state: u16,
+    pub state: NudState,
     pub flags: u8,
     pub neigh_type: u8,
 }
 
-impl Attribute for NeighborMessage {
+impl Attribute for NdMsg {
     fn len(&self) -> usize {
         12
     }
@@ -592,8 +941,8 @@ impl Attribute for NeighborMessage {
     }
 }
 
-impl NeighborMessage {
-    pub fn new(family: u8, index: u32, state: u16, flags: u8, neigh_type: u8) -> Self {
+impl NdMsg {
+    pub fn new(family: u8, index: u32, state: NudState, flags: u8, neigh_type: u8) -> Self {
         Self {
             family,
             _pad: [0; 3],
@@ -605,11 +954,75 @@ impl NeighborMessage {
     }
 }
 
+/// `ndtmsg`, the body of the `RTM_*NEIGHTBL` messages.
 #[repr(C)]
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct NdtMsg {
+    pub family: u8,
+    pub _pad1: u8,
+    pub _pad2: u16,
+}
+
+impl Attribute for NdtMsg {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+/// `tcmsg`
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct TcMessage {
+    pub family: u8,
+    pub _pad1: u8,
+    pub _pad2: u16,
+    pub index: i32,
+    pub handle: u32,
+    pub parent: u32,
+    pub info: u32,
+}
+
+impl Attribute for TcMessage {
+    fn len(&self) -> usize {
+        20
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+/// `nhmsg`
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct NexthopMessage {
+    pub family: u8,
+    pub scope: u8,
+    pub protocol: u8,
+    pub _resvd: u8,
+    pub flags: u32,
+}
+
+impl Attribute for NexthopMessage {
+    fn len(&self) -> usize {
+        8
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
 pub struct GenlMessage {
     pub command: u8,
     pub version: u8,
+    pub reserved: u16,
 }
 
 impl Attribute for GenlMessage {
@@ -623,10 +1036,43 @@ impl Attribute for GenlMessage {
 }
 
 impl GenlMessage {
+    pub fn new(command: u8, version: u8) -> Self {
+        Self {
+            command,
+            version,
+            reserved: 0,
+        }
+    }
+
     pub fn get_family_message() -> Self {
+        Self::new(GENL_CTRL_CMD_GETFAMILY, GENL_CTRL_VERSION)
+    }
+}
+
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+pub struct NfGenMessage {
+    pub family: u8,
+    pub version: u8,
+    pub res_id: u16,
+}
+
+impl Attribute for NfGenMessage {
+    fn len(&self) -> usize {
+        4
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+}
+
+impl NfGenMessage {
+    pub fn new(family: i32) -> Self {
         Self {
-            command: GENL_CTRL_CMD_GETFAMILY,
-            version: GENL_CTRL_VERSION,
+            family: family as u8,
+            version: NFNETLINK_V0,
+            res_id: 0,
         }
     }
 }
@@ -664,11 +1110,190 @@ impl<'a> Buffer<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::types::message::LinkMessage;
+    use std::fmt;
+
+    use proptest::{collection::vec, prelude::*};
+    use serde::de::DeserializeOwned;
+
+    use crate::types::message::IfInfoMsg;
     use crate::types::message::RouteAttrHeader;
+    use crate::types::AF_UNSPEC;
 
     use super::*;
 
+    const RTA_DST: u16 = 1;
+    const RTM_F_CLONED: u32 = 0x200;
+    const IFF_UP: u32 = 0x1;
+    const IFF_LOOPBACK: u32 = 0x8;
+    const IFF_RUNNING: u32 = 0x40;
+
+    fn roundtrip(attr: RouteAttr) -> RouteAttr {
+        RouteAttr::try_from(attr.serialize().unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_route_attr_primitives() {
+        assert_eq!(
+            RouteAttr::u8(1, 0xab).serialize().unwrap(),
+            [0x05, 0x00, 0x01, 0x00, 0xab, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            roundtrip(RouteAttr::u8(1, 0xab)).payload.as_u8().unwrap(),
+            0xab
+        );
+
+        let attr = roundtrip(RouteAttr::u16(2, 0x1234));
+        assert_eq!(attr.header.rta_len, 6);
+        assert_eq!(*attr.payload, 0x1234u16.to_ne_bytes());
+        assert_eq!(attr.payload.as_u16().unwrap(), 0x1234);
+
+        let attr = roundtrip(RouteAttr::u16_be(3, 5555));
+        assert_eq!(*attr.payload, [0x15, 0xb3]);
+        assert_eq!(attr.payload.as_u16_be().unwrap(), 5555);
+
+        let attr = roundtrip(RouteAttr::u32(4, 0xdead_beef));
+        assert_eq!(attr.header.rta_len, 8);
+        assert_eq!(attr.payload.as_u32().unwrap(), 0xdead_beef);
+
+        let attr = roundtrip(RouteAttr::u64(5, u64::MAX - 1));
+        assert_eq!(attr.header.rta_len, 12);
+        assert_eq!(attr.payload.as_u64().unwrap(), u64::MAX - 1);
+    }
+
+    #[test]
+    fn test_route_attr_big_endian() {
+        let attr = roundtrip(RouteAttr::u32_be(1, 0x0102_0304));
+        assert_eq!(*attr.payload, [0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(attr.payload.as_u32_be().unwrap(), 0x0102_0304);
+
+        let attr = roundtrip(RouteAttr::u64_be(2, 0x0102_0304_0506_0708));
+        assert_eq!(
+            *attr.payload,
+            [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]
+        );
+        assert_eq!(attr.payload.as_u64_be().unwrap(), 0x0102_0304_0506_0708);
+
+        assert!(attr.payload.as_u16_be().is_err());
+        assert!(attr.payload.as_u32_be().is_err());
+        assert!(roundtrip(RouteAttr::u8(1, 1)).payload.as_u64_be().is_err());
+    }
+
+    /// `IFLA_VXLAN_PORT` and both ends of `IFLA_VXLAN_PORT_RANGE` are
+    /// `__be16`, while `IFLA_VXLAN_ID` is a host order `u32`.
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_vxlan_byte_order() {
+        let vxlan = VxlanAttrs {
+            id: 10,
+            port: Some(4789),
+            port_range: Some((1000, 2000)),
+            ..Default::default()
+        };
+
+        let attr = RouteAttr::from_vxlan(&vxlan).unwrap();
+        let buf = attr.serialize().unwrap();
+        let attrs = RouteAttrs::from(&buf[4..]);
+        let map = RouteAttrMap::from(&attrs);
+
+        assert_eq!(map[&IFLA_VXLAN_ID], 10u32.to_ne_bytes());
+        assert_eq!(map[&IFLA_VXLAN_PORT], [0x12, 0xb5]);
+        assert_eq!(map[&IFLA_VXLAN_PORT_RANGE], [0x03, 0xe8, 0x07, 0xd0]);
+
+        assert_eq!(map.get_u16_be(&IFLA_VXLAN_PORT), Some(4789));
+        assert_eq!(
+            map.get_u16_tuple_be(&IFLA_VXLAN_PORT_RANGE),
+            Some((1000, 2000))
+        );
+    }
+
+    #[test]
+    fn test_route_attr_primitives_reject_bad_length() {
+        let attr = roundtrip(RouteAttr::u16(1, 1));
+
+        assert!(attr.payload.as_u8().is_err());
+        assert!(attr.payload.as_u32().is_err());
+        assert!(attr.payload.as_u64().is_err());
+        assert!(roundtrip(RouteAttr::u64(1, 1)).payload.as_u32().is_err());
+        assert!(roundtrip(RouteAttr::new(1, &[])).payload.as_u16().is_err());
+    }
+
+    #[test]
+    fn test_route_attr_string() {
+        assert_eq!(
+            RouteAttr::string(3, "lo").serialize().unwrap(),
+            [0x07, 0x00, 0x03, 0x00, b'l', b'o', 0x00, 0x00]
+        );
+        assert_eq!(
+            roundtrip(RouteAttr::string(3, "eth0"))
+                .payload
+                .as_string()
+                .unwrap(),
+            "eth0"
+        );
+        assert_eq!(
+            roundtrip(RouteAttr::string(3, ""))
+                .payload
+                .as_string()
+                .unwrap(),
+            ""
+        );
+
+        assert!(roundtrip(RouteAttr::new(3, b"lo"))
+            .payload
+            .as_string()
+            .is_err());
+        assert!(roundtrip(RouteAttr::new(3, &[]))
+            .payload
+            .as_string()
+            .is_err());
+        assert!(roundtrip(RouteAttr::new(3, &[0xff, 0]))
+            .payload
+            .as_string()
+            .is_err());
+    }
+
+    #[test]
+    fn test_route_attr_ip() {
+        let v4: IpAddr = "10.0.0.1".parse().unwrap();
+        let v6: IpAddr = "fd00::1".parse().unwrap();
+
+        let attr = roundtrip(RouteAttr::ip(RTA_DST, v4));
+        assert_eq!(*attr.payload, [10, 0, 0, 1]);
+        assert_eq!(attr.payload.as_ip(AF_INET).unwrap(), v4);
+        assert!(attr.payload.as_ip(AF_INET6).is_err());
+
+        let attr = roundtrip(RouteAttr::ip(RTA_DST, v6));
+        assert_eq!(attr.header.rta_len, 20);
+        assert_eq!(attr.payload.as_ip(AF_INET6).unwrap(), v6);
+        assert!(attr.payload.as_ip(AF_INET).is_err());
+
+        assert!(attr.payload.as_ip(AF_UNSPEC).is_err());
+    }
+
+    #[test]
+    fn test_route_message_roundtrip() {
+        let v4 = RouteMessage {
+            family: AF_INET,
+            dst_len: 24,
+            ..RouteMessage::new()
+        };
+        let v6 = RouteMessage {
+            family: AF_INET6,
+            dst_len: 64,
+            src_len: 48,
+            table: 100,
+            flags: RTM_F_CLONED,
+            ..RouteMessage::new_delete_msg()
+        };
+
+        for msg in [v4, v6] {
+            let buf = Attribute::serialize(&msg).unwrap();
+            assert_eq!(buf.len(), msg.len());
+            assert_eq!(RouteMessage::deserialize(&buf).unwrap(), msg);
+            assert!(RouteMessage::deserialize(&buf[..buf.len() - 1]).is_err());
+        }
+    }
+
     struct TestAttribute {
         len: usize,
     }
@@ -710,16 +1335,13 @@ mod tests {
 
     #[test]
     fn test_link_header_deserialize() {
-        let msg: LinkMessage = bincode::deserialize(&NETLINK_MSG).unwrap();
+        let msg: IfInfoMsg = bincode::deserialize(&NETLINK_MSG).unwrap();
 
         assert_eq!(msg.family, 0);
-        assert_eq!(msg.dev_type, 772);
+        assert_eq!(msg.if_type, 772);
         assert_eq!(msg.index, 1);
-        assert_eq!(
-            msg.flags,
-            libc::IFF_UP as u32 | libc::IFF_LOOPBACK as u32 | libc::IFF_RUNNING as u32
-        );
-        assert_eq!(msg.change_mask, 0);
+        assert_eq!(msg.flags, IFF_UP | IFF_LOOPBACK | IFF_RUNNING);
+        assert_eq!(msg.change, 0);
     }
 
     #[test]
@@ -743,17 +1365,17 @@ mod tests {
     }
 
     #[test]
-    fn test_link_message_serialize() {
-        let link_message = LinkMessage {
+    fn test_if_info_msg_serialize() {
+        let msg = IfInfoMsg {
             family: 1,
             _pad: 0,
-            dev_type: 2,
+            if_type: 2,
             index: 3,
             flags: 4,
-            change_mask: 5,
+            change: 5,
         };
 
-        let serialized = Attribute::serialize(&link_message).unwrap();
+        let serialized = Attribute::serialize(&msg).unwrap();
 
         // Assert the serialized bytes are correct
         assert_eq!(
@@ -762,12 +1384,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_if_addr_msg_serialize() {
+        let msg = IfAddrMsg {
+            family: AF_INET6,
+            prefix_len: 64,
+            flags: 0x80,
+            scope: 253,
+            index: 7,
+        };
+
+        let serialized = Attribute::serialize(&msg).unwrap();
+
+        assert_eq!(serialized.len(), msg.len());
+        assert_eq!(serialized, vec![10, 64, 0x80, 253, 7, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_nd_msg_serialize() {
+        let msg = NdMsg::new(AF_INET, 3, NudState::PERMANENT | NudState::NOARP, 0x02, 1);
+
+        let serialized = Attribute::serialize(&msg).unwrap();
+
+        assert_eq!(serialized.len(), msg.len());
+        assert_eq!(serialized, vec![2, 0, 0, 0, 3, 0, 0, 0, 0xc0, 0, 0x02, 1]);
+    }
+
+    #[test]
+    fn test_genl_message_serialize() {
+        let msg = GenlMessage::get_family_message();
+        let serialized = Attribute::serialize(&msg).unwrap();
+
+        assert_eq!(serialized.len(), msg.len());
+        assert_eq!(
+            serialized,
+            vec![GENL_CTRL_CMD_GETFAMILY, GENL_CTRL_VERSION, 0, 0]
+        );
+    }
+
     #[test]
     fn test_route_attrs_from() {
         let route_attrs = RouteAttrs::from(&NETLINK_MSG[16..]);
         assert_eq!(route_attrs.len(), 10);
     }
 
+    #[test]
+    fn test_route_attr_try_from_truncated() {
+        // rta_len = 8, but only 2 bytes of payload follow.
+        let buf = [0x08, 0x00, 0x01, 0x00, 0xab, 0xcd];
+
+        assert!(RouteAttr::try_from(&buf[..]).is_err());
+        assert!(RouteAttr::try_from(&buf[..2]).is_err());
+    }
+
+    #[rustfmt::skip]
+    #[cfg(target_os = "linux")]
+    static UNALIGNED_ATTRS: [u8; 16] = [
+        0x07, 0x00, 0x03, 0x00, // rta_len = 7, rta_type = IFLA_IFNAME
+        b'e', b't', 0x00, // "et"
+        0x00, // padding
+        0x08, 0x00, 0x04, 0x00, // rta_len = 8, rta_type = IFLA_MTU
+        0xdc, 0x05, 0x00, 0x00, // 1500
+    ];
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_route_attrs_from_unaligned_len() {
+        let attrs = RouteAttrs::from(&UNALIGNED_ATTRS[..]);
+
+        // The second attribute starts at the 4 byte aligned end of the
+        // first, not at its `rta_len`.
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].header.rta_type, libc::IFLA_IFNAME);
+        assert_eq!(attrs[0].payload.as_string().unwrap(), "et");
+        assert_eq!(attrs[1].header.rta_type, libc::IFLA_MTU);
+        assert_eq!(attrs[1].payload.as_u32().unwrap(), 1500);
+    }
+
     #[test]
     fn test_route_attr_new() {
         let payload = Payload::from(&[0; 10][..]);
@@ -796,4 +1489,177 @@ mod tests {
         assert_eq!(attributes.len(), 1);
         assert_eq!(attributes[0].len(), 5);
     }
+
+    #[test]
+    fn test_serialize_aligned() {
+        let attr = TestAttribute { len: 5 };
+        assert_eq!(attr.serialize().unwrap().len(), 5);
+        assert_eq!(attr.serialize_aligned().unwrap(), [0; 8]);
+
+        let attr = TestAttribute { len: 8 };
+        assert_eq!(attr.serialize_aligned().unwrap().len(), 8);
+
+        // A nested attribute of odd length is padded before the next one.
+        let mut attr = RouteAttr::new(1, &[]);
+        attr.add_attribute(Box::new(TestAttribute { len: 5 }));
+        attr.add_attribute(Box::new(RouteAttr::u8(2, 0xab)));
+
+        let buf = attr.serialize().unwrap();
+        assert_eq!(buf.len(), 4 + 8 + 8);
+        assert_eq!(buf[..2], 20u16.to_ne_bytes());
+        assert_eq!(buf[12..], [0x05, 0x00, 0x02, 0x00, 0xab, 0x00, 0x00, 0x00]);
+    }
+
+    fn header_roundtrip<T>(msg: T) -> Result<(), TestCaseError>
+    where
+        T: Attribute + DeserializeOwned + PartialEq + fmt::Debug,
+    {
+        let buf = msg.serialize().unwrap();
+        prop_assert_eq!(buf.len(), msg.len());
+        prop_assert_eq!(bincode::deserialize::<T>(&buf).unwrap(), msg);
+        Ok(())
+    }
+
+    fn attr_strategy() -> impl Strategy<Value = (u16, Vec<u8>)> {
+        (any::<u16>(), vec(any::<u8>(), 0..64))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_if_info_msg_roundtrip(
+            (family, _pad, if_type, index) in any::<(u8, u8, u16, i32)>(),
+            (flags, change) in any::<(u32, u32)>(),
+        ) {
+            header_roundtrip(IfInfoMsg { family, _pad, if_type, index, flags, change })?;
+        }
+
+        #[test]
+        fn prop_if_addr_msg_roundtrip(
+            (family, prefix_len, flags, scope, index) in any::<(u8, u8, u8, u8, i32)>()
+        ) {
+            header_roundtrip(IfAddrMsg { family, prefix_len, flags, scope, index })?;
+        }
+
+        #[test]
+        fn prop_prefix_message_roundtrip(
+            (family, _pad1, _pad2, index) in any::<(u8, u8, u16, i32)>(),
+            (prefix_type, prefix_len, flags, _pad3) in any::<(u8, u8, u8, u8)>(),
+        ) {
+            header_roundtrip(PrefixMessage {
+                family, _pad1, _pad2, index, prefix_type, prefix_len, flags, _pad3,
+            })?;
+        }
+
+        #[test]
+        fn prop_route_message_roundtrip(
+            (family, dst_len, src_len, tos) in any::<(u8, u8, u8, u8)>(),
+            (table, protocol, scope, route_type, flags) in any::<(u8, u8, u8, u8, u32)>(),
+        ) {
+            let msg = RouteMessage {
+                family, dst_len, src_len, tos, table, protocol, scope, route_type, flags,
+            };
+            let buf = Attribute::serialize(&msg).unwrap();
+            prop_assert_eq!(RouteMessage::deserialize(&buf).unwrap(), msg.clone());
+            header_roundtrip(msg)?;
+        }
+
+        #[test]
+        fn prop_rule_message_roundtrip(
+            (family, dst_len, src_len, tos) in any::<(u8, u8, u8, u8)>(),
+            (table, _res1, _res2, action, flags) in any::<(u8, u8, u8, u8, u32)>(),
+        ) {
+            let msg = RuleMessage {
+                family, dst_len, src_len, tos, table, _res1, _res2, action, flags,
+            };
+            let buf = Attribute::serialize(&msg).unwrap();
+            prop_assert_eq!(RuleMessage::deserialize(&buf).unwrap(), msg.clone());
+            prop_assert!(RuleMessage::deserialize(&buf[..buf.len() - 1]).is_err());
+            header_roundtrip(msg)?;
+        }
+
+        #[test]
+        fn prop_nd_msg_roundtrip(
+            (family, _pad, index) in any::<(u8, [u8; 3], u32)>(),
+            (state, flags, neigh_type) in any::<(u16, u8, u8)>(),
+        ) {
+            let state = NudState::from_bits_retain(state);
+            header_roundtrip(NdMsg { family, _pad, index, state, flags, neigh_type })?;
+        }
+
+        #[test]
+        fn prop_tc_message_roundtrip(
+            (family, _pad1, _pad2, index) in any::<(u8, u8, u16, i32)>(),
+            (handle, parent, info) in any::<(u32, u32, u32)>(),
+        ) {
+            header_roundtrip(TcMessage { family, _pad1, _pad2, index, handle, parent, info })?;
+        }
+
+        #[test]
+        fn prop_nexthop_message_roundtrip(
+            (family, scope, protocol, _resvd, flags) in any::<(u8, u8, u8, u8, u32)>()
+        ) {
+            header_roundtrip(NexthopMessage { family, scope, protocol, _resvd, flags })?;
+        }
+
+        #[test]
+        fn prop_genl_message_roundtrip((command, version, reserved) in any::<(u8, u8, u16)>()) {
+            header_roundtrip(GenlMessage { command, version, reserved })?;
+        }
+
+        #[test]
+        fn prop_nfgen_message_roundtrip((family, version, res_id) in any::<(u8, u8, u16)>()) {
+            header_roundtrip(NfGenMessage { family, version, res_id })?;
+        }
+
+        #[test]
+        fn prop_route_attrs_roundtrip(
+            attrs in vec(attr_strategy(), 0..8),
+            (nested_type, nested) in (any::<u16>(), vec(attr_strategy(), 1..4)),
+        ) {
+            let mut route_attrs = RouteAttrs::default();
+            for (rta_type, payload) in &attrs {
+                route_attrs.push(RouteAttr::new(*rta_type, payload));
+            }
+            let children = nested
+                .iter()
+                .map(|(rta_type, payload)| {
+                    Box::new(RouteAttr::new(*rta_type, payload)) as Box<dyn Attribute>
+                })
+                .collect();
+            route_attrs.push(RouteAttr::with_attrs(nested_type, &[], Some(children)));
+
+            let buf = route_attrs.serialize().unwrap();
+            let parsed = RouteAttrs::from(buf.as_slice());
+            prop_assert_eq!(parsed.len(), attrs.len() + 1);
+
+            for (attr, (rta_type, payload)) in parsed.iter().zip(&attrs) {
+                prop_assert_eq!(attr.header.rta_type, *rta_type);
+                prop_assert_eq!(attr.payload.as_slice(), payload.as_slice());
+            }
+
+            let last = parsed.last().unwrap();
+            prop_assert_eq!(last.header.rta_type, nested_type);
+            let parsed_nested = RouteAttrs::from(last.payload.as_slice());
+            prop_assert_eq!(parsed_nested.len(), nested.len());
+            for (attr, (rta_type, payload)) in parsed_nested.iter().zip(&nested) {
+                prop_assert_eq!(attr.header.rta_type, *rta_type);
+                prop_assert_eq!(attr.payload.as_slice(), payload.as_slice());
+            }
+        }
+
+        /// Walking arbitrary bytes stops at the first malformed attribute, and
+        /// reading any attribute fails instead of panicking.
+        #[test]
+        fn prop_route_attrs_arbitrary_bytes(buf in vec(any::<u8>(), 0..256)) {
+            for attr in RouteAttrs::from(buf.as_slice()) {
+                prop_assert!(attr.header.rta_len as usize >= RT_ATTR_HDR_SIZE);
+                let _ = attr.payload.to_string();
+                let _ = attr.payload.to_u16();
+                let _ = attr.payload.to_u32();
+                let _ = attr.payload.to_i32();
+                let _ = attr.payload.as_u64();
+                let _ = attr.payload.as_string();
+            }
+        }
+    }
 }
@@ -3,12 +3,55 @@ use std::net::IpAddr;
 use anyhow::Result;
 use thiserror::Error;
 
+#[cfg(target_os = "linux")]
 pub mod addr;
+pub mod bpf;
+#[cfg(target_os = "linux")]
+pub mod conntrack;
+pub mod ethtool;
+#[cfg(target_os = "linux")]
+pub mod fou;
+#[cfg(target_os = "linux")]
 pub mod generic;
+#[cfg(target_os = "linux")]
 pub mod link;
 pub mod message;
+pub mod mptcp;
+pub mod ndt;
+#[cfg(target_os = "linux")]
 pub mod neigh;
+#[cfg(target_os = "linux")]
+pub mod nexthop;
+#[cfg(target_os = "linux")]
+pub mod nft;
+pub mod nl80211;
+#[cfg(target_os = "linux")]
 pub mod routing;
+pub mod rule;
+pub mod seg6;
+pub mod sock_diag;
+pub mod tc;
+#[cfg(target_os = "linux")]
+pub mod xfrm;
+
+// The uapi values the portable modules need. They are the same on every Linux
+// architecture, but `libc` only defines them, or defines them with these
+// values, when targeting Linux.
+pub const AF_UNSPEC: u8 = 0;
+pub const AF_INET: u8 = 2;
+pub const AF_INET6: u8 = 10;
+
+pub const IPPROTO_TCP: u8 = 6;
+pub const IPPROTO_UDP: u8 = 17;
+pub const IPPROTO_SCTP: u8 = 132;
+
+pub const RT_TABLE_MAIN: u8 = 254;
+pub const RTPROT_BOOT: u8 = 3;
+pub const RT_SCOPE_UNIVERSE: u8 = 0;
+pub const RT_SCOPE_NOWHERE: u8 = 255;
+pub const RTN_UNICAST: u8 = 1;
+
+pub const NFNETLINK_V0: u8 = 0;
 
 pub const IFLA_VXLAN_UNSPEC: u16 = 0;
 pub const IFLA_VXLAN_ID: u16 = 1;
@@ -38,6 +81,10 @@ pub const IFLA_VXLAN_REMCSUM_NOPARTIAL: u16 = 24;
 pub const IFLA_VXLAN_FLOWBASED: u16 = 25;
 pub const IFLA_VXLAN_MAX: u16 = IFLA_VXLAN_FLOWBASED;
 
+pub const NLA_F_NESTED: u16 = 0x8000;
+pub const NLA_F_NET_BYTEORDER: u16 = 0x4000;
+pub const NLA_TYPE_MASK: u16 = !(NLA_F_NESTED | NLA_F_NET_BYTEORDER);
+
 pub const GENL_CTRL_VERSION: u8 = 2;
 pub const GENL_CTRL_CMD_GETFAMILY: u8 = 3;
 
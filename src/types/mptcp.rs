@@ -0,0 +1,118 @@
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Result};
+
+use super::{message::RouteAttrs, AF_INET, AF_INET6, NLA_TYPE_MASK};
+
+pub const MPTCP_PM_NAME: &str = "mptcp_pm";
+pub const MPTCP_PM_VER: u8 = 1;
+
+pub const MPTCP_PM_CMD_ADD_ADDR: u8 = 1;
+pub const MPTCP_PM_CMD_DEL_ADDR: u8 = 2;
+pub const MPTCP_PM_CMD_GET_ADDR: u8 = 3;
+
+pub const MPTCP_PM_ATTR_ADDR: u16 = 1;
+
+pub const MPTCP_PM_ADDR_ATTR_FAMILY: u16 = 1;
+pub const MPTCP_PM_ADDR_ATTR_ID: u16 = 2;
+pub const MPTCP_PM_ADDR_ATTR_ADDR4: u16 = 3;
+pub const MPTCP_PM_ADDR_ATTR_ADDR6: u16 = 4;
+pub const MPTCP_PM_ADDR_ATTR_PORT: u16 = 5;
+pub const MPTCP_PM_ADDR_ATTR_FLAGS: u16 = 6;
+pub const MPTCP_PM_ADDR_ATTR_IF_IDX: u16 = 7;
+
+pub const MPTCP_PM_ADDR_FLAG_SIGNAL: u32 = 1 << 0;
+pub const MPTCP_PM_ADDR_FLAG_SUBFLOW: u32 = 1 << 1;
+pub const MPTCP_PM_ADDR_FLAG_BACKUP: u32 = 1 << 2;
+pub const MPTCP_PM_ADDR_FLAG_FULLMESH: u32 = 1 << 3;
+pub const MPTCP_PM_ADDR_FLAG_IMPLICIT: u32 = 1 << 4;
+
+/// An endpoint of the in-kernel path manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MptcpAddr {
+    pub id: u8,
+    pub addr: IpAddr,
+    pub port: u16,
+    pub flags: u32,
+    pub ifindex: i32,
+}
+
+impl TryFrom<&[u8]> for MptcpAddr {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("mptcp message too short: {} bytes", buf.len()))?,
+        );
+        let nested = attrs
+            .iter()
+            .find(|attr| attr.header.rta_type & NLA_TYPE_MASK == MPTCP_PM_ATTR_ADDR)
+            .ok_or_else(|| anyhow!("mptcp endpoint without MPTCP_PM_ATTR_ADDR"))?;
+
+        let mut id = 0;
+        let mut addr = None;
+        let mut port = 0;
+        let mut flags = 0;
+        let mut ifindex = 0;
+
+        for attr in RouteAttrs::from(nested.payload.as_slice()) {
+            match attr.header.rta_type & NLA_TYPE_MASK {
+                MPTCP_PM_ADDR_ATTR_ID => id = attr.payload.as_u8()?,
+                MPTCP_PM_ADDR_ATTR_ADDR4 => addr = Some(attr.payload.as_ip(AF_INET)?),
+                MPTCP_PM_ADDR_ATTR_ADDR6 => addr = Some(attr.payload.as_ip(AF_INET6)?),
+                MPTCP_PM_ADDR_ATTR_PORT => port = attr.payload.to_u16()?,
+                MPTCP_PM_ADDR_ATTR_FLAGS => flags = attr.payload.to_u32()?,
+                MPTCP_PM_ADDR_ATTR_IF_IDX => ifindex = attr.payload.to_i32()?,
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            id,
+            addr: addr.ok_or_else(|| anyhow!("mptcp endpoint without address"))?,
+            port,
+            flags,
+            ifindex,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static GET_ADDR_MSG: [u8; 40] = [
+        0x03, 0x01, 0x00, 0x00, // genlmsghdr: cmd = MPTCP_PM_CMD_GET_ADDR, version = 1
+        0x24, 0x00, 0x01, 0x80, // addr L=36,T=1|NLA_F_NESTED
+        0x06, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, // family = AF_INET
+        0x05, 0x00, 0x02, 0x00, 0x03, 0x00, 0x00, 0x00, // id = 3
+        0x08, 0x00, 0x03, 0x00, 0x0a, 0x00, 0x00, 0x01, // addr4 = 10.0.0.1
+        0x08, 0x00, 0x06, 0x00, 0x02, 0x00, 0x00, 0x00, // flags = SUBFLOW
+    ];
+
+    #[test]
+    fn test_mptcp_addr_try_from() {
+        let addr = MptcpAddr::try_from(&GET_ADDR_MSG[..]).unwrap();
+
+        assert_eq!(addr.id, 3);
+        assert_eq!(addr.addr, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(addr.port, 0);
+        assert_eq!(addr.flags, MPTCP_PM_ADDR_FLAG_SUBFLOW);
+        assert_eq!(addr.ifindex, 0);
+
+        assert!(MptcpAddr::try_from(&GET_ADDR_MSG[..4]).is_err());
+        assert!(MptcpAddr::try_from(&GET_ADDR_MSG[..2]).is_err());
+
+        // MPTCP_PM_ADDR_ATTR_ID without a payload.
+        let mut empty_id = GET_ADDR_MSG;
+        empty_id[16] = 0x04;
+        assert!(MptcpAddr::try_from(&empty_id[..]).is_err());
+
+        // MPTCP_PM_ADDR_ATTR_ADDR4 with a 2 byte payload.
+        let mut short_addr = GET_ADDR_MSG;
+        short_addr[24] = 0x06;
+        assert!(MptcpAddr::try_from(&short_addr[..]).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::message::{Attribute, NdtMsg, RouteAttrs};
+
+pub const NDTA_NAME: u16 = 1;
+pub const NDTA_THRESH1: u16 = 2;
+pub const NDTA_THRESH2: u16 = 3;
+pub const NDTA_THRESH3: u16 = 4;
+pub const NDTA_CONFIG: u16 = 5;
+pub const NDTA_PARMS: u16 = 6;
+pub const NDTA_STATS: u16 = 7;
+pub const NDTA_GC_INTERVAL: u16 = 8;
+
+pub const NDTPA_IFINDEX: u16 = 1;
+pub const NDTPA_REACHABLE_TIME: u16 = 3;
+pub const NDTPA_BASE_REACHABLE_TIME: u16 = 4;
+pub const NDTPA_GC_STALETIME: u16 = 6;
+
+/// `struct ndt_stats`, the counters of a neighbor table.
+#[repr(C)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct NeighTableStats {
+    pub allocs: u64,
+    pub destroys: u64,
+    pub hash_grows: u64,
+    pub res_failed: u64,
+    pub lookups: u64,
+    pub hits: u64,
+    pub rcv_probes_mcast: u64,
+    pub rcv_probes_ucast: u64,
+    pub periodic_gc_runs: u64,
+    pub forced_gc_runs: u64,
+    /// The times an entry could not be added because the table was full,
+    /// i.e. had `gc_thresh3` entries.
+    pub table_fulls: u64,
+}
+
+/// A neighbor table, e.g. `arp_cache` or `ndisc_cache`, with the default
+/// parameters of its entries.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NeighTable {
+    pub name: String,
+    pub family: u8,
+    /// The length of the protocol address, e.g. 4 for `arp_cache`.
+    pub key_len: u16,
+    pub entry_size: u16,
+    /// The number of entries in the table.
+    pub entries: u32,
+    /// Below this many entries the garbage collector leaves the table alone.
+    pub gc_thresh1: u32,
+    /// Above this many entries the garbage collector runs within 5 seconds.
+    pub gc_thresh2: u32,
+    /// The most entries the table holds.
+    pub gc_thresh3: u32,
+    pub gc_interval: Duration,
+    /// The time an entry is reachable for after a confirmation, picked at
+    /// random around `base_reachable_time`.
+    pub reachable_time: Duration,
+    pub base_reachable_time: Duration,
+    pub gc_stale_time: Duration,
+    pub stats: NeighTableStats,
+}
+
+impl TryFrom<&[u8]> for NeighTable {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg: NdtMsg = bincode::deserialize(buf)?;
+
+        let mut table = Self {
+            family: msg.family,
+            ..Default::default()
+        };
+
+        for attr in RouteAttrs::from(&buf[msg.len()..]) {
+            match attr.header.rta_type {
+                NDTA_NAME => table.name = attr.payload.as_string()?,
+                NDTA_THRESH1 => table.gc_thresh1 = attr.payload.as_u32()?,
+                NDTA_THRESH2 => table.gc_thresh2 = attr.payload.as_u32()?,
+                NDTA_THRESH3 => table.gc_thresh3 = attr.payload.as_u32()?,
+                NDTA_GC_INTERVAL => {
+                    table.gc_interval = Duration::from_millis(attr.payload.as_u64()?)
+                }
+                NDTA_CONFIG => {
+                    // `struct ndt_config`, of which the fields after
+                    // `ndtc_entries` are internals of the hash table.
+                    if attr.payload.len() < 8 {
+                        bail!("ndt_config too short: {} bytes", attr.payload.len());
+                    }
+                    table.key_len = u16::from_ne_bytes([attr.payload[0], attr.payload[1]]);
+                    table.entry_size = u16::from_ne_bytes([attr.payload[2], attr.payload[3]]);
+                    table.entries = u32::from_ne_bytes(attr.payload[4..8].try_into()?);
+                }
+                NDTA_STATS => table.stats = bincode::deserialize(&attr.payload)?,
+                NDTA_PARMS => {
+                    for parm in RouteAttrs::from(attr.payload.as_slice()) {
+                        let ms = || parm.payload.as_u64().map(Duration::from_millis);
+                        match parm.header.rta_type {
+                            NDTPA_REACHABLE_TIME => table.reachable_time = ms()?,
+                            NDTPA_BASE_REACHABLE_TIME => table.base_reachable_time = ms()?,
+                            NDTPA_GC_STALETIME => table.gc_stale_time = ms()?,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(table)
+    }
+}
+
+/// The parameters `NdtHandle::set` changes. Unset ones are left as they are.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NeighTableParams {
+    /// The link whose own parameters to change, or `None` for the defaults
+    /// of the table, which only the initial network namespace may change.
+    pub ifindex: Option<u32>,
+    /// The thresholds and the interval of the garbage collector are global,
+    /// other namespaces fail to change them with `ENOENT`.
+    pub gc_thresh1: Option<u32>,
+    pub gc_thresh2: Option<u32>,
+    pub gc_thresh3: Option<u32>,
+    pub gc_interval: Option<Duration>,
+    pub base_reachable_time: Option<Duration>,
+    pub gc_stale_time: Option<Duration>,
+}
@@ -3,7 +3,7 @@ use std::net::IpAddr;
 use anyhow::Result;
 use derive_builder::Builder;
 
-use crate::types::message::{Attribute, NeighborMessage, RouteAttrs};
+use crate::types::message::{Attribute, NdMsg, NudState, RouteAttrs};
 
 use super::vec_to_addr;
 
@@ -12,7 +12,7 @@ use super::vec_to_addr;
 pub struct Neighbor {
     pub link_index: u32,
     pub family: Option<u8>,
-    pub state: u16,
+    pub state: NudState,
     pub ip_addr: Option<IpAddr>,
     pub mac_addr: Option<Vec<u8>>,
     pub neigh_type: u8,
@@ -21,7 +21,7 @@ pub struct Neighbor {
 
 impl From<&[u8]> for Neighbor {
     fn from(buf: &[u8]) -> Self {
-        let neigh_msg: NeighborMessage = bincode::deserialize(buf).unwrap();
+        let neigh_msg: NdMsg = bincode::deserialize(buf).unwrap();
         let rt_attrs = RouteAttrs::from(&buf[neigh_msg.len()..]);
 
         let mut neighbor = Self {
@@ -49,6 +49,17 @@ impl From<&[u8]> for Neighbor {
     }
 }
 
+/// An IP address whose neighbor entry moved to another MAC address or link,
+/// see `NeighHandle::subscribe_mac_move`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacMoveEvent {
+    pub ip: IpAddr,
+    pub old_mac: Vec<u8>,
+    pub new_mac: Vec<u8>,
+    pub old_ifindex: u32,
+    pub new_ifindex: u32,
+}
+
 impl NeighborBuilder {
     fn validate(&self) -> Result<(), String> {
         if self.ip_addr.is_none() {
@@ -77,7 +88,7 @@ mod tests {
     fn test_neighbor_builder_arp() {
         let _ = NeighborBuilder::default()
             .link_index(5)
-            .state(128)
+            .state(NudState::PERMANENT)
             .ip_addr(Some(IpAddr::V4("10.244.1.0".parse().unwrap())))
             .mac_addr(Some(vec![0x02, 0x12, 0x34, 0x56, 0x78, 0x9A]))
             .neigh_type(1)
@@ -89,7 +100,7 @@ mod tests {
     fn test_neighbor_build_fdb() {
         let _ = NeighborBuilder::default()
             .link_index(5)
-            .state(128)
+            .state(NudState::PERMANENT)
             .ip_addr(Some(IpAddr::V4("10.244.1.0".parse().unwrap())))
             .mac_addr(Some(vec![0x02, 0x12, 0x34, 0x56, 0x78, 0x9A]))
             .family(Some(7))
@@ -101,10 +112,10 @@ mod tests {
     #[test]
     fn test_from_bytes() {
         let mac_bytes = parse_mac("aa:bb:cc:dd:00:01").unwrap();
-        let neigh_msg = NeighborMessage {
+        let neigh_msg = NdMsg {
             family: libc::AF_INET as u8,
             index: 5,
-            state: 128,
+            state: NudState::PERMANENT,
             neigh_type: 1,
             ..Default::default()
         };
@@ -126,7 +137,7 @@ mod tests {
             attributes: None,
         });
 
-        let mut buf = NeighborMessage::serialize(&neigh_msg).unwrap();
+        let mut buf = NdMsg::serialize(&neigh_msg).unwrap();
         buf.extend_from_slice(RouteAttrs::serialize(&rt_attrs).unwrap().as_slice());
 
         let neighbor = Neighbor::from(&buf[..]);
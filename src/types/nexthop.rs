@@ -0,0 +1,289 @@
+use std::net::IpAddr;
+
+use anyhow::{bail, Result};
+
+use super::{
+    message::{Attribute, NexthopMessage, RouteAttr, RouteAttrs},
+    vec_to_addr,
+};
+
+pub const RTM_NEWNEXTHOP: u16 = 104;
+pub const RTM_DELNEXTHOP: u16 = 105;
+pub const RTM_GETNEXTHOP: u16 = 106;
+
+pub const NHA_ID: u16 = 1;
+pub const NHA_GROUP: u16 = 2;
+pub const NHA_GROUP_TYPE: u16 = 3;
+pub const NHA_BLACKHOLE: u16 = 4;
+pub const NHA_OIF: u16 = 5;
+pub const NHA_GATEWAY: u16 = 6;
+pub const NHA_GROUPS: u16 = 9;
+
+pub const NEXTHOP_GRP_TYPE_MPATH: u16 = 0;
+pub const NEXTHOP_GRP_TYPE_RES: u16 = 1;
+
+const NEXTHOP_GRP_LEN: usize = 8;
+
+/// A member of a nexthop group, `struct nexthop_grp`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NexthopGroupMember {
+    pub id: u32,
+    /// The relative weight, starting at 1.
+    pub weight: u16,
+}
+
+impl NexthopGroupMember {
+    fn serialize(&self) -> Result<Vec<u8>> {
+        if self.weight == 0 {
+            bail!("nexthop group member {} has weight 0", self.id);
+        }
+
+        let mut buf = Vec::with_capacity(NEXTHOP_GRP_LEN);
+        buf.extend_from_slice(&self.id.to_ne_bytes());
+        buf.extend_from_slice(&(self.weight - 1).to_le_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        Ok(buf)
+    }
+
+    fn deserialize(buf: &[u8]) -> Self {
+        // The kernel stores `weight - 1`, split over a low and a high byte. A
+        // stored 0xffff is out of range for the kernel, so saturate instead of
+        // overflowing on it.
+        let weight = u16::from_le_bytes([buf[4], buf[5]]);
+
+        Self {
+            id: u32::from_ne_bytes(buf[..4].try_into().unwrap()),
+            weight: weight.saturating_add(1),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NexthopGroup {
+    pub id: u32,
+    pub group: Vec<NexthopGroupMember>,
+    /// Whether this is a resilient group (`NEXTHOP_GRP_TYPE_RES`), which
+    /// keeps flows on their nexthop when members are added or removed.
+    pub resilient: bool,
+}
+
+impl TryFrom<&[u8]> for NexthopGroup {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let header_len = NexthopMessage::default().len();
+        if buf.len() < header_len {
+            bail!("nhmsg too short: {} < {}", buf.len(), header_len);
+        }
+
+        let mut group = NexthopGroup::default();
+
+        for attr in RouteAttrs::from(&buf[header_len..]) {
+            match attr.header.rta_type {
+                NHA_ID => group.id = attr.payload.to_u32()?,
+                NHA_GROUP => {
+                    group.group = attr
+                        .payload
+                        .chunks_exact(NEXTHOP_GRP_LEN)
+                        .map(NexthopGroupMember::deserialize)
+                        .collect()
+                }
+                NHA_GROUP_TYPE => group.resilient = attr.payload.to_u16()? == NEXTHOP_GRP_TYPE_RES,
+                _ => {}
+            }
+        }
+
+        if group.group.is_empty() {
+            bail!("nexthop {} is not a group", group.id);
+        }
+
+        Ok(group)
+    }
+}
+
+/// A nexthop object: either a single nexthop, reached through `oif` and
+/// optionally `gateway`, a blackhole, or a group of other nexthop objects.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NexthopObject {
+    /// 0 lets the kernel pick a free id.
+    pub id: u32,
+    pub oif: Option<u32>,
+    pub gateway: Option<IpAddr>,
+    pub blackhole: bool,
+    pub group: Vec<NexthopGroupMember>,
+    pub resilient: bool,
+}
+
+impl NexthopObject {
+    /// The `nhmsg` header and attributes of `RTM_NEWNEXTHOP`.
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        // Groups must not have a family, single nexthops must have one.
+        let family = match self.gateway {
+            _ if !self.group.is_empty() => libc::AF_UNSPEC,
+            Some(IpAddr::V6(_)) => libc::AF_INET6,
+            _ => libc::AF_INET,
+        };
+
+        let msg = NexthopMessage {
+            family: family as u8,
+            protocol: libc::RTPROT_BOOT,
+            ..Default::default()
+        };
+
+        let mut buf = msg.serialize()?;
+
+        if self.id != 0 {
+            buf.extend(RouteAttr::new(NHA_ID, &self.id.to_ne_bytes()).serialize()?);
+        }
+
+        if !self.group.is_empty() {
+            let mut group = Vec::with_capacity(self.group.len() * NEXTHOP_GRP_LEN);
+            for member in &self.group {
+                group.extend(member.serialize()?);
+            }
+            buf.extend(RouteAttr::new(NHA_GROUP, &group).serialize()?);
+
+            if self.resilient {
+                buf.extend(
+                    RouteAttr::new(NHA_GROUP_TYPE, &NEXTHOP_GRP_TYPE_RES.to_ne_bytes())
+                        .serialize()?,
+                );
+            }
+
+            return Ok(buf);
+        }
+
+        if self.blackhole {
+            buf.extend(RouteAttr::new(NHA_BLACKHOLE, &[]).serialize()?);
+            return Ok(buf);
+        }
+
+        if let Some(oif) = self.oif {
+            buf.extend(RouteAttr::new(NHA_OIF, &oif.to_ne_bytes()).serialize()?);
+        }
+
+        if let Some(gateway) = self.gateway {
+            let gateway = match gateway {
+                IpAddr::V4(ip) => ip.octets().to_vec(),
+                IpAddr::V6(ip) => ip.octets().to_vec(),
+            };
+            buf.extend(RouteAttr::new(NHA_GATEWAY, &gateway).serialize()?);
+        }
+
+        Ok(buf)
+    }
+}
+
+impl TryFrom<&[u8]> for NexthopObject {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let header_len = NexthopMessage::default().len();
+        if buf.len() < header_len {
+            bail!("nhmsg too short: {} < {}", buf.len(), header_len);
+        }
+
+        let mut nh = NexthopObject::default();
+
+        for attr in RouteAttrs::from(&buf[header_len..]) {
+            match attr.header.rta_type {
+                NHA_ID => nh.id = attr.payload.to_u32()?,
+                NHA_OIF => nh.oif = Some(attr.payload.to_u32()?),
+                NHA_GATEWAY => nh.gateway = Some(vec_to_addr(&attr.payload)?),
+                NHA_BLACKHOLE => nh.blackhole = true,
+                NHA_GROUP => {
+                    nh.group = attr
+                        .payload
+                        .chunks_exact(NEXTHOP_GRP_LEN)
+                        .map(NexthopGroupMember::deserialize)
+                        .collect()
+                }
+                NHA_GROUP_TYPE => nh.resilient = attr.payload.to_u16()? == NEXTHOP_GRP_TYPE_RES,
+                _ => {}
+            }
+        }
+
+        Ok(nh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static NEWNEXTHOP_MSG: [u8; 44] = [
+        0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, // nhmsg: family = AF_UNSPEC, protocol = RTPROT_BOOT
+        0x08, 0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00, // id = 10
+        0x14, 0x00, 0x02, 0x00, // group L=20,T=2
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id = 1, weight = 1
+        0x02, 0x00, 0x00, 0x00, 0x02, 0x01, 0x00, 0x00, // id = 2, weight = 0x103
+        0x06, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, // type = NEXTHOP_GRP_TYPE_RES
+    ];
+
+    #[test]
+    fn test_nexthop_group_try_from() {
+        let group = NexthopGroup::try_from(&NEWNEXTHOP_MSG[..]).unwrap();
+
+        assert_eq!(group.id, 10);
+        assert_eq!(
+            group.group,
+            vec![
+                NexthopGroupMember { id: 1, weight: 1 },
+                NexthopGroupMember {
+                    id: 2,
+                    weight: 0x103
+                },
+            ]
+        );
+        assert!(group.resilient);
+
+        assert!(NexthopGroup::try_from(&NEWNEXTHOP_MSG[..16]).is_err());
+    }
+
+    #[test]
+    fn test_nexthop_group_member_max_weight() {
+        let member =
+            NexthopGroupMember::deserialize(&[0x01, 0x00, 0x00, 0x00, 0xff, 0xff, 0x00, 0x00]);
+
+        assert_eq!(member.weight, u16::MAX);
+    }
+
+    #[test]
+    fn test_nexthop_object_roundtrip() {
+        let group = NexthopObject {
+            id: 10,
+            group: vec![
+                NexthopGroupMember { id: 1, weight: 1 },
+                NexthopGroupMember {
+                    id: 2,
+                    weight: 0x103,
+                },
+            ],
+            resilient: true,
+            ..Default::default()
+        };
+
+        let buf = group.serialize().unwrap();
+        assert_eq!(&buf[4..], &NEWNEXTHOP_MSG[4..]);
+        assert_eq!(buf[0], libc::AF_UNSPEC as u8);
+        assert_eq!(NexthopObject::try_from(buf.as_slice()).unwrap(), group);
+
+        let nh = NexthopObject {
+            id: 1,
+            oif: Some(2),
+            gateway: Some("fe80::1".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let buf = nh.serialize().unwrap();
+        assert_eq!(buf[0], libc::AF_INET6 as u8);
+        assert_eq!(NexthopObject::try_from(buf.as_slice()).unwrap(), nh);
+
+        let zero_weight = NexthopObject {
+            group: vec![NexthopGroupMember { id: 1, weight: 0 }],
+            ..Default::default()
+        };
+        assert!(zero_weight.serialize().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{anyhow, Result};
+
+use super::{
+    message::{NfGenMessage, RouteAttrs},
+    NLA_TYPE_MASK,
+};
+
+pub const NFT_MSG_NEWTABLE: u16 = 0;
+pub const NFT_MSG_GETTABLE: u16 = 1;
+pub const NFT_MSG_GETCHAIN: u16 = 4;
+
+pub const NFTA_TABLE_NAME: u16 = 1;
+pub const NFTA_TABLE_FLAGS: u16 = 2;
+pub const NFTA_TABLE_USE: u16 = 3;
+pub const NFTA_TABLE_HANDLE: u16 = 4;
+
+pub const NFTA_CHAIN_TABLE: u16 = 1;
+pub const NFTA_CHAIN_HANDLE: u16 = 2;
+pub const NFTA_CHAIN_NAME: u16 = 3;
+pub const NFTA_CHAIN_HOOK: u16 = 4;
+pub const NFTA_CHAIN_POLICY: u16 = 5;
+pub const NFTA_CHAIN_USE: u16 = 6;
+pub const NFTA_CHAIN_TYPE: u16 = 7;
+
+pub const NFTA_HOOK_HOOKNUM: u16 = 1;
+pub const NFTA_HOOK_PRIORITY: u16 = 2;
+
+/// Builds the netlink message type of an nftables command.
+pub fn nft_msg_type(command: u16) -> u16 {
+    ((libc::NFNL_SUBSYS_NFTABLES as u16) << 8) | command
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NftTable {
+    pub family: u8,
+    pub name: String,
+    pub handle: u64,
+    pub flags: u32,
+    pub chains: u32,
+}
+
+impl TryFrom<&[u8]> for NftTable {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg: NfGenMessage = bincode::deserialize(buf)?;
+        let mut table = NftTable {
+            family: msg.family,
+            ..Default::default()
+        };
+
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("nftables message too short: {} bytes", buf.len()))?,
+        );
+
+        for attr in attrs {
+            match attr.header.rta_type & NLA_TYPE_MASK {
+                NFTA_TABLE_NAME => table.name = attr.payload.to_string()?,
+                NFTA_TABLE_FLAGS => table.flags = attr.payload.as_u32_be()?,
+                NFTA_TABLE_USE => table.chains = attr.payload.as_u32_be()?,
+                NFTA_TABLE_HANDLE => table.handle = attr.payload.as_u64_be()?,
+                _ => {}
+            }
+        }
+
+        if table.name.is_empty() {
+            return Err(anyhow!("nftables table without NFTA_TABLE_NAME"));
+        }
+
+        Ok(table)
+    }
+}
+
+/// The hook of a base chain. Regular chains that are only reached through
+/// jumps have none.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NftHook {
+    pub hooknum: u32,
+    pub priority: i32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NftChain {
+    pub family: u8,
+    pub table: String,
+    pub name: String,
+    pub handle: u64,
+    pub hook: Option<NftHook>,
+    pub policy: Option<u32>,
+    pub chain_type: Option<String>,
+    pub rules: u32,
+}
+
+impl TryFrom<&[u8]> for NftChain {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg: NfGenMessage = bincode::deserialize(buf)?;
+        let mut chain = NftChain {
+            family: msg.family,
+            ..Default::default()
+        };
+
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("nftables message too short: {} bytes", buf.len()))?,
+        );
+
+        for attr in attrs {
+            match attr.header.rta_type & NLA_TYPE_MASK {
+                NFTA_CHAIN_TABLE => chain.table = attr.payload.to_string()?,
+                NFTA_CHAIN_NAME => chain.name = attr.payload.to_string()?,
+                NFTA_CHAIN_HANDLE => chain.handle = attr.payload.as_u64_be()?,
+                NFTA_CHAIN_POLICY => chain.policy = Some(attr.payload.as_u32_be()?),
+                NFTA_CHAIN_USE => chain.rules = attr.payload.as_u32_be()?,
+                NFTA_CHAIN_TYPE => chain.chain_type = Some(attr.payload.to_string()?),
+                NFTA_CHAIN_HOOK => {
+                    let mut hook = NftHook::default();
+                    for a in RouteAttrs::from(attr.payload.as_slice()) {
+                        match a.header.rta_type & NLA_TYPE_MASK {
+                            NFTA_HOOK_HOOKNUM => hook.hooknum = a.payload.as_u32_be()?,
+                            NFTA_HOOK_PRIORITY => hook.priority = a.payload.as_u32_be()? as i32,
+                            _ => {}
+                        }
+                    }
+                    chain.hook = Some(hook);
+                }
+                _ => {}
+            }
+        }
+
+        if chain.name.is_empty() {
+            return Err(anyhow!("nftables chain without NFTA_CHAIN_NAME"));
+        }
+
+        Ok(chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static NEWTABLE_MSG: [u8; 44] = [
+        0x02, 0x00, 0x00, 0x0a, // nfgenmsg: family = NFPROTO_IPV4, res_id = 10 (be)
+        0x0b, 0x00, 0x01, 0x00, 0x66, 0x69, 0x6c, 0x74, 0x65, 0x72, 0x00, 0x00, // name "filter"
+        0x08, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, // flags 0
+        0x08, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x02, // use 2 (be)
+        0x0c, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // handle 1 (be)
+    ];
+
+    #[rustfmt::skip]
+    static NEWCHAIN_MSG: [u8; 96] = [
+        0x02, 0x00, 0x00, 0x0a, // nfgenmsg: family = NFPROTO_IPV4, res_id = 10 (be)
+        0x0b, 0x00, 0x01, 0x00, 0x66, 0x69, 0x6c, 0x74, 0x65, 0x72, 0x00, 0x00, // table "filter"
+        0x0c, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // handle 2 (be)
+        0x0a, 0x00, 0x03, 0x00, 0x69, 0x6e, 0x70, 0x75, 0x74, 0x00, 0x00, 0x00, // name "input"
+        0x14, 0x00, 0x04, 0x80, // hook L=20,T=4|NLA_F_NESTED
+        0x08, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, // hooknum = NF_INET_LOCAL_IN (be)
+        0x08, 0x00, 0x02, 0x00, 0xff, 0xff, 0xff, 0x9c, // priority = -100 (be)
+        0x08, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x01, // policy = NF_ACCEPT (be)
+        0x0b, 0x00, 0x07, 0x00, 0x66, 0x69, 0x6c, 0x74, 0x65, 0x72, 0x00, 0x00, // type "filter"
+        0x08, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x03, // use 3 (be)
+        0x08, 0x00, 0x63, 0x00, 0x00, 0x00, 0x00, 0x00, // unknown attribute
+    ];
+
+    #[test]
+    fn test_nft_table_try_from() {
+        let table = NftTable::try_from(&NEWTABLE_MSG[..]).unwrap();
+
+        assert_eq!(table.family, libc::NFPROTO_IPV4 as u8);
+        assert_eq!(table.name, "filter");
+        assert_eq!(table.handle, 1);
+        assert_eq!(table.chains, 2);
+
+        assert!(NftTable::try_from(&NEWTABLE_MSG[..4]).is_err());
+        assert!(NftTable::try_from(&NEWTABLE_MSG[..2]).is_err());
+
+        // NFTA_TABLE_HANDLE with a 4 byte payload.
+        let mut short_handle = NEWTABLE_MSG;
+        short_handle[32] = 0x08;
+        assert!(NftTable::try_from(&short_handle[..]).is_err());
+    }
+
+    #[test]
+    fn test_nft_chain_try_from() {
+        let chain = NftChain::try_from(&NEWCHAIN_MSG[..]).unwrap();
+
+        assert_eq!(chain.table, "filter");
+        assert_eq!(chain.name, "input");
+        assert_eq!(chain.handle, 2);
+        assert_eq!(
+            chain.hook,
+            Some(NftHook {
+                hooknum: 1,
+                priority: -100
+            })
+        );
+        assert_eq!(chain.policy, Some(1));
+        assert_eq!(chain.chain_type.as_deref(), Some("filter"));
+        assert_eq!(chain.rules, 3);
+
+        assert!(NftChain::try_from(&NEWCHAIN_MSG[..2]).is_err());
+
+        // NFTA_HOOK_HOOKNUM with a 2 byte payload.
+        let mut short_hooknum = NEWCHAIN_MSG;
+        short_hooknum[44] = 0x06;
+        assert!(NftChain::try_from(&short_hooknum[..]).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{anyhow, Result};
+
+use super::{message::RouteAttrs, NLA_TYPE_MASK};
+
+pub const NL80211_GENL_NAME: &str = "nl80211";
+pub const NL80211_GENL_VERSION: u8 = 1;
+
+pub const NL80211_CMD_GET_INTERFACE: u8 = 5;
+pub const NL80211_CMD_GET_SCAN: u8 = 32;
+
+pub const NL80211_ATTR_IFINDEX: u16 = 3;
+pub const NL80211_ATTR_IFNAME: u16 = 4;
+pub const NL80211_ATTR_IFTYPE: u16 = 5;
+pub const NL80211_ATTR_MAC: u16 = 6;
+pub const NL80211_ATTR_WIPHY_FREQ: u16 = 38;
+pub const NL80211_ATTR_BSS: u16 = 47;
+pub const NL80211_ATTR_SSID: u16 = 52;
+pub const NL80211_ATTR_WIPHY_TX_POWER_LEVEL: u16 = 98;
+
+pub const NL80211_BSS_BSSID: u16 = 1;
+pub const NL80211_BSS_STATUS: u16 = 9;
+
+pub const NL80211_BSS_STATUS_ASSOCIATED: u32 = 1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceType {
+    #[default]
+    Unspecified,
+    Adhoc,
+    Station,
+    Ap,
+    ApVlan,
+    Wds,
+    Monitor,
+    MeshPoint,
+    P2pClient,
+    P2pGo,
+    P2pDevice,
+    Ocb,
+    Nan,
+    Other(u32),
+}
+
+impl From<u32> for InterfaceType {
+    fn from(val: u32) -> Self {
+        match val {
+            0 => Self::Unspecified,
+            1 => Self::Adhoc,
+            2 => Self::Station,
+            3 => Self::Ap,
+            4 => Self::ApVlan,
+            5 => Self::Wds,
+            6 => Self::Monitor,
+            7 => Self::MeshPoint,
+            8 => Self::P2pClient,
+            9 => Self::P2pGo,
+            10 => Self::P2pDevice,
+            11 => Self::Ocb,
+            12 => Self::Nan,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// A wireless interface as reported by `NL80211_CMD_GET_INTERFACE`.
+/// `tx_power` is in mBm (1/100 dBm) and `frequency` in MHz.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Nl80211Interface {
+    pub index: u32,
+    pub name: String,
+    pub mac: Vec<u8>,
+    pub iftype: InterfaceType,
+    pub ssid: Option<String>,
+    pub bssid: Option<Vec<u8>>,
+    pub frequency: Option<u32>,
+    pub tx_power: Option<u32>,
+}
+
+impl TryFrom<&[u8]> for Nl80211Interface {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let attrs = RouteAttrs::from(
+            buf.get(4..)
+                .ok_or_else(|| anyhow!("nl80211 message too short: {} bytes", buf.len()))?,
+        );
+        let mut iface = Nl80211Interface::default();
+
+        for attr in attrs {
+            match attr.header.rta_type {
+                NL80211_ATTR_IFINDEX => iface.index = attr.payload.to_u32()?,
+                NL80211_ATTR_IFNAME => iface.name = attr.payload.to_string()?,
+                NL80211_ATTR_IFTYPE => iface.iftype = attr.payload.to_u32()?.into(),
+                NL80211_ATTR_MAC => iface.mac = attr.payload.to_vec(),
+                NL80211_ATTR_SSID => {
+                    iface.ssid = Some(String::from_utf8_lossy(&attr.payload).into_owned())
+                }
+                NL80211_ATTR_WIPHY_FREQ => iface.frequency = Some(attr.payload.to_u32()?),
+                NL80211_ATTR_WIPHY_TX_POWER_LEVEL => iface.tx_power = Some(attr.payload.to_u32()?),
+                _ => {}
+            }
+        }
+
+        Ok(iface)
+    }
+}
+
+/// Returns the BSSID carried by an `NL80211_CMD_GET_SCAN` reply if the
+/// interface is associated with that BSS.
+pub fn associated_bssid(buf: &[u8]) -> Result<Option<Vec<u8>>> {
+    let attrs = RouteAttrs::from(
+        buf.get(4..)
+            .ok_or_else(|| anyhow!("nl80211 message too short: {} bytes", buf.len()))?,
+    );
+
+    for attr in attrs {
+        if attr.header.rta_type & NLA_TYPE_MASK != NL80211_ATTR_BSS {
+            continue;
+        }
+
+        let mut bssid = None;
+        let mut associated = false;
+
+        for a in RouteAttrs::from(attr.payload.as_slice()) {
+            match a.header.rta_type {
+                NL80211_BSS_BSSID => bssid = Some(a.payload.to_vec()),
+                NL80211_BSS_STATUS => {
+                    associated = a.payload.to_u32()? == NL80211_BSS_STATUS_ASSOCIATED
+                }
+                _ => {}
+            }
+        }
+
+        if associated {
+            return Ok(bssid);
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rustfmt::skip]
+    static GET_INTERFACE_MSG: [u8; 68] = [
+        0x07, 0x01, 0x00, 0x00, // genlmsghdr: cmd = NEW_INTERFACE, version = 1
+        0x08, 0x00, 0x03, 0x00, 0x04, 0x00, 0x00, 0x00, // ifindex L=8,T=3,V=4
+        0x0a, 0x00, 0x04, 0x00, 0x77, 0x6c, 0x61, 0x6e, 0x30, 0x00, 0x00, 0x00, // ifname "wlan0"
+        0x08, 0x00, 0x05, 0x00, 0x02, 0x00, 0x00, 0x00, // iftype L=8,T=5,V=station
+        0x0a, 0x00, 0x06, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, // mac
+        0x08, 0x00, 0x34, 0x00, 0x72, 0x73, 0x6c, 0x6e, // ssid L=8,T=52,V="rsln"
+        0x08, 0x00, 0x26, 0x00, 0x85, 0x09, 0x00, 0x00, // freq L=8,T=38,V=2437
+        0x08, 0x00, 0x62, 0x00, 0xd0, 0x07, 0x00, 0x00, // tx power L=8,T=98,V=2000
+    ];
+
+    #[rustfmt::skip]
+    static GET_SCAN_MSG: [u8; 28] = [
+        0x22, 0x01, 0x00, 0x00, // genlmsghdr: cmd = NEW_SCAN_RESULTS, version = 1
+        0x18, 0x00, 0x2f, 0x80, // bss L=24,T=47|NLA_F_NESTED
+        0x0a, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, // bssid
+        0x08, 0x00, 0x09, 0x00, 0x01, 0x00, 0x00, 0x00, // status L=8,T=9,V=associated
+    ];
+
+    #[test]
+    fn test_nl80211_interface_try_from() {
+        let iface = Nl80211Interface::try_from(&GET_INTERFACE_MSG[..]).unwrap();
+
+        assert_eq!(iface.index, 4);
+        assert_eq!(iface.name, "wlan0");
+        assert_eq!(iface.iftype, InterfaceType::Station);
+        assert_eq!(iface.mac, vec![0x02, 0, 0, 0, 0, 0x01]);
+        assert_eq!(iface.ssid.as_deref(), Some("rsln"));
+        assert_eq!(iface.bssid, None);
+        assert_eq!(iface.frequency, Some(2437));
+        assert_eq!(iface.tx_power, Some(2000));
+
+        assert!(Nl80211Interface::try_from(&GET_INTERFACE_MSG[..2]).is_err());
+    }
+
+    #[test]
+    fn test_associated_bssid() {
+        assert_eq!(
+            associated_bssid(&GET_SCAN_MSG[..]).unwrap(),
+            Some(vec![0x02, 0, 0, 0, 0, 0x02])
+        );
+        assert_eq!(associated_bssid(&GET_SCAN_MSG[..4]).unwrap(), None);
+        assert!(associated_bssid(&GET_SCAN_MSG[..2]).is_err());
+    }
+}
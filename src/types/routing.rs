@@ -1,14 +1,14 @@
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use derive_builder::Builder;
 use ipnet::IpNet;
 
-use crate::RTA_VIA;
+use crate::{align_of, RTA_VIA};
 
 use super::{
     addr::AddrFamily,
-    message::{Attribute, RouteAttrs, RouteMessage},
+    message::{Attribute, RouteAttr, RouteAttrs, RouteMessage},
     vec_to_addr,
 };
 
@@ -19,6 +19,23 @@ pub enum RtCmd {
     Delete,
 }
 
+/// The `rtm_scope`/`ifa_scope` of a route or an address, i.e. how far away
+/// its destination is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteScope {
+    Universe = 0,
+    Site = 200,
+    Link = 253,
+    Host = 254,
+    Nowhere = 255,
+}
+
+impl From<RouteScope> for u8 {
+    fn from(val: RouteScope) -> Self {
+        val as u8
+    }
+}
+
 #[derive(Default, Builder)]
 #[builder(default)]
 pub struct Routing {
@@ -26,6 +43,7 @@ pub struct Routing {
     pub iif_index: i32,
     pub family: u8,
     pub dst: Option<IpNet>,
+    /// The preferred source address, carried in `RTA_PREFSRC`.
     pub src: Option<IpAddr>,
     pub gw: Option<IpAddr>,
     pub tos: u8,
@@ -35,12 +53,110 @@ pub struct Routing {
     pub rtm_type: u8,
     pub via: Option<Via>,
     pub mtu: Option<u32>,
+    /// The metric, carried in `RTA_PRIORITY`. Lower values are preferred.
+    pub priority: Option<u32>,
     pub flags: u32,
+    /// The nexthops of a multipath route, carried in `RTA_MULTIPATH`
+    /// instead of `gw` and `oif_index`.
+    pub nexthops: Vec<Nexthop>,
 }
 
-impl From<&[u8]> for Routing {
-    fn from(buf: &[u8]) -> Self {
-        let rt_msg: RouteMessage = bincode::deserialize(buf).unwrap();
+/// A nexthop of a multipath route, i.e. an `rtnexthop`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nexthop {
+    /// The gateway, or the unspecified address for a nexthop without one.
+    pub gateway: IpAddr,
+    pub ifindex: u32,
+    /// The share of the traffic relative to the other nexthops, from 1 to
+    /// 255. 0 is taken as 1.
+    pub weight: u8,
+}
+
+impl Nexthop {
+    const RTNH_LEN: usize = 8;
+
+    /// The `rtnexthop` followed by its `RTA_GATEWAY`, as an entry of
+    /// `RTA_MULTIPATH`. A nexthop with the unspecified gateway is a device
+    /// nexthop and is sent without `RTA_GATEWAY`.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let gateway = match self.gateway {
+            gw if gw.is_unspecified() => vec![],
+            IpAddr::V4(ip) => {
+                RouteAttr::new(libc::RTA_GATEWAY, &ip.octets()).serialize_aligned()?
+            }
+            IpAddr::V6(ip) => {
+                RouteAttr::new(libc::RTA_GATEWAY, &ip.octets()).serialize_aligned()?
+            }
+        };
+
+        let len = (Self::RTNH_LEN + gateway.len()) as u16;
+
+        let mut buf = Vec::with_capacity(len as usize);
+        buf.extend_from_slice(&len.to_ne_bytes());
+        buf.push(0); // rtnh_flags
+        buf.push(self.weight.saturating_sub(1)); // rtnh_hops
+        buf.extend_from_slice(&self.ifindex.to_ne_bytes());
+        buf.extend(gateway);
+
+        Ok(buf)
+    }
+
+    /// Parses the entries of an `RTA_MULTIPATH` of a route of `family`.
+    fn decode_all(buf: &[u8], family: u8) -> Result<Vec<Self>> {
+        let mut nexthops = vec![];
+        let mut offset = 0;
+
+        while offset + Self::RTNH_LEN <= buf.len() {
+            let len = u16::from_ne_bytes([buf[offset], buf[offset + 1]]) as usize;
+
+            if len < Self::RTNH_LEN || offset + len > buf.len() {
+                bail!("invalid rtnexthop length: {}", len);
+            }
+
+            let hops = buf[offset + 3];
+            let ifindex = u32::from_ne_bytes(buf[offset + 4..offset + 8].try_into()?);
+
+            let mut gateway = match family as i32 {
+                libc::AF_INET6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                _ => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            };
+
+            for attr in RouteAttrs::from(&buf[offset + Self::RTNH_LEN..offset + len]) {
+                match attr.header.rta_type {
+                    libc::RTA_GATEWAY => gateway = vec_to_addr(&attr.payload)?,
+                    RTA_VIA if attr.payload.len() > 2 => gateway = vec_to_addr(&attr.payload[2..])?,
+                    _ => {}
+                }
+            }
+
+            nexthops.push(Self {
+                gateway,
+                ifindex,
+                weight: hops.saturating_add(1),
+            });
+
+            offset += align_of(len, 4);
+        }
+
+        Ok(nexthops)
+    }
+}
+
+impl Routing {
+    /// The flags of an `RTM_NEWROUTE` that replaces a matching route in
+    /// place, or adds the route if there is none.
+    pub const FLAG_REPLACE: i32 = libc::NLM_F_CREATE | libc::NLM_F_REPLACE;
+
+    /// The flags of an `RTM_NEWROUTE` that adds a route next to the ones
+    /// to the same destination, see `RouteHandle::append`.
+    pub const FLAG_APPEND: i32 = libc::NLM_F_CREATE | libc::NLM_F_APPEND;
+}
+
+impl TryFrom<&[u8]> for Routing {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let rt_msg = RouteMessage::deserialize(buf)?;
         let rt_attrs = RouteAttrs::from(&buf[rt_msg.len()..]);
 
         let mut routing = Self {
@@ -56,35 +172,42 @@ impl From<&[u8]> for Routing {
         for attr in rt_attrs {
             match attr.header.rta_type {
                 libc::RTA_GATEWAY => {
-                    routing.gw = Some(vec_to_addr(&attr.payload).unwrap());
+                    routing.gw = Some(vec_to_addr(&attr.payload)?);
                 }
                 libc::RTA_PREFSRC => {
-                    routing.src = Some(vec_to_addr(&attr.payload).unwrap());
+                    routing.src = Some(vec_to_addr(&attr.payload)?);
                 }
                 libc::RTA_DST => {
-                    routing.dst = Some(
-                        IpNet::new(vec_to_addr(&attr.payload).unwrap(), rt_msg.dst_len).unwrap(),
-                    );
+                    routing.dst = Some(IpNet::new(vec_to_addr(&attr.payload)?, rt_msg.dst_len)?);
                 }
                 libc::RTA_OIF => {
-                    routing.oif_index = i32::from_ne_bytes(attr.payload[..4].try_into().unwrap());
+                    routing.oif_index = attr.payload.as_u32()? as i32;
                 }
                 libc::RTA_IIF => {
-                    routing.iif_index = i32::from_ne_bytes(attr.payload[..4].try_into().unwrap());
+                    routing.iif_index = attr.payload.as_u32()? as i32;
+                }
+                libc::RTA_PRIORITY => {
+                    routing.priority = Some(attr.payload.as_u32()?);
                 }
                 libc::RTA_TABLE => {
-                    routing.table = u8::from_ne_bytes(attr.payload[..1].try_into().unwrap());
+                    routing.table = attr.payload.as_u32()? as u8;
+                }
+                libc::RTA_MULTIPATH => {
+                    routing.nexthops = Nexthop::decode_all(&attr.payload, rt_msg.family)?;
                 }
                 RTA_VIA => {
-                    let family = u16::from_ne_bytes(attr.payload[..2].try_into().unwrap());
-                    let addr = vec_to_addr(&attr.payload[2..]).unwrap();
+                    if attr.payload.len() < 2 {
+                        bail!("RTA_VIA too short: {}", attr.payload.len());
+                    }
+                    let family = u16::from_ne_bytes([attr.payload[0], attr.payload[1]]);
+                    let addr = vec_to_addr(&attr.payload[2..])?;
                     routing.via = Some(Via { family, addr });
                 }
                 _ => {}
             }
         }
 
-        routing
+        Ok(routing)
     }
 }
 
@@ -149,11 +272,19 @@ mod tests {
             payload: Payload::from(&[192, 168, 1, 1][..]),
             attributes: None,
         });
+        rt_attrs.push(RouteAttr {
+            header: RouteAttrHeader {
+                rta_type: libc::RTA_PREFSRC,
+                rta_len: 8,
+            },
+            payload: Payload::from(&[10, 0, 0, 1][..]),
+            attributes: None,
+        });
 
         let mut buf = RouteMessage::serialize(&rt_msg).unwrap();
         buf.extend_from_slice(RouteAttrs::serialize(&rt_attrs).unwrap().as_slice());
 
-        let routing = Routing::from(&buf[..]);
+        let routing = Routing::try_from(&buf[..]).unwrap();
 
         assert_eq!(routing.family, rt_msg.family);
         assert_eq!(routing.tos, rt_msg.tos);
@@ -165,5 +296,88 @@ mod tests {
             routing.dst,
             Some(IpNet::V4("192.168.1.1/32".parse().unwrap()))
         );
+        assert_eq!(routing.src, Some("10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_from_bytes_rejects_truncated() {
+        let rt_msg = RouteMessage {
+            family: 2,
+            dst_len: 32,
+            ..Default::default()
+        };
+        let mut buf = RouteMessage::serialize(&rt_msg).unwrap();
+
+        assert!(Routing::try_from(&buf[..buf.len() - 1]).is_err());
+
+        // RTA_OIF with a 2 byte payload.
+        buf.extend_from_slice(&[0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        assert!(Routing::try_from(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_multipath() {
+        let nexthops = vec![
+            Nexthop {
+                gateway: "10.1.0.2".parse().unwrap(),
+                ifindex: 2,
+                weight: 1,
+            },
+            Nexthop {
+                gateway: "10.1.0.3".parse().unwrap(),
+                ifindex: 3,
+                weight: 3,
+            },
+        ];
+
+        let mut multipath = vec![];
+        for nh in &nexthops {
+            multipath.extend(nh.encode().unwrap());
+        }
+
+        // rtnh_len = 16, rtnh_flags = 0, rtnh_hops = 2, rtnh_ifindex = 3
+        assert_eq!(
+            multipath[16..24],
+            [0x10, 0x00, 0x00, 0x02, 0x03, 0x00, 0x00, 0x00]
+        );
+
+        let rt_msg = RouteMessage {
+            family: libc::AF_INET as u8,
+            dst_len: 24,
+            ..Default::default()
+        };
+        let mut buf = RouteMessage::serialize(&rt_msg).unwrap();
+        buf.extend(
+            RouteAttr::new(libc::RTA_MULTIPATH, &multipath)
+                .serialize()
+                .unwrap(),
+        );
+
+        let routing = Routing::try_from(&buf[..]).unwrap();
+        assert_eq!(routing.nexthops, nexthops);
+
+        // An rtnexthop that claims more than the attribute holds.
+        multipath[16] = 0x20;
+        let mut buf = RouteMessage::serialize(&rt_msg).unwrap();
+        buf.extend(
+            RouteAttr::new(libc::RTA_MULTIPATH, &multipath)
+                .serialize()
+                .unwrap(),
+        );
+        assert!(Routing::try_from(&buf[..]).is_err());
+
+        let device = Nexthop {
+            gateway: Ipv4Addr::UNSPECIFIED.into(),
+            ifindex: 2,
+            weight: 1,
+        };
+
+        // rtnh_len = 8, rtnh_flags = 0, rtnh_hops = 0, rtnh_ifindex = 2
+        let multipath = device.encode().unwrap();
+        assert_eq!(multipath, [0x08, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            Nexthop::decode_all(&multipath, libc::AF_INET as u8).unwrap(),
+            [device]
+        );
     }
 }
@@ -0,0 +1,323 @@
+use anyhow::{bail, Result};
+use derive_builder::Builder;
+use ipnet::IpNet;
+
+use super::{
+    message::{Attribute, RouteAttr, RouteAttrs, RuleMessage},
+    AF_INET, AF_INET6,
+};
+
+pub const FRA_DST: u16 = 1;
+pub const FRA_SRC: u16 = 2;
+pub const FRA_IIFNAME: u16 = 3;
+pub const FRA_GOTO: u16 = 4;
+pub const FRA_PRIORITY: u16 = 6;
+pub const FRA_FWMARK: u16 = 10;
+pub const FRA_SUPPRESS_PREFIXLEN: u16 = 14;
+pub const FRA_TABLE: u16 = 15;
+pub const FRA_FWMASK: u16 = 16;
+pub const FRA_OIFNAME: u16 = 17;
+pub const FRA_L3MDEV: u16 = 19;
+pub const FRA_PROTOCOL: u16 = 21;
+pub const FRA_IP_PROTO: u16 = 22;
+
+pub const FR_ACT_TO_TBL: u8 = 1;
+pub const FR_ACT_GOTO: u8 = 2;
+
+pub const FIB_RULE_INVERT: u32 = 0x2;
+
+const RT_TABLE_UNSPEC: u8 = 0;
+
+/// A policy routing rule, i.e. an entry of `ip rule`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Builder)]
+#[builder(default)]
+pub struct Rule {
+    /// `AF_INET` or `AF_INET6`. 0 takes the family of `src` or `dst`, or
+    /// `AF_INET` without them.
+    pub family: u8,
+    /// `None` lets the kernel pick one below the lowest existing priority.
+    pub priority: Option<u32>,
+    /// The table to look up, or 0 for none, e.g. with `l3mdev` or `goto`.
+    pub table: u32,
+    pub src: Option<IpNet>,
+    pub dst: Option<IpNet>,
+    pub tos: u8,
+    pub mark: Option<u32>,
+    pub mask: Option<u32>,
+    pub iif_name: Option<String>,
+    pub oif_name: Option<String>,
+    /// The priority of the rule to continue with, for `FR_ACT_GOTO`.
+    pub goto: Option<u32>,
+    pub suppress_prefixlen: Option<u32>,
+    pub invert: bool,
+    /// Looks up the table of the L3 master device, i.e. the VRF, of the
+    /// interface instead of `table`, so a single rule serves every VRF.
+    pub l3mdev: bool,
+    /// The `FR_ACT_*` action. 0 picks `FR_ACT_GOTO` with `goto` and
+    /// `FR_ACT_TO_TBL` otherwise.
+    pub action: u8,
+    /// The `RTPROT_*` value of whoever installed the rule.
+    pub protocol: u8,
+    /// The L4 protocol to match, e.g. `IPPROTO_TCP`, or 0 for any.
+    pub ip_proto: u8,
+}
+
+impl Rule {
+    /// Parses an `RTM_NEWRULE` payload, i.e. a `fib_rule_hdr` and its
+    /// attributes.
+    pub fn from_netlink_message(buf: &[u8]) -> Result<Self> {
+        let msg = RuleMessage::deserialize(buf)?;
+
+        let mut rule = Self {
+            family: msg.family,
+            table: msg.table as u32,
+            tos: msg.tos,
+            invert: msg.flags & FIB_RULE_INVERT != 0,
+            action: msg.action,
+            ..Default::default()
+        };
+
+        for attr in RouteAttrs::from(&buf[msg.len()..]) {
+            match attr.header.rta_type {
+                FRA_DST => {
+                    rule.dst = Some(IpNet::new(attr.payload.as_ip(msg.family)?, msg.dst_len)?);
+                }
+                FRA_SRC => {
+                    rule.src = Some(IpNet::new(attr.payload.as_ip(msg.family)?, msg.src_len)?);
+                }
+                FRA_IIFNAME => rule.iif_name = Some(attr.payload.as_string()?),
+                FRA_OIFNAME => rule.oif_name = Some(attr.payload.as_string()?),
+                FRA_GOTO => rule.goto = Some(attr.payload.as_u32()?),
+                FRA_PRIORITY => rule.priority = Some(attr.payload.as_u32()?),
+                FRA_FWMARK => rule.mark = Some(attr.payload.as_u32()?),
+                FRA_FWMASK => rule.mask = Some(attr.payload.as_u32()?),
+                FRA_SUPPRESS_PREFIXLEN => rule.suppress_prefixlen = Some(attr.payload.as_u32()?),
+                FRA_TABLE => rule.table = attr.payload.as_u32()?,
+                FRA_L3MDEV => rule.l3mdev = attr.payload.as_u8()? != 0,
+                FRA_PROTOCOL => rule.protocol = attr.payload.as_u8()?,
+                FRA_IP_PROTO => rule.ip_proto = attr.payload.as_u8()?,
+                _ => {}
+            }
+        }
+
+        Ok(rule)
+    }
+
+    /// Matches only packets of the L4 protocol `proto`, e.g. `IPPROTO_UDP`.
+    pub fn with_l4_proto(mut self, proto: u8) -> Self {
+        self.ip_proto = proto;
+        self
+    }
+
+    /// The `fib_rule_hdr` and attributes of `RTM_NEWRULE` and `RTM_DELRULE`.
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let action = match self.action {
+            0 if self.goto.is_some() => FR_ACT_GOTO,
+            0 => FR_ACT_TO_TBL,
+            action => action,
+        };
+
+        let msg = RuleMessage {
+            family: self.family()?,
+            dst_len: self.dst.map_or(0, |dst| dst.prefix_len()),
+            src_len: self.src.map_or(0, |src| src.prefix_len()),
+            tos: self.tos,
+            table: u8::try_from(self.table).unwrap_or(RT_TABLE_UNSPEC),
+            action,
+            flags: if self.invert { FIB_RULE_INVERT } else { 0 },
+            ..Default::default()
+        };
+
+        let mut attrs = vec![];
+
+        if let Some(dst) = self.dst {
+            attrs.push(RouteAttr::ip(FRA_DST, dst.addr()));
+        }
+
+        if let Some(src) = self.src {
+            attrs.push(RouteAttr::ip(FRA_SRC, src.addr()));
+        }
+
+        if let Some(priority) = self.priority {
+            attrs.push(RouteAttr::u32(FRA_PRIORITY, priority));
+        }
+
+        if self.table > 0 {
+            attrs.push(RouteAttr::u32(FRA_TABLE, self.table));
+        }
+
+        if let Some(mark) = self.mark {
+            attrs.push(RouteAttr::u32(FRA_FWMARK, mark));
+        }
+
+        if let Some(mask) = self.mask {
+            attrs.push(RouteAttr::u32(FRA_FWMASK, mask));
+        }
+
+        if let Some(iif_name) = &self.iif_name {
+            attrs.push(RouteAttr::string(FRA_IIFNAME, iif_name));
+        }
+
+        if let Some(oif_name) = &self.oif_name {
+            attrs.push(RouteAttr::string(FRA_OIFNAME, oif_name));
+        }
+
+        if let Some(goto) = self.goto {
+            attrs.push(RouteAttr::u32(FRA_GOTO, goto));
+        }
+
+        if let Some(suppress_prefixlen) = self.suppress_prefixlen {
+            attrs.push(RouteAttr::u32(FRA_SUPPRESS_PREFIXLEN, suppress_prefixlen));
+        }
+
+        if self.l3mdev {
+            attrs.push(RouteAttr::u8(FRA_L3MDEV, 1));
+        }
+
+        if self.protocol > 0 {
+            attrs.push(RouteAttr::u8(FRA_PROTOCOL, self.protocol));
+        }
+
+        // A single byte, the kernel rejects a rule with a wider one.
+        if self.ip_proto > 0 {
+            attrs.push(RouteAttr::u8(FRA_IP_PROTO, self.ip_proto));
+        }
+
+        let mut buf = msg.serialize()?;
+        for attr in attrs {
+            buf.extend(attr.serialize_aligned()?);
+        }
+
+        Ok(buf)
+    }
+
+    fn family(&self) -> Result<u8> {
+        let family_of = |net: &IpNet| match net {
+            IpNet::V4(_) => AF_INET,
+            IpNet::V6(_) => AF_INET6,
+        };
+
+        let mut family = self.family;
+
+        for net in self.src.iter().chain(self.dst.iter()) {
+            if family == 0 {
+                family = family_of(net);
+            } else if family != family_of(net) {
+                bail!("src and dst address family mismatch");
+            }
+        }
+
+        Ok(if family == 0 { AF_INET } else { family })
+    }
+}
+
+impl TryFrom<&[u8]> for Rule {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        Self::from_netlink_message(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_roundtrip() {
+        let rule = RuleBuilder::default()
+            .priority(Some(100))
+            .table(1000)
+            .src(Some("10.0.0.0/8".parse().unwrap()))
+            .mark(Some(0x10))
+            .mask(Some(0xff))
+            .iif_name(Some("eth0".to_string()))
+            .build()
+            .unwrap();
+
+        let buf = rule.serialize().unwrap();
+        let parsed = Rule::from_netlink_message(&buf).unwrap();
+
+        assert_eq!(
+            parsed,
+            Rule {
+                family: AF_INET,
+                action: FR_ACT_TO_TBL,
+                ..rule
+            }
+        );
+    }
+
+    #[test]
+    fn test_rule_l3mdev() {
+        let rule = Rule {
+            priority: Some(1000),
+            l3mdev: true,
+            ..Default::default()
+        };
+
+        let buf = rule.serialize().unwrap();
+        let msg = RuleMessage::deserialize(&buf).unwrap();
+
+        assert_eq!(msg.table, RT_TABLE_UNSPEC);
+        assert_eq!(msg.action, FR_ACT_TO_TBL);
+        // FRA_L3MDEV is a u8, padded to 4 bytes.
+        assert_eq!(buf[buf.len() - 8..], [5, 0, 19, 0, 1, 0, 0, 0]);
+
+        let parsed = Rule::from_netlink_message(&buf).unwrap();
+        assert!(parsed.l3mdev);
+        assert_eq!(parsed.table, 0);
+    }
+
+    #[test]
+    fn test_rule_fwmark_and_fwmask() {
+        let rule = Rule {
+            mark: Some(0x100),
+            mask: Some(0xff00),
+            ..Default::default()
+        };
+
+        let buf = rule.serialize().unwrap();
+        let attrs: Vec<_> = RouteAttrs::from(&buf[RuleMessage::default().len()..])
+            .into_iter()
+            .map(|attr| (attr.header.rta_type, attr.payload.as_u32().unwrap()))
+            .collect();
+
+        // The mask is FRA_FWMASK (16), not a second FRA_FWMARK, nor
+        // FRA_FLOW (11) that follows FRA_FWMARK in the kernel's enum.
+        assert_eq!(attrs, vec![(FRA_FWMARK, 0x100), (FRA_FWMASK, 0xff00)]);
+        assert_eq!(FRA_FWMASK, 16);
+
+        let parsed = Rule::from_netlink_message(&buf).unwrap();
+        assert_eq!(parsed.mark, Some(0x100));
+        assert_eq!(parsed.mask, Some(0xff00));
+    }
+
+    #[test]
+    fn test_rule_ip_proto() {
+        let rule = Rule {
+            priority: Some(100),
+            table: 10,
+            ..Default::default()
+        }
+        .with_l4_proto(6);
+
+        let buf = rule.serialize().unwrap();
+        // FRA_IP_PROTO is a u8, padded to 4 bytes.
+        assert_eq!(buf[buf.len() - 8..], [5, 0, 22, 0, 6, 0, 0, 0]);
+
+        let parsed = Rule::from_netlink_message(&buf).unwrap();
+        assert_eq!(parsed.ip_proto, 6);
+    }
+
+    #[test]
+    fn test_rule_family_mismatch() {
+        let rule = Rule {
+            src: Some("10.0.0.0/8".parse().unwrap()),
+            dst: Some("fd00::/64".parse().unwrap()),
+            ..Default::default()
+        };
+
+        assert!(rule.serialize().is_err());
+    }
+}
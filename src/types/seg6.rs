@@ -0,0 +1,62 @@
+use std::net::Ipv4Addr;
+
+use super::{message::RouteAttr, NLA_F_NESTED};
+
+pub const RTA_ENCAP_TYPE: u16 = 21;
+pub const RTA_ENCAP: u16 = 22;
+
+pub const LWTUNNEL_ENCAP_SEG6_LOCAL: u16 = 7;
+
+pub const SEG6_LOCAL_ACTION: u16 = 1;
+pub const SEG6_LOCAL_SRH: u16 = 2;
+pub const SEG6_LOCAL_TABLE: u16 = 3;
+pub const SEG6_LOCAL_NH4: u16 = 4;
+pub const SEG6_LOCAL_NH6: u16 = 5;
+pub const SEG6_LOCAL_IIF: u16 = 6;
+pub const SEG6_LOCAL_OIF: u16 = 7;
+
+pub const SEG6_LOCAL_ACTION_END: u32 = 1;
+pub const SEG6_LOCAL_ACTION_END_X: u32 = 2;
+pub const SEG6_LOCAL_ACTION_END_T: u32 = 3;
+pub const SEG6_LOCAL_ACTION_END_DX2: u32 = 4;
+pub const SEG6_LOCAL_ACTION_END_DX6: u32 = 5;
+pub const SEG6_LOCAL_ACTION_END_DX4: u32 = 6;
+pub const SEG6_LOCAL_ACTION_END_DT6: u32 = 7;
+pub const SEG6_LOCAL_ACTION_END_DT4: u32 = 8;
+
+/// Builds the nested `RTA_ENCAP` of a `seg6local` End.DX4 function, which
+/// decapsulates the IPv4 packet carried in an SRv6 packet and forwards it to
+/// the IPv4 next hop `nh4`. It goes along with `RTA_ENCAP_TYPE` set to
+/// `LWTUNNEL_ENCAP_SEG6_LOCAL`.
+pub fn end_dx4_encap(nh4: Ipv4Addr) -> RouteAttr {
+    let mut encap = RouteAttr::new(RTA_ENCAP | NLA_F_NESTED, &[]);
+
+    encap.add(SEG6_LOCAL_ACTION, &SEG6_LOCAL_ACTION_END_DX4.to_ne_bytes());
+    encap.add(SEG6_LOCAL_NH4, &nh4.octets());
+
+    encap
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::message::Attribute;
+
+    use super::*;
+
+    #[rustfmt::skip]
+    static END_DX4_ENCAP: [u8; 20] = [
+        0x14, 0x00, 0x16, 0x80, // encap L=20,T=22|NLA_F_NESTED
+        0x08, 0x00, 0x01, 0x00, 0x06, 0x00, 0x00, 0x00, // action = End.DX4
+        0x08, 0x00, 0x04, 0x00, 0x0a, 0x00, 0x00, 0x01, // nh4 = 10.0.0.1
+    ];
+
+    #[test]
+    fn test_end_dx4_encap() {
+        assert_eq!(
+            end_dx4_encap(Ipv4Addr::new(10, 0, 0, 1))
+                .serialize()
+                .unwrap(),
+            END_DX4_ENCAP
+        );
+    }
+}
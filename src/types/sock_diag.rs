@@ -0,0 +1,925 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    message::{Attribute, RouteAttrs},
+    AF_INET, AF_INET6, IPPROTO_SCTP, IPPROTO_TCP, IPPROTO_UDP,
+};
+
+pub const SOCK_DIAG_BY_FAMILY: u16 = 20;
+
+pub const INET_DIAG_MEMINFO: u16 = 1;
+pub const INET_DIAG_INFO: u16 = 2;
+pub const INET_DIAG_VEGASINFO: u16 = 3;
+pub const INET_DIAG_CONG: u16 = 4;
+pub const INET_DIAG_SKMEMINFO: u16 = 7;
+pub const INET_DIAG_LOCALS: u16 = 11;
+pub const INET_DIAG_PEERS: u16 = 12;
+pub const INET_DIAG_BBRINFO: u16 = 16;
+
+/// Bits of the `ext` mask of a `SockDiagReq`, each selecting one extension
+/// attribute the kernel includes in its replies.
+pub const INET_DIAG_EXT_MEMINFO: u8 = 1 << (INET_DIAG_MEMINFO - 1);
+pub const INET_DIAG_EXT_INFO: u8 = 1 << (INET_DIAG_INFO - 1);
+/// Asks the congestion control algorithm for its info, which is returned as
+/// `INET_DIAG_VEGASINFO` by Vegas and as `INET_DIAG_BBRINFO` by BBR. The
+/// `INET_DIAG_BBRINFO` bit itself does not fit into the mask.
+pub const INET_DIAG_EXT_VEGASINFO: u8 = 1 << (INET_DIAG_VEGASINFO - 1);
+pub const INET_DIAG_EXT_CONG: u8 = 1 << (INET_DIAG_CONG - 1);
+pub const INET_DIAG_EXT_SKMEMINFO: u8 = 1 << (INET_DIAG_SKMEMINFO - 1);
+
+pub const TCP_ALL_STATES: u32 = 0xfff;
+pub const TCP_TIME_WAIT: u8 = 6;
+pub const TCP_LISTEN: u8 = 10;
+
+const SOCK_DIAG_ID_LEN: usize = 48;
+const SOCK_DIAG_MSG_LEN: usize = 72;
+const TCP_INFO_LEN: usize = 144;
+const SCTP_INFO_LEN: usize = 52;
+const SOCKADDR_STORAGE_LEN: usize = 128;
+
+/// The 1.0 of the Q8.8 gains of BBR.
+const BBR_UNIT: f64 = 256.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagFamily {
+    V4 = AF_INET as isize,
+    V6 = AF_INET6 as isize,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown address family: {0}")]
+pub struct ParseDiagFamilyError(pub String);
+
+impl FromStr for DiagFamily {
+    type Err = ParseDiagFamilyError;
+
+    /// Parses `ipv4`, `inet`, `4` or `AF_INET` and their IPv6 counterparts,
+    /// ignoring case.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ipv4" | "inet" | "4" | "af_inet" => Ok(Self::V4),
+            "ipv6" | "inet6" | "6" | "af_inet6" => Ok(Self::V6),
+            _ => Err(ParseDiagFamilyError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp = IPPROTO_TCP as isize,
+    Udp = IPPROTO_UDP as isize,
+    Sctp = IPPROTO_SCTP as isize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SockDiagId {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub interface: u32,
+    pub cookie: [u32; 2],
+}
+
+impl Default for SockDiagId {
+    fn default() -> Self {
+        Self {
+            src_port: 0,
+            dst_port: 0,
+            src_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            dst_ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            interface: 0,
+            cookie: [0; 2],
+        }
+    }
+}
+
+impl SockDiagId {
+    /// An id that selects a socket by its cookie alone, e.g. for a query of
+    /// a single socket whose cookie was taken from `SockDiag::cookie`.
+    pub fn with_cookie(cookie: u64) -> Self {
+        Self {
+            cookie: [cookie as u32, (cookie >> 32) as u32],
+            ..Default::default()
+        }
+    }
+
+    fn deserialize(buf: &[u8], family: u8) -> Self {
+        let ip = |b: &[u8]| -> IpAddr {
+            match family {
+                AF_INET6 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(b).unwrap())),
+                _ => IpAddr::V4(Ipv4Addr::new(b[0], b[1], b[2], b[3])),
+            }
+        };
+        let u32_at = |i: usize| u32::from_ne_bytes(buf[i..i + 4].try_into().unwrap());
+
+        Self {
+            src_port: u16::from_be_bytes([buf[0], buf[1]]),
+            dst_port: u16::from_be_bytes([buf[2], buf[3]]),
+            src_ip: ip(&buf[4..20]),
+            dst_ip: ip(&buf[20..36]),
+            interface: u32_at(36),
+            cookie: [u32_at(40), u32_at(44)],
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let ip = |addr: &IpAddr| -> [u8; 16] {
+            let mut b = [0; 16];
+            match addr {
+                IpAddr::V4(ip) => b[..4].copy_from_slice(&ip.octets()),
+                IpAddr::V6(ip) => b.copy_from_slice(&ip.octets()),
+            }
+            b
+        };
+
+        let mut buf = Vec::with_capacity(SOCK_DIAG_ID_LEN);
+        buf.extend_from_slice(&self.src_port.to_be_bytes());
+        buf.extend_from_slice(&self.dst_port.to_be_bytes());
+        buf.extend_from_slice(&ip(&self.src_ip));
+        buf.extend_from_slice(&ip(&self.dst_ip));
+        buf.extend_from_slice(&self.interface.to_ne_bytes());
+        buf.extend_from_slice(&self.cookie[0].to_ne_bytes());
+        buf.extend_from_slice(&self.cookie[1].to_ne_bytes());
+        buf
+    }
+}
+
+/// `inet_diag_req_v2`
+pub struct SockDiagReq {
+    pub family: u8,
+    pub protocol: u8,
+    pub ext: u8,
+    pub states: u32,
+    pub id: SockDiagId,
+}
+
+impl Attribute for SockDiagReq {
+    fn len(&self) -> usize {
+        8 + SOCK_DIAG_ID_LEN
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(self.len());
+        buf.extend_from_slice(&[self.family, self.protocol, self.ext, 0]);
+        buf.extend_from_slice(&self.states.to_ne_bytes());
+        buf.extend_from_slice(&self.id.serialize());
+        Ok(buf)
+    }
+}
+
+impl SockDiagReq {
+    pub fn new(family: DiagFamily, protocol: Protocol) -> Self {
+        Self {
+            family: family as u8,
+            protocol: protocol as u8,
+            ext: 0,
+            states: TCP_ALL_STATES,
+            id: SockDiagId::default(),
+        }
+    }
+
+    pub fn request_tcp_info(family: DiagFamily) -> Self {
+        let mut req = Self::new(family, Protocol::Tcp);
+        req.ext = INET_DIAG_EXT_MEMINFO
+            | INET_DIAG_EXT_INFO
+            | INET_DIAG_EXT_VEGASINFO
+            | INET_DIAG_EXT_CONG;
+        req
+    }
+
+    /// Dumps listening endpoints and associations, including their local
+    /// and peer addresses.
+    pub fn request_sctp_info(family: DiagFamily) -> Self {
+        let mut req = Self::new(family, Protocol::Sctp);
+        req.ext = INET_DIAG_EXT_MEMINFO | INET_DIAG_EXT_INFO;
+        req
+    }
+
+    pub fn request_udp_info(family: DiagFamily) -> Self {
+        let mut req = Self::new(family, Protocol::Udp);
+        req.ext = INET_DIAG_EXT_MEMINFO;
+        req
+    }
+}
+
+/// `inet_diag_msg`
+#[derive(Debug, Clone, Default)]
+pub struct SockDiag {
+    pub family: u8,
+    pub state: u8,
+    pub timer: u8,
+    pub retrans: u8,
+    pub id: SockDiagId,
+    pub expires: u32,
+    pub rqueue: u32,
+    pub wqueue: u32,
+    pub uid: u32,
+    pub inode: u32,
+}
+
+impl SockDiag {
+    /// Whether both ends of the socket are loopback addresses.
+    pub fn is_loopback(&self) -> bool {
+        self.id.src_ip.is_loopback() && self.id.dst_ip.is_loopback()
+    }
+
+    /// Whether the socket is bound to no address and port at either end,
+    /// e.g. an unbound UDP socket.
+    pub fn is_wildcard(&self) -> bool {
+        self.id.src_port == 0
+            && self.id.dst_port == 0
+            && self.id.src_ip.is_unspecified()
+            && self.id.dst_ip.is_unspecified()
+    }
+
+    /// Whether the socket is a TCP socket in `LISTEN`, or a listening SCTP
+    /// endpoint, which reports the same state.
+    pub fn is_listening(&self) -> bool {
+        self.state == TCP_LISTEN
+    }
+
+    /// The socket cookie, whose low 32 bits are `id.cookie[0]`.
+    pub fn cookie(&self) -> u64 {
+        (self.id.cookie[1] as u64) << 32 | self.id.cookie[0] as u64
+    }
+
+    pub fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < SOCK_DIAG_MSG_LEN {
+            bail!(
+                "inet_diag_msg too short: {} < {}",
+                buf.len(),
+                SOCK_DIAG_MSG_LEN
+            );
+        }
+
+        let u32_at = |i: usize| u32::from_ne_bytes(buf[i..i + 4].try_into().unwrap());
+
+        Ok(Self {
+            family: buf[0],
+            state: buf[1],
+            timer: buf[2],
+            retrans: buf[3],
+            id: SockDiagId::deserialize(&buf[4..4 + SOCK_DIAG_ID_LEN], buf[0]),
+            expires: u32_at(52),
+            rqueue: u32_at(56),
+            wqueue: u32_at(60),
+            uid: u32_at(64),
+            inode: u32_at(68),
+        })
+    }
+}
+
+/// `inet_diag_meminfo`
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Memory {
+    pub rmem: u32,
+    pub wmem: u32,
+    pub fmem: u32,
+    pub tmem: u32,
+}
+
+/// The `SK_MEMINFO_*` array of `INET_DIAG_SKMEMINFO`, which unlike
+/// `inet_diag_meminfo` includes the size of the socket buffers.
+#[derive(Debug, Clone, Default)]
+pub struct SkMemInfo {
+    pub rmem_alloc: u32,
+    /// `SO_RCVBUF`
+    pub rcvbuf: u32,
+    pub wmem_alloc: u32,
+    /// `SO_SNDBUF`
+    pub sndbuf: u32,
+    pub fwd_alloc: u32,
+    pub wmem_queued: u32,
+    pub optmem: u32,
+    pub backlog: u32,
+    /// Only reported since Linux 4.7.
+    pub drops: u32,
+}
+
+impl SkMemInfo {
+    fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < 32 {
+            bail!("sk_meminfo too short: {}", buf.len());
+        }
+
+        let u32_at = |i: usize| {
+            buf.get(i..i + 4)
+                .map_or(0, |b| u32::from_ne_bytes(b.try_into().unwrap()))
+        };
+
+        Ok(Self {
+            rmem_alloc: u32_at(0),
+            rcvbuf: u32_at(4),
+            wmem_alloc: u32_at(8),
+            sndbuf: u32_at(12),
+            fwd_alloc: u32_at(16),
+            wmem_queued: u32_at(20),
+            optmem: u32_at(24),
+            backlog: u32_at(28),
+            drops: u32_at(32),
+        })
+    }
+}
+
+/// `tcpvegas_info`
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TcpVegasDiag {
+    pub enabled: u32,
+    pub rtt_cnt: u32,
+    pub rtt: u32,
+    pub min_rtt: u32,
+}
+
+/// `tcp_bbr_info`, with `bbr_bw_lo` and `bbr_bw_hi` combined into `bandwidth`.
+#[derive(Debug, Clone, Default)]
+pub struct TcpBbrDiag {
+    pub bandwidth: u64,
+    pub min_rtt: u32,
+    pub pacing_gain: u32,
+    pub cwnd_gain: u32,
+}
+
+impl TcpBbrDiag {
+    /// The bottleneck bandwidth estimate BBR paces at, in bytes per second.
+    pub fn pacing_rate_bps(&self) -> u64 {
+        self.bandwidth
+    }
+
+    /// `pacing_rate_bps` in megabits per second.
+    pub fn pacing_rate_mbps(&self) -> f64 {
+        self.bandwidth as f64 / 125_000.0
+    }
+
+    /// The pacing gain, which the kernel stores as Q8.8 fixed point, e.g.
+    /// 2.885 at startup and 1.0 when cruising.
+    pub fn pacing_gain_f64(&self) -> f64 {
+        self.pacing_gain as f64 / BBR_UNIT
+    }
+
+    /// The congestion window gain, like `pacing_gain_f64`.
+    pub fn cwnd_gain_f64(&self) -> f64 {
+        self.cwnd_gain as f64 / BBR_UNIT
+    }
+
+    fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < 20 {
+            bail!("tcp_bbr_info too short: {}", buf.len());
+        }
+
+        let u32_at = |i: usize| u32::from_ne_bytes(buf[i..i + 4].try_into().unwrap());
+
+        Ok(Self {
+            bandwidth: (u32_at(4) as u64) << 32 | u32_at(0) as u64,
+            min_rtt: u32_at(8),
+            pacing_gain: u32_at(12),
+            cwnd_gain: u32_at(16),
+        })
+    }
+}
+
+/// Leading, stable part of `struct tcp_info`.
+#[repr(C)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TcpInfo {
+    pub state: u8,
+    pub ca_state: u8,
+    pub retransmits: u8,
+    pub probes: u8,
+    pub backoff: u8,
+    pub options: u8,
+    pub wscale: u8,
+    pub app_limited: u8,
+    pub rto: u32,
+    pub ato: u32,
+    pub snd_mss: u32,
+    pub rcv_mss: u32,
+    pub unacked: u32,
+    pub sacked: u32,
+    pub lost: u32,
+    pub retrans: u32,
+    pub fackets: u32,
+    pub last_data_sent: u32,
+    pub last_ack_sent: u32,
+    pub last_data_recv: u32,
+    pub last_ack_recv: u32,
+    pub pmtu: u32,
+    pub rcv_ssthresh: u32,
+    pub rtt: u32,
+    pub rttvar: u32,
+    pub snd_ssthresh: u32,
+    pub snd_cwnd: u32,
+    pub advmss: u32,
+    pub reordering: u32,
+    pub rcv_rtt: u32,
+    pub rcv_space: u32,
+    pub total_retrans: u32,
+    pub pacing_rate: u64,
+    pub max_pacing_rate: u64,
+    pub bytes_acked: u64,
+    pub bytes_received: u64,
+    pub segs_out: u32,
+    pub segs_in: u32,
+}
+
+impl TcpInfo {
+    fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < TCP_INFO_LEN {
+            bail!("tcp_info too short: {} < {}", buf.len(), TCP_INFO_LEN);
+        }
+
+        Ok(bincode::deserialize(buf)?)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InetDiagTcpResp {
+    pub msg: SockDiag,
+    pub mem: Option<Memory>,
+    pub skmem: Option<SkMemInfo>,
+    pub tcp_info: Option<TcpInfo>,
+    pub vegas: Option<TcpVegasDiag>,
+    pub bbr: Option<TcpBbrDiag>,
+    pub congestion: Option<String>,
+}
+
+impl TryFrom<&[u8]> for InetDiagTcpResp {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg = SockDiag::deserialize(buf)?;
+        let attrs = RouteAttrs::from(&buf[SOCK_DIAG_MSG_LEN..]);
+
+        let mut resp = Self {
+            msg,
+            ..Default::default()
+        };
+
+        for attr in attrs {
+            match attr.header.rta_type {
+                INET_DIAG_MEMINFO => resp.mem = Some(bincode::deserialize(&attr.payload)?),
+                INET_DIAG_SKMEMINFO => resp.skmem = Some(SkMemInfo::deserialize(&attr.payload)?),
+                INET_DIAG_INFO => resp.tcp_info = Some(TcpInfo::deserialize(&attr.payload)?),
+                INET_DIAG_VEGASINFO => resp.vegas = Some(bincode::deserialize(&attr.payload)?),
+                INET_DIAG_BBRINFO => resp.bbr = Some(TcpBbrDiag::deserialize(&attr.payload)?),
+                INET_DIAG_CONG => resp.congestion = Some(attr.payload.to_string()?),
+                _ => {}
+            }
+        }
+
+        Ok(resp)
+    }
+}
+
+/// Leading part of `struct sctp_info`, which the kernel only includes for
+/// associations.
+#[derive(Debug, Clone, Default)]
+pub struct SctpInfo {
+    pub tag: u32,
+    /// The `SCTP_STATE_*` of the association.
+    pub state: u32,
+    pub rwnd: u32,
+    pub unackdata: u16,
+    pub penddata: u16,
+    /// The number of inbound streams.
+    pub instrms: u16,
+    /// The number of outbound streams.
+    pub outstrms: u16,
+    pub fragmentation_point: u32,
+    pub inqueue: u32,
+    pub outqueue: u32,
+    pub overall_error: u32,
+    pub max_burst: u32,
+    pub maxseg: u32,
+    pub peer_rwnd: u32,
+    pub peer_tag: u32,
+}
+
+impl SctpInfo {
+    fn deserialize(buf: &[u8]) -> Result<Self> {
+        if buf.len() < SCTP_INFO_LEN {
+            bail!("sctp_info too short: {} < {}", buf.len(), SCTP_INFO_LEN);
+        }
+
+        let u16_at = |i: usize| u16::from_ne_bytes(buf[i..i + 2].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_ne_bytes(buf[i..i + 4].try_into().unwrap());
+
+        Ok(Self {
+            tag: u32_at(0),
+            state: u32_at(4),
+            rwnd: u32_at(8),
+            unackdata: u16_at(12),
+            penddata: u16_at(14),
+            instrms: u16_at(16),
+            outstrms: u16_at(18),
+            fragmentation_point: u32_at(20),
+            inqueue: u32_at(24),
+            outqueue: u32_at(28),
+            overall_error: u32_at(32),
+            max_burst: u32_at(36),
+            maxseg: u32_at(40),
+            peer_rwnd: u32_at(44),
+            peer_tag: u32_at(48),
+        })
+    }
+}
+
+/// Parses the array of `sockaddr_storage` in `INET_DIAG_LOCALS` and
+/// `INET_DIAG_PEERS`.
+fn sockaddrs(buf: &[u8]) -> Vec<SocketAddr> {
+    buf.chunks_exact(SOCKADDR_STORAGE_LEN)
+        .filter_map(|sa| {
+            let family = u8::try_from(u16::from_ne_bytes([sa[0], sa[1]]));
+            let port = u16::from_be_bytes([sa[2], sa[3]]);
+
+            match family {
+                Ok(AF_INET) => {
+                    let ip: [u8; 4] = sa[4..8].try_into().unwrap();
+                    Some(SocketAddr::new(ip.into(), port))
+                }
+                Ok(AF_INET6) => {
+                    let ip: [u8; 16] = sa[8..24].try_into().unwrap();
+                    Some(SocketAddr::new(ip.into(), port))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// An SCTP endpoint or association. `msg.state` is `TCP_LISTEN` for a
+/// listening endpoint and the `SCTP_STATE_*` of an association otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct SctpDiagResp {
+    pub msg: SockDiag,
+    pub mem: Option<Memory>,
+    pub sctp_info: Option<SctpInfo>,
+    pub locals: Vec<SocketAddr>,
+    pub peers: Vec<SocketAddr>,
+}
+
+impl TryFrom<&[u8]> for SctpDiagResp {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg = SockDiag::deserialize(buf)?;
+        let attrs = RouteAttrs::from(&buf[SOCK_DIAG_MSG_LEN..]);
+
+        let mut resp = Self {
+            msg,
+            ..Default::default()
+        };
+
+        for attr in attrs {
+            match attr.header.rta_type {
+                INET_DIAG_MEMINFO => resp.mem = Some(bincode::deserialize(&attr.payload)?),
+                INET_DIAG_INFO => resp.sctp_info = Some(SctpInfo::deserialize(&attr.payload)?),
+                INET_DIAG_LOCALS => resp.locals = sockaddrs(&attr.payload),
+                INET_DIAG_PEERS => resp.peers = sockaddrs(&attr.payload),
+                _ => {}
+            }
+        }
+
+        Ok(resp)
+    }
+}
+
+/// The TCP socket usage of the kernel, like the `TCP:` line of `ss -s`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagSummary {
+    /// The TCP sockets of both address families, in any state.
+    pub tcp_total: usize,
+    /// The sockets no longer attached to a file, e.g. closed while data was
+    /// still queued.
+    pub tcp_orphaned: usize,
+    /// The sockets in `TIME_WAIT`.
+    pub tcp_tw: usize,
+    /// The TCP sockets the kernel allocated, including those not hashed yet.
+    pub tcp_allocated: usize,
+    /// The memory used by TCP, in pages.
+    pub mem: usize,
+}
+
+impl DiagSummary {
+    /// Parses the `TCP:` line of `/proc/net/sockstat`, e.g.
+    /// `TCP: inuse 5 orphan 0 tw 2 alloc 7 mem 1`, for the counters the
+    /// sock_diag dump does not expose.
+    pub fn from_sockstat(sockstat: &str) -> Result<Self> {
+        let Some(line) = sockstat.lines().find_map(|line| line.strip_prefix("TCP:")) else {
+            bail!("no TCP line in sockstat");
+        };
+
+        let fields: Vec<_> = line.split_whitespace().collect();
+        let mut summary = Self::default();
+
+        for pair in fields.chunks_exact(2) {
+            let value = pair[1].parse()?;
+            match pair[0] {
+                "inuse" => summary.tcp_total = value,
+                "orphan" => summary.tcp_orphaned = value,
+                "tw" => summary.tcp_tw = value,
+                "alloc" => summary.tcp_allocated = value,
+                "mem" => summary.mem = value,
+                _ => {}
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use crate::types::message::RouteAttr;
+
+    use super::*;
+
+    #[test]
+    fn test_diag_family_from_str() {
+        for s in ["ipv4", "inet", "4", "AF_INET", "IPv4"] {
+            assert_eq!(s.parse::<DiagFamily>(), Ok(DiagFamily::V4));
+        }
+        for s in ["ipv6", "inet6", "6", "AF_INET6"] {
+            assert_eq!(s.parse::<DiagFamily>(), Ok(DiagFamily::V6));
+        }
+
+        let err = "unix".parse::<DiagFamily>().unwrap_err();
+        assert_eq!(err, ParseDiagFamilyError("unix".to_string()));
+        assert_eq!(err.to_string(), "unknown address family: unix");
+    }
+
+    #[rustfmt::skip]
+    static INET_DIAG_MSG: [u8; 92] = [
+        0x02, // family = AF_INET
+        0x0a, // state = TCP_LISTEN
+        0x00, // timer
+        0x00, // retrans
+        // inet_diag_sockid
+        0x1f, 0x90, // sport = 8080
+        0x00, 0x00, // dport = 0
+        0x7f, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // src = 127.0.0.1
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // dst = 0.0.0.0
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, // interface
+        0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // cookie
+        0x00, 0x00, 0x00, 0x00, // expires
+        0x00, 0x00, 0x00, 0x00, // rqueue
+        0x80, 0x00, 0x00, 0x00, // wqueue = 128
+        0xe8, 0x03, 0x00, 0x00, // uid = 1000
+        0x39, 0x30, 0x00, 0x00, // inode = 12345
+        // nlas
+        0x0a, 0x00, 0x04, 0x00, 0x63, 0x75, 0x62, 0x69, 0x63, 0x00, // cong L=10,T=4,V=cubic
+        0x00, 0x00, // padding
+        0x08, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, // tos L=8,T=5,V=0
+    ];
+
+    #[test]
+    fn test_sock_diag_deserialize() {
+        let msg = SockDiag::deserialize(&INET_DIAG_MSG).unwrap();
+
+        assert_eq!(msg.family, AF_INET);
+        assert_eq!(msg.state, 10);
+        assert_eq!(msg.id.src_port, 8080);
+        assert_eq!(msg.id.src_ip, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(msg.id.dst_ip, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(msg.id.cookie, [1, 2]);
+        assert_eq!(msg.wqueue, 128);
+        assert_eq!(msg.uid, 1000);
+        assert_eq!(msg.inode, 12345);
+
+        assert!(SockDiag::deserialize(&INET_DIAG_MSG[..40]).is_err());
+    }
+
+    #[test]
+    fn test_inet_diag_tcp_resp_try_from() {
+        let resp = InetDiagTcpResp::try_from(&INET_DIAG_MSG[..]).unwrap();
+
+        assert_eq!(resp.msg.inode, 12345);
+        assert_eq!(resp.congestion.as_deref(), Some("cubic"));
+        assert!(resp.tcp_info.is_none());
+        assert!(resp.bbr.is_none());
+    }
+
+    #[test]
+    fn test_inet_diag_skmeminfo() {
+        let mut skmem = [0u8; 36];
+        skmem[..4].copy_from_slice(&768u32.to_ne_bytes());
+        skmem[4..8].copy_from_slice(&4608u32.to_ne_bytes());
+        skmem[32..].copy_from_slice(&3u32.to_ne_bytes());
+
+        let mut buf = INET_DIAG_MSG[..SOCK_DIAG_MSG_LEN].to_vec();
+        buf.extend(
+            RouteAttr::new(INET_DIAG_SKMEMINFO, &skmem)
+                .serialize()
+                .unwrap(),
+        );
+
+        let skmem = InetDiagTcpResp::try_from(&buf[..]).unwrap().skmem.unwrap();
+        assert_eq!(skmem.rmem_alloc, 768);
+        assert_eq!(skmem.rcvbuf, 4608);
+        assert_eq!(skmem.drops, 3);
+
+        assert!(SkMemInfo::deserialize(&[0; 28]).is_err());
+        assert_eq!(SkMemInfo::deserialize(&[0; 32]).unwrap().drops, 0);
+    }
+
+    #[test]
+    fn test_sctp_diag_resp_try_from() {
+        let mut local = [0u8; SOCKADDR_STORAGE_LEN];
+        local[..2].copy_from_slice(&(AF_INET as u16).to_ne_bytes());
+        local[2..4].copy_from_slice(&9899u16.to_be_bytes());
+        local[4..8].copy_from_slice(&[10, 0, 0, 1]);
+
+        let mut peer = [0u8; SOCKADDR_STORAGE_LEN];
+        peer[..2].copy_from_slice(&(AF_INET6 as u16).to_ne_bytes());
+        peer[2..4].copy_from_slice(&40000u16.to_be_bytes());
+        peer[8..24].copy_from_slice(&Ipv6Addr::LOCALHOST.octets());
+
+        let mut info = [0u8; 56];
+        info[4..8].copy_from_slice(&3u32.to_ne_bytes());
+        info[16..18].copy_from_slice(&10u16.to_ne_bytes());
+        info[18..20].copy_from_slice(&5u16.to_ne_bytes());
+        info[48..52].copy_from_slice(&0xcafeu32.to_ne_bytes());
+
+        let mut buf = INET_DIAG_MSG[..SOCK_DIAG_MSG_LEN].to_vec();
+        buf.extend(
+            RouteAttr::new(INET_DIAG_LOCALS, &local)
+                .serialize()
+                .unwrap(),
+        );
+        buf.extend(RouteAttr::new(INET_DIAG_PEERS, &peer).serialize().unwrap());
+        buf.extend(RouteAttr::new(INET_DIAG_INFO, &info).serialize().unwrap());
+
+        let resp = SctpDiagResp::try_from(buf.as_slice()).unwrap();
+
+        assert_eq!(resp.msg.inode, 12345);
+        assert_eq!(resp.locals, ["10.0.0.1:9899".parse().unwrap()]);
+        assert_eq!(resp.peers, ["[::1]:40000".parse().unwrap()]);
+
+        let info = resp.sctp_info.unwrap();
+        assert_eq!(info.state, 3);
+        assert_eq!(info.instrms, 10);
+        assert_eq!(info.outstrms, 5);
+        assert_eq!(info.peer_tag, 0xcafe);
+
+        buf.truncate(SOCK_DIAG_MSG_LEN);
+        buf.extend(
+            RouteAttr::new(INET_DIAG_INFO, &[0; 20])
+                .serialize()
+                .unwrap(),
+        );
+        assert!(SctpDiagResp::try_from(buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_sock_diag_req_serialize() {
+        let mut req = SockDiagReq::request_tcp_info(DiagFamily::V6);
+        req.id.src_port = 443;
+
+        let buf = req.serialize().unwrap();
+
+        assert_eq!(buf.len(), req.len());
+        assert_eq!(&buf[..4], &[AF_INET6, IPPROTO_TCP, 0x0f, 0]);
+        assert_eq!(&buf[4..8], &TCP_ALL_STATES.to_ne_bytes());
+        assert_eq!(&buf[8..10], &[0x01, 0xbb]);
+    }
+
+    fn ip_strategy(family: u8) -> BoxedStrategy<IpAddr> {
+        match family {
+            AF_INET6 => any::<[u8; 16]>().prop_map(IpAddr::from).boxed(),
+            _ => any::<[u8; 4]>().prop_map(IpAddr::from).boxed(),
+        }
+    }
+
+    fn id_strategy(family: u8) -> impl Strategy<Value = SockDiagId> {
+        (
+            any::<(u16, u16, u32, [u32; 2])>(),
+            ip_strategy(family),
+            ip_strategy(family),
+        )
+            .prop_map(
+                |((src_port, dst_port, interface, cookie), src_ip, dst_ip)| SockDiagId {
+                    src_port,
+                    dst_port,
+                    src_ip,
+                    dst_ip,
+                    interface,
+                    cookie,
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn prop_sock_diag_id_roundtrip(
+            (family, id) in prop_oneof![Just(AF_INET), Just(AF_INET6)]
+                .prop_flat_map(|family| (Just(family), id_strategy(family)))
+        ) {
+            let buf = id.serialize();
+            prop_assert_eq!(buf.len(), SOCK_DIAG_ID_LEN);
+            prop_assert_eq!(SockDiagId::deserialize(&buf, family), id);
+        }
+
+        /// Arbitrary bytes after a well-formed `inet_diag_msg` fail to parse
+        /// or are skipped, but never panic.
+        #[test]
+        fn prop_inet_diag_tcp_resp_arbitrary_bytes(
+            msg in vec(any::<u8>(), 0..SOCK_DIAG_MSG_LEN + 1),
+            attrs in vec(any::<u8>(), 0..256),
+        ) {
+            let _ = SockDiag::deserialize(&msg);
+
+            let mut buf = msg;
+            buf.resize(SOCK_DIAG_MSG_LEN, 0);
+            buf.extend_from_slice(&attrs);
+            let _ = InetDiagTcpResp::try_from(buf.as_slice());
+            let _ = SctpDiagResp::try_from(buf.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_diag_summary_from_sockstat() {
+        let sockstat = "sockets: used 24\n\
+                        TCP: inuse 5 orphan 1 tw 2 alloc 7 mem 3\n\
+                        UDP: inuse 0 mem 0\n";
+
+        assert_eq!(
+            DiagSummary::from_sockstat(sockstat).unwrap(),
+            DiagSummary {
+                tcp_total: 5,
+                tcp_orphaned: 1,
+                tcp_tw: 2,
+                tcp_allocated: 7,
+                mem: 3,
+            }
+        );
+
+        assert!(DiagSummary::from_sockstat("sockets: used 24\n").is_err());
+    }
+
+    #[test]
+    fn test_sock_diag_cookie() {
+        let id = SockDiagId::with_cookie(0x1122_3344_5566_7788);
+        assert_eq!(id.cookie, [0x5566_7788, 0x1122_3344]);
+
+        let msg = SockDiag {
+            id,
+            ..Default::default()
+        };
+        assert_eq!(msg.cookie(), 0x1122_3344_5566_7788);
+    }
+
+    #[test]
+    fn test_tcp_bbr_diag_gains() {
+        let bbr = TcpBbrDiag {
+            bandwidth: 12_500_000,
+            min_rtt: 100,
+            pacing_gain: 739,
+            cwnd_gain: 512,
+        };
+
+        assert_eq!(bbr.pacing_rate_bps(), 12_500_000);
+        assert_eq!(bbr.pacing_rate_mbps(), 100.0);
+        assert!((bbr.pacing_gain_f64() - 2.885).abs() < 0.01);
+        assert_eq!(bbr.cwnd_gain_f64(), 2.0);
+    }
+
+    #[test]
+    fn test_sock_diag_predicates() {
+        let msg = |src: &str, dst: &str, src_port, state| SockDiag {
+            state,
+            id: SockDiagId {
+                src_ip: src.parse().unwrap(),
+                dst_ip: dst.parse().unwrap(),
+                src_port,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let conn = msg("127.0.0.1", "127.0.0.1", 80, 1);
+        assert!(conn.is_loopback());
+        assert!(!conn.is_wildcard());
+        assert!(!conn.is_listening());
+
+        let listener = msg("::1", "::", 80, TCP_LISTEN);
+        assert!(!listener.is_loopback());
+        assert!(listener.is_listening());
+
+        let unbound = msg("0.0.0.0", "0.0.0.0", 0, 7);
+        assert!(unbound.is_wildcard());
+        assert!(!unbound.is_loopback());
+    }
+}
@@ -0,0 +1,391 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+use super::message::{Attribute, RouteAttr, RouteAttrs, TcMessage};
+
+pub const TCA_KIND: u16 = 1;
+pub const TCA_OPTIONS: u16 = 2;
+pub const TCA_STATS2: u16 = 7;
+
+pub const TCA_STATS_BASIC: u16 = 1;
+pub const TCA_STATS_QUEUE: u16 = 3;
+
+pub const TC_H_ROOT: u32 = 0xffff_ffff;
+/// The parent of the `clsact` qdisc, whose minor numbers select the ingress
+/// and the egress hook.
+pub const TC_H_CLSACT: u32 = 0xffff_fff1;
+pub const TC_H_MIN_INGRESS: u32 = 0xfff2;
+pub const TC_H_MIN_EGRESS: u32 = 0xfff3;
+
+pub const TC_ACT_PIPE: i32 = 3;
+
+pub const TCA_ACT_KIND: u16 = 1;
+pub const TCA_ACT_OPTIONS: u16 = 2;
+
+pub const TCA_MATCHALL_ACT: u16 = 2;
+
+pub const TCA_SKBEDIT_PARMS: u16 = 2;
+pub const TCA_SKBEDIT_PRIORITY: u16 = 3;
+pub const TCA_SKBEDIT_QUEUE_MAPPING: u16 = 4;
+pub const TCA_SKBEDIT_MARK: u16 = 5;
+
+pub const TCA_FQ_CODEL_TARGET: u16 = 1;
+pub const TCA_FQ_CODEL_LIMIT: u16 = 2;
+pub const TCA_FQ_CODEL_INTERVAL: u16 = 3;
+pub const TCA_FQ_CODEL_ECN: u16 = 4;
+pub const TCA_FQ_CODEL_FLOWS: u16 = 5;
+pub const TCA_FQ_CODEL_QUANTUM: u16 = 6;
+
+pub const TCA_TBF_PARMS: u16 = 1;
+pub const TCA_TBF_RATE64: u16 = 4;
+pub const TCA_TBF_BURST: u16 = 6;
+
+/// The kernel measures qdisc time in ticks of 64ns.
+const PSCHED_SHIFT: u32 = 6;
+
+pub const TCA_CAKE_BASE_RATE64: u16 = 2;
+pub const TCA_CAKE_NAT: u16 = 11;
+pub const TCA_CAKE_WASH: u16 = 13;
+
+/// A qdisc as reported by `RTM_GETQDISC`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Qdisc {
+    pub ifindex: u32,
+    pub handle: u32,
+    pub parent: u32,
+    /// The qdisc type, e.g. `fq_codel`.
+    pub kind: String,
+    pub stats: QdiscStats,
+}
+
+/// The counters of a qdisc, from `gnet_stats_basic` and
+/// `gnet_stats_queue` in `TCA_STATS2`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QdiscStats {
+    pub bytes: u64,
+    pub packets: u32,
+    pub qlen: u32,
+    pub backlog: u32,
+    pub drops: u32,
+    pub requeues: u32,
+    pub overlimits: u32,
+}
+
+impl TryFrom<&[u8]> for Qdisc {
+    type Error = anyhow::Error;
+
+    fn try_from(buf: &[u8]) -> Result<Self> {
+        let msg: TcMessage = bincode::deserialize(buf)?;
+
+        let mut qdisc = Self {
+            ifindex: msg.index as u32,
+            handle: msg.handle,
+            parent: msg.parent,
+            ..Default::default()
+        };
+
+        for attr in RouteAttrs::from(&buf[msg.len()..]) {
+            match attr.header.rta_type {
+                TCA_KIND => qdisc.kind = attr.payload.as_string()?,
+                TCA_STATS2 => qdisc.stats = QdiscStats::from_stats2(&attr.payload)?,
+                _ => {}
+            }
+        }
+
+        Ok(qdisc)
+    }
+}
+
+impl QdiscStats {
+    fn from_stats2(buf: &[u8]) -> Result<Self> {
+        let mut stats = Self::default();
+
+        for attr in RouteAttrs::from(buf) {
+            let p = &attr.payload;
+            let u32_at = |i: usize| u32::from_ne_bytes(p[i..i + 4].try_into().unwrap());
+
+            match attr.header.rta_type {
+                TCA_STATS_BASIC if p.len() >= 12 => {
+                    stats.bytes = u64::from_ne_bytes(p[..8].try_into()?);
+                    stats.packets = u32_at(8);
+                }
+                TCA_STATS_QUEUE if p.len() >= 20 => {
+                    stats.qlen = u32_at(0);
+                    stats.backlog = u32_at(4);
+                    stats.drops = u32_at(8);
+                    stats.requeues = u32_at(12);
+                    stats.overlimits = u32_at(16);
+                }
+                TCA_STATS_BASIC | TCA_STATS_QUEUE => {
+                    bail!("gnet stats too short: {} bytes", p.len())
+                }
+                _ => {}
+            }
+        }
+
+        Ok(stats)
+    }
+}
+
+/// Parameters of the `fq_codel` qdisc. Unset fields keep the kernel
+/// defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FqCodelParams {
+    /// The acceptable minimum standing queue delay.
+    pub target: Option<Duration>,
+    /// The width of the window over which the minimum delay is tracked.
+    pub interval: Option<Duration>,
+    /// The queue limit in packets.
+    pub limit: Option<u32>,
+    /// The number of flow buckets, which can only be set at creation.
+    pub flows: Option<u32>,
+    /// The number of bytes dequeued from a flow per round.
+    pub quantum: Option<u32>,
+    /// Whether packets are ECN marked instead of dropped.
+    pub ecn: Option<bool>,
+}
+
+impl FqCodelParams {
+    pub fn options(&self) -> RouteAttr {
+        let mut options = RouteAttr::new(TCA_OPTIONS, &[]);
+
+        let micros = |d: &Duration| (d.as_micros() as u32).to_ne_bytes();
+
+        if let Some(target) = &self.target {
+            options.add(TCA_FQ_CODEL_TARGET, &micros(target));
+        }
+        if let Some(limit) = self.limit {
+            options.add(TCA_FQ_CODEL_LIMIT, &limit.to_ne_bytes());
+        }
+        if let Some(interval) = &self.interval {
+            options.add(TCA_FQ_CODEL_INTERVAL, &micros(interval));
+        }
+        if let Some(ecn) = self.ecn {
+            options.add(TCA_FQ_CODEL_ECN, &(ecn as u32).to_ne_bytes());
+        }
+        if let Some(flows) = self.flows {
+            options.add(TCA_FQ_CODEL_FLOWS, &flows.to_ne_bytes());
+        }
+        if let Some(quantum) = self.quantum {
+            options.add(TCA_FQ_CODEL_QUANTUM, &quantum.to_ne_bytes());
+        }
+
+        options
+    }
+}
+
+/// Parameters of the `tbf` qdisc.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tbf {
+    /// The shaping rate in bytes per second.
+    pub rate: u64,
+    /// The size of the bucket in bytes, i.e. how much can be sent at once.
+    pub burst: u32,
+    /// How long a packet may wait in the queue, in microseconds. Together
+    /// with the rate and burst this determines the queue limit in bytes.
+    pub latency: u32,
+    /// The size of the largest packet in bytes.
+    pub mtu: u32,
+}
+
+impl Tbf {
+    /// Builds the options from a `tc_tbf_qopt` plus the 64-bit rate and the
+    /// burst in bytes, which spare the kernel from converting back from
+    /// ticks.
+    pub fn options(&self) -> Result<RouteAttr> {
+        if self.rate == 0 {
+            bail!("tbf rate must not be zero");
+        }
+
+        let ticks = |bytes: u32| {
+            let ns = bytes as u128 * 1_000_000_000 / self.rate as u128;
+            (ns >> PSCHED_SHIFT).min(u32::MAX as u128) as u32
+        };
+        let limit = self.rate as u128 * self.latency as u128 / 1_000_000 + self.burst as u128;
+
+        // tc_ratespec rate: cell_log, linklayer, overhead, cell_align, mpu
+        let mut qopt = vec![0, 1, 0, 0, 0, 0, 0, 0];
+        qopt.extend_from_slice(&(self.rate.min(u32::MAX as u64) as u32).to_ne_bytes());
+        // tc_ratespec peakrate
+        qopt.extend_from_slice(&[0; 12]);
+        qopt.extend_from_slice(&(limit.min(u32::MAX as u128) as u32).to_ne_bytes());
+        qopt.extend_from_slice(&ticks(self.burst).to_ne_bytes());
+        qopt.extend_from_slice(&ticks(self.mtu).to_ne_bytes());
+
+        let mut options = RouteAttr::new(TCA_OPTIONS, &[]);
+
+        options.add(TCA_TBF_PARMS, &qopt);
+        options.add(TCA_TBF_RATE64, &self.rate.to_ne_bytes());
+        options.add(TCA_TBF_BURST, &self.burst.to_ne_bytes());
+
+        Ok(options)
+    }
+}
+
+/// Builds the options of the `cake` qdisc. The kernel takes the shaper rate
+/// in bytes per second, so `bandwidth_bps` is converted from bits; a rate of
+/// zero leaves the shaper disabled.
+pub fn cake_options(bandwidth_bps: u64, nat: bool, wash: bool) -> RouteAttr {
+    let mut options = RouteAttr::new(TCA_OPTIONS, &[]);
+
+    options.add(TCA_CAKE_BASE_RATE64, &(bandwidth_bps / 8).to_ne_bytes());
+    options.add(TCA_CAKE_NAT, &(nat as u32).to_ne_bytes());
+    options.add(TCA_CAKE_WASH, &(wash as u32).to_ne_bytes());
+
+    options
+}
+
+/// Builds a `skbedit` action, which sets the fwmark, the `skb->priority`
+/// and the tx queue of every packet it sees, at position `prio` of an action
+/// list. The packet continues to the next action, i.e. `TC_ACT_PIPE`.
+pub fn skbedit_action(
+    prio: u16,
+    mark: Option<u32>,
+    priority: Option<u32>,
+    queue_mapping: Option<u16>,
+) -> Result<RouteAttr> {
+    if mark.is_none() && priority.is_none() && queue_mapping.is_none() {
+        bail!("skbedit needs a mark, a priority or a queue mapping");
+    }
+
+    // tc_skbedit: index, capab, action, refcnt, bindcnt
+    let mut parms = vec![0; 8];
+    parms.extend_from_slice(&TC_ACT_PIPE.to_ne_bytes());
+    parms.extend_from_slice(&[0; 8]);
+
+    let mut options = RouteAttr::new(TCA_ACT_OPTIONS, &[]);
+    options.add(TCA_SKBEDIT_PARMS, &parms);
+
+    if let Some(priority) = priority {
+        options.add(TCA_SKBEDIT_PRIORITY, &priority.to_ne_bytes());
+    }
+    if let Some(queue_mapping) = queue_mapping {
+        options.add(TCA_SKBEDIT_QUEUE_MAPPING, &queue_mapping.to_ne_bytes());
+    }
+    if let Some(mark) = mark {
+        options.add(TCA_SKBEDIT_MARK, &mark.to_ne_bytes());
+    }
+
+    let mut action = RouteAttr::new(prio, &[]);
+    action.add_attribute(Box::new(RouteAttr::string(TCA_ACT_KIND, "skbedit")));
+    action.add_attribute(Box::new(options));
+
+    Ok(action)
+}
+
+/// Builds the options of a `matchall` filter, which applies `actions` to
+/// every packet.
+pub fn matchall_options(actions: Vec<RouteAttr>) -> RouteAttr {
+    let mut act = RouteAttr::new(TCA_MATCHALL_ACT, &[]);
+    for action in actions {
+        act.add_attribute(Box::new(action));
+    }
+
+    let mut options = RouteAttr::new(TCA_OPTIONS, &[]);
+    options.add_attribute(Box::new(act));
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::message::Attribute;
+
+    use super::*;
+
+    #[rustfmt::skip]
+    static FQ_CODEL_OPTIONS: [u8; 36] = [
+        0x24, 0x00, 0x02, 0x00, // options L=36,T=2
+        0x08, 0x00, 0x01, 0x00, 0x88, 0x13, 0x00, 0x00, // target = 5000us
+        0x08, 0x00, 0x03, 0x00, 0xa0, 0x86, 0x01, 0x00, // interval = 100000us
+        0x08, 0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, // ecn = 1
+        0x08, 0x00, 0x05, 0x00, 0x00, 0x04, 0x00, 0x00, // flows = 1024
+    ];
+
+    #[rustfmt::skip]
+    static CAKE_OPTIONS: [u8; 32] = [
+        0x20, 0x00, 0x02, 0x00, // options L=32,T=2
+        0x0c, 0x00, 0x02, 0x00, // base_rate64 L=12,T=2
+        0x40, 0x42, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00, // 1000000 bytes/s
+        0x08, 0x00, 0x0b, 0x00, 0x01, 0x00, 0x00, 0x00, // nat = 1
+        0x08, 0x00, 0x0d, 0x00, 0x00, 0x00, 0x00, 0x00, // wash = 0
+    ];
+
+    #[test]
+    fn test_fq_codel_options() {
+        let params = FqCodelParams {
+            target: Some(Duration::from_millis(5)),
+            interval: Some(Duration::from_millis(100)),
+            flows: Some(1024),
+            ecn: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(params.options().serialize().unwrap(), FQ_CODEL_OPTIONS);
+        assert_eq!(
+            FqCodelParams::default().options().serialize().unwrap(),
+            [0x04, 0x00, 0x02, 0x00]
+        );
+    }
+
+    #[rustfmt::skip]
+    static TBF_OPTIONS: [u8; 64] = [
+        0x40, 0x00, 0x02, 0x00, // options L=64,T=2
+        0x28, 0x00, 0x01, 0x00, // parms L=40,T=1
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // rate: linklayer = ethernet
+        0x40, 0x42, 0x0f, 0x00, // rate = 1000000
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // peakrate
+        0x00, 0x00, 0x00, 0x00,
+        0x50, 0xc3, 0x00, 0x00, // limit = 40000 + 10000
+        0x5a, 0x62, 0x02, 0x00, // buffer = 10ms in ticks
+        0x68, 0x5c, 0x00, 0x00, // mtu = 1514us in ticks
+        0x0c, 0x00, 0x04, 0x00, // rate64 L=12,T=4
+        0x40, 0x42, 0x0f, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x08, 0x00, 0x06, 0x00, 0x10, 0x27, 0x00, 0x00, // burst = 10000
+    ];
+
+    #[test]
+    fn test_tbf_options() {
+        let tbf = Tbf {
+            rate: 1_000_000,
+            burst: 10_000,
+            latency: 40_000,
+            mtu: 1514,
+        };
+
+        assert_eq!(tbf.options().unwrap().serialize().unwrap(), TBF_OPTIONS);
+        assert!(Tbf::default().options().is_err());
+    }
+
+    #[test]
+    fn test_cake_options() {
+        assert_eq!(
+            cake_options(8_000_000, true, false).serialize().unwrap(),
+            CAKE_OPTIONS
+        );
+    }
+
+    #[rustfmt::skip]
+    static SKBEDIT_ACTION: [u8; 68] = [
+        0x44, 0x00, 0x01, 0x00, // action L=68,T=prio 1
+        0x0c, 0x00, 0x01, 0x00, // kind L=12,T=1
+        0x73, 0x6b, 0x62, 0x65, 0x64, 0x69, 0x74, 0x00, // "skbedit"
+        0x34, 0x00, 0x02, 0x00, // options L=52,T=2
+        0x18, 0x00, 0x02, 0x00, // parms L=24,T=2
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // index, capab
+        0x03, 0x00, 0x00, 0x00, // action = TC_ACT_PIPE
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // refcnt, bindcnt
+        0x08, 0x00, 0x03, 0x00, 0x07, 0x00, 0x00, 0x00, // priority = 7
+        0x06, 0x00, 0x04, 0x00, 0x02, 0x00, 0x00, 0x00, // queue_mapping = 2
+        0x08, 0x00, 0x05, 0x00, 0x10, 0x00, 0x00, 0x00, // mark = 0x10
+    ];
+
+    #[test]
+    fn test_skbedit_action() {
+        let action = skbedit_action(1, Some(0x10), Some(7), Some(2)).unwrap();
+
+        assert_eq!(action.serialize().unwrap(), SKBEDIT_ACTION);
+        assert!(skbedit_action(1, None, None, None).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use rsln::{
+    handle::sock_handle::SocketHandle,
+    types::{addr::AddressBuilder, link::LinkAttrs},
+};
+
+use crate::netns::netns_fixture;
+
+#[test]
+fn test_addr_add_list() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let lo = handle.handle_link().get(&LinkAttrs::new("lo")).unwrap();
+
+        let ip = "127.0.0.2/24".parse().unwrap();
+        let addr = AddressBuilder::default().ip(ip).build().unwrap();
+
+        let mut addr_handle = handle.handle_addr();
+        addr_handle
+            .handle(
+                &lo,
+                &addr,
+                libc::RTM_NEWADDR,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let addrs = addr_handle.list(&lo, libc::AF_INET).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].ip, ip);
+    });
+}
+
+#[test]
+fn test_addr_handle() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let link = handle.handle_link().get(&LinkAttrs::new("lo")).unwrap();
+
+        let address = "127.0.0.2/24".parse().unwrap();
+        let addr = AddressBuilder::default().ip(address).build().unwrap();
+
+        let proto = libc::RTM_NEWADDR;
+        let flags = libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK;
+
+        let mut addr_handle = handle.handle_addr();
+        addr_handle.handle(&link, &addr, proto, flags).unwrap();
+
+        let addrs = addr_handle.list(&link, libc::AF_UNSPEC).unwrap();
+        assert_eq!(addrs.len(), 1);
+        assert_eq!(addrs[0].ip, address);
+    });
+}
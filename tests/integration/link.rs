@@ -0,0 +1,203 @@
+use rsln::{
+    handle::sock_handle::SocketHandle,
+    types::link::{Kind, LinkAttrs, OperState},
+};
+
+use crate::netns::netns_fixture;
+
+#[test]
+fn test_link_veth_add_del() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let attr = LinkAttrs::new("veth0");
+
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "veth1".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        link_handle
+            .add(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = link_handle.get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "veth");
+
+        let mut names: Vec<_> = link_handle
+            .list()
+            .unwrap()
+            .iter()
+            .map(|l| l.attrs().name.clone())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["lo", "veth0", "veth1"]);
+
+        link_handle.delete(&link).unwrap();
+        assert!(link_handle.get(&attr).is_err());
+    });
+}
+
+#[test]
+fn test_link_add_modify_del() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let mut attr = LinkAttrs::new("foo");
+
+        let link = Kind::Dummy(attr.clone());
+
+        link_handle
+            .add(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = link_handle.get(&attr).unwrap();
+        assert_eq!(link.attrs().name, "foo");
+
+        attr = link.attrs().clone();
+        attr.name = "bar".to_string();
+
+        let link = Kind::Dummy(attr.clone());
+
+        link_handle.add(&link, libc::NLM_F_ACK).unwrap();
+
+        let link = link_handle.get(&attr).unwrap();
+        assert_eq!(link.attrs().name, "bar");
+
+        link_handle.delete(&link).unwrap();
+        assert!(link_handle.get(&attr).is_err());
+    });
+}
+
+#[test]
+fn test_link_bridge() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let attr = LinkAttrs::new("foo");
+
+        let link = Kind::Bridge {
+            attrs: attr.clone(),
+            hello_time: None,
+            ageing_time: Some(30102),
+            vlan_filtering: Some(true),
+            multicast_snooping: None,
+            stp_state: None,
+            forward_delay: None,
+            vlan_default_pvid: None,
+        };
+
+        link_handle
+            .add(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = link_handle.get(&attr).unwrap();
+        assert_eq!(link.attrs().link_type, "bridge");
+        assert_eq!(link.attrs().name, "foo");
+
+        match link.kind() {
+            Kind::Bridge {
+                hello_time,
+                ageing_time,
+                vlan_filtering,
+                multicast_snooping,
+                ..
+            } => {
+                assert_eq!(hello_time.unwrap(), 200);
+                assert_eq!(ageing_time.unwrap(), 30102);
+                assert!(vlan_filtering.unwrap());
+                assert!(multicast_snooping.unwrap());
+            }
+            _ => panic!("wrong link type"),
+        }
+
+        link_handle.delete(&link).unwrap();
+        assert!(link_handle.get(&attr).is_err());
+    });
+}
+
+#[test]
+fn test_link_veth() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+        let mut attr = LinkAttrs::new("foo");
+        attr.mtu = 1400;
+        attr.tx_queue_len = 100;
+        attr.num_tx_queues = 4;
+        attr.num_rx_queues = 8;
+
+        let link = Kind::Veth {
+            attrs: attr.clone(),
+            peer_name: "bar".to_string(),
+            peer_hw_addr: None,
+            peer_ns: None,
+        };
+
+        link_handle
+            .add(
+                &link,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let link = link_handle.get(&attr).unwrap();
+        let peer = link_handle.get(&LinkAttrs::new("bar")).unwrap();
+
+        for (link, name) in [(&link, "foo"), (&peer, "bar")] {
+            assert_eq!(link.attrs().link_type, "veth");
+            assert_eq!(link.attrs().name, name);
+            assert_eq!(link.attrs().mtu, 1400);
+            assert_eq!(link.attrs().tx_queue_len, 100);
+            assert_eq!(link.attrs().num_tx_queues, 4);
+            assert_eq!(link.attrs().num_rx_queues, 8);
+        }
+
+        link_handle.delete(&peer).unwrap();
+        assert!(link_handle.get(&attr).is_err());
+    });
+}
+
+#[test]
+fn test_link_get() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let link = handle.handle_link().get(&LinkAttrs::new("lo")).unwrap();
+
+        assert_eq!(link.attrs().index, 1);
+        assert_eq!(link.attrs().name, "lo");
+    });
+}
+
+#[test]
+fn test_link_list() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let links = link_handle.list().unwrap();
+        assert!(!links.is_empty());
+
+        let lo = links.iter().find(|link| link.attrs().name == "lo").unwrap();
+        assert!(lo.attrs().is_loopback());
+        assert!(!lo.attrs().is_up());
+
+        link_handle.up(lo).unwrap();
+
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        assert!(lo.attrs().is_up());
+        assert!(lo.attrs().is_running());
+        assert_eq!(lo.attrs().oper_state, OperState::Unknown);
+    });
+}
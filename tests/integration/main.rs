@@ -0,0 +1,8 @@
+//! Tests against the kernel, each in a network namespace of its own.
+
+#![cfg(target_os = "linux")]
+
+mod addr;
+mod link;
+mod netns;
+mod route;
@@ -0,0 +1,106 @@
+use std::{
+    ffi::CString,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use nix::sched::{unshare, CloneFlags};
+
+const NETNS_RUN_DIR: &str = "/run/netns";
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs `f` in a fresh network namespace and tears the namespace down
+/// afterwards, even if `f` panics. The namespace is bind mounted to
+/// `/run/netns/rsln-test-<pid>-<n>` while `f` runs, so it can be inspected
+/// with `ip netns exec` when a test hangs.
+///
+/// Network namespaces are per thread, so `f` runs on a thread of its own
+/// and the test thread is left untouched. Returns `None` without running
+/// `f` when not running as root.
+pub fn netns_fixture<F, T>(f: F) -> Option<T>
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    if !nix::unistd::getuid().is_root() {
+        eprintln!("test skipped, requires root");
+        return None;
+    }
+
+    let name = format!(
+        "rsln-test-{}-{}",
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = PathBuf::from(NETNS_RUN_DIR).join(name);
+
+    let res = thread::scope(|s| {
+        s.spawn(|| {
+            unshare(CloneFlags::CLONE_NEWNET).expect("unshare(CLONE_NEWNET)");
+            bind_netns(&path);
+
+            let res = panic::catch_unwind(AssertUnwindSafe(f));
+            unbind_netns(&path);
+            res
+        })
+        .join()
+        .unwrap()
+    });
+
+    match res {
+        Ok(v) => Some(v),
+        Err(e) => panic::resume_unwind(e),
+    }
+}
+
+fn bind_netns(path: &PathBuf) {
+    fs::create_dir_all(NETNS_RUN_DIR).unwrap();
+    fs::File::create(path).unwrap();
+
+    let src = CString::new("/proc/thread-self/ns/net").unwrap();
+    let dst = CString::new(path.to_str().unwrap()).unwrap();
+
+    let ret = unsafe {
+        libc::mount(
+            src.as_ptr(),
+            dst.as_ptr(),
+            std::ptr::null(),
+            libc::MS_BIND,
+            std::ptr::null(),
+        )
+    };
+    assert_eq!(ret, 0, "mount --bind: {}", std::io::Error::last_os_error());
+}
+
+fn unbind_netns(path: &PathBuf) {
+    let dst = CString::new(path.to_str().unwrap()).unwrap();
+
+    unsafe { libc::umount2(dst.as_ptr(), libc::MNT_DETACH) };
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn test_netns_fixture_isolates() {
+    let outer = fs::read_link("/proc/thread-self/ns/net").unwrap();
+
+    let inner = netns_fixture(|| {
+        let inner = fs::read_link("/proc/thread-self/ns/net").unwrap();
+        let mounted = fs::read_dir(NETNS_RUN_DIR).unwrap().any(|e| {
+            e.unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with("rsln-test-")
+        });
+        assert!(mounted);
+        inner
+    });
+
+    if let Some(inner) = inner {
+        assert_ne!(inner, outer);
+        assert_eq!(fs::read_link("/proc/thread-self/ns/net").unwrap(), outer);
+    }
+}
@@ -0,0 +1,109 @@
+use rsln::{
+    handle::sock_handle::SocketHandle,
+    types::{
+        link::LinkAttrs,
+        routing::{Routing, Via},
+    },
+};
+
+use crate::netns::netns_fixture;
+
+#[test]
+fn test_route_add_get_del() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+
+        let route = Routing {
+            oif_index: lo.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let mut route_handle = handle.handle_route();
+        route_handle
+            .handle(
+                &route,
+                libc::RTM_NEWROUTE,
+                libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+            )
+            .unwrap();
+
+        let routes = route_handle.get(&"192.168.0.1".parse().unwrap()).unwrap();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].oif_index, lo.attrs().index);
+
+        route_handle
+            .handle(&route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+            .unwrap();
+        assert!(route_handle.get(&"192.168.0.1".parse().unwrap()).is_err());
+    });
+}
+
+/// Adds `route`, checks a lookup of its destination resolves to it, and
+/// deletes it again.
+fn add_get_del(handle: &mut SocketHandle, route: &Routing) {
+    let mut route_handle = handle.handle_route();
+    let dst = route.dst.unwrap();
+
+    route_handle
+        .handle(
+            route,
+            libc::RTM_NEWROUTE,
+            libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK,
+        )
+        .unwrap();
+
+    let routes = route_handle.get(&dst.addr()).unwrap();
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].oif_index, route.oif_index);
+    assert_eq!(routes[0].dst.unwrap().network(), dst.network());
+
+    route_handle
+        .handle(route, libc::RTM_DELROUTE, libc::NLM_F_ACK)
+        .unwrap();
+    assert!(route_handle.get(&dst.addr()).is_err());
+}
+
+#[test]
+fn test_route_handle() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+
+        let route = Routing {
+            oif_index: lo.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            src: Some("127.0.0.2".parse().unwrap()),
+            ..Default::default()
+        };
+
+        add_get_del(&mut handle, &route);
+    });
+}
+
+#[test]
+fn test_route_handle_via() {
+    netns_fixture(|| {
+        let mut handle = SocketHandle::new(libc::NETLINK_ROUTE);
+        let mut link_handle = handle.handle_link();
+
+        let lo = link_handle.get(&LinkAttrs::new("lo")).unwrap();
+        link_handle.up(&lo).unwrap();
+
+        let route = Routing {
+            oif_index: lo.attrs().index,
+            dst: Some("192.168.0.0/24".parse().unwrap()),
+            via: Some(Via::new("2001::1").unwrap()),
+            ..Default::default()
+        };
+
+        add_get_del(&mut handle, &route);
+    });
+}